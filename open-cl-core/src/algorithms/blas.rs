@@ -0,0 +1,450 @@
+use super::{div_ceil, template_with_defines, AlgorithmError};
+use crate::{
+    Buffer, ClTypeName, Dims, HasDeviceInfo, KernelOperation, Number, NumberTypedT, Output,
+    ReduceOp, Session, Work,
+};
+
+const BLAS_SRC: &'static str = "
+#if IS_DOUBLE
+#pragma OPENCL EXTENSION cl_khr_fp64 : enable
+#endif
+
+__kernel void axpy(const ${T} alpha, __global const ${T} *x, __global ${T} *y) {
+    size_t i = get_global_id(0);
+    y[i] = alpha * x[i] + y[i];
+}
+
+__kernel void multiply(
+    __global const ${T} *a,
+    __global const ${T} *b,
+    __global ${T} *out
+) {
+    size_t i = get_global_id(0);
+    out[i] = a[i] * b[i];
+}
+
+__kernel void gemv(
+    const ulong rows,
+    const ulong cols,
+    const ${T} alpha,
+    __global const ${T} *a,
+    const ulong a_rs,
+    const ulong a_cs,
+    __global const ${T} *x,
+    const ${T} beta,
+    __global ${T} *y
+) {
+    ulong row = get_global_id(0);
+    ${T} sum = 0;
+    for (ulong j = 0; j < cols; j++) {
+        sum += a[row * a_rs + j * a_cs] * x[j];
+    }
+    y[row] = beta == 0 ? alpha * sum : alpha * sum + beta * y[row];
+}
+
+__kernel void gemm(
+    const ulong m,
+    const ulong n,
+    const ulong k,
+    const ${T} alpha,
+    __global const ${T} *a,
+    const ulong a_rs,
+    const ulong a_cs,
+    __global const ${T} *b,
+    const ulong b_rs,
+    const ulong b_cs,
+    const ${T} beta,
+    __global ${T} *c,
+    const ulong c_rs,
+    const ulong c_cs
+) {
+    __local ${T} a_tile[TILE][TILE];
+    __local ${T} b_tile[TILE][TILE];
+    ulong row = get_global_id(0);
+    ulong col = get_global_id(1);
+    size_t lr = get_local_id(0);
+    size_t lc = get_local_id(1);
+    ${T} sum = 0;
+    for (ulong t = 0; t < k; t += TILE) {
+        ulong a_col = t + lc;
+        ulong b_row = t + lr;
+        a_tile[lr][lc] = (row < m && a_col < k) ? a[row * a_rs + a_col * a_cs] : 0;
+        b_tile[lr][lc] = (b_row < k && col < n) ? b[b_row * b_rs + col * b_cs] : 0;
+        barrier(CLK_LOCAL_MEM_FENCE);
+        for (int i = 0; i < TILE; i++) {
+            sum += a_tile[lr][i] * b_tile[i][lc];
+        }
+        barrier(CLK_LOCAL_MEM_FENCE);
+    }
+    if (row < m && col < n) {
+        ulong i = row * c_rs + col * c_cs;
+        c[i] = beta == 0 ? alpha * sum : alpha * sum + beta * c[i];
+    }
+}
+";
+
+/// The largest tile size of `Session::gemm`.
+const MAX_TILE: usize = 32;
+
+/// A Number the BLAS kernels support.
+pub trait BlasNumber: Number + NumberTypedT + ClTypeName {
+    const IS_DOUBLE: bool;
+}
+
+impl BlasNumber for f32 {
+    const IS_DOUBLE: bool = false;
+}
+
+impl BlasNumber for f64 {
+    const IS_DOUBLE: bool = true;
+}
+
+/// The order of the items of a matrix in a Buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    RowMajor,
+    ColumnMajor,
+}
+
+/// A matrix view of a Buffer.
+///
+/// The shape is `Dims::Two(rows, cols)` and the strides are `Dims::Two(row_stride,
+/// col_stride)`: the item at (row, col) is at index `row * row_stride + col *
+/// col_stride` of the Buffer.
+#[derive(Debug, Clone)]
+pub struct Matrix<'a, T: Number> {
+    buffer: &'a Buffer<T>,
+    rows: usize,
+    cols: usize,
+    row_stride: usize,
+    col_stride: usize,
+}
+
+impl<'a, T: Number> Matrix<'a, T> {
+    /// A densely packed matrix of the given shape and layout.
+    pub fn new<D: Into<Dims>>(buffer: &'a Buffer<T>, shape: D, layout: Layout) -> Output<Self> {
+        let (rows, cols) = two_dims(shape.into())?;
+        let strides = match layout {
+            Layout::RowMajor => Dims::Two(cols, 1),
+            Layout::ColumnMajor => Dims::Two(1, rows),
+        };
+        Matrix::with_strides(buffer, Dims::Two(rows, cols), strides)
+    }
+
+    /// A matrix of the given shape and strides (e.g. a row-major matrix with padded rows).
+    pub fn with_strides<S, D>(buffer: &'a Buffer<T>, shape: S, strides: D) -> Output<Self>
+    where
+        S: Into<Dims>,
+        D: Into<Dims>,
+    {
+        let (rows, cols) = two_dims(shape.into())?;
+        let (row_stride, col_stride) = two_dims(strides.into())?;
+        let matrix = Matrix {
+            buffer,
+            rows,
+            cols,
+            row_stride,
+            col_stride,
+        };
+        if rows == 0 || cols == 0 || matrix.index(rows - 1, cols - 1) >= buffer.len() {
+            return Err(AlgorithmError::ShapeMismatch(format!(
+                "a {}x{} matrix with strides ({}, {}) does not fit in a Buffer of length {}",
+                rows,
+                cols,
+                row_stride,
+                col_stride,
+                buffer.len()
+            )))?;
+        }
+        Ok(matrix)
+    }
+
+    /// The transposed matrix. No data is copied.
+    pub fn transpose(&self) -> Matrix<'a, T> {
+        Matrix {
+            buffer: self.buffer,
+            rows: self.cols,
+            cols: self.rows,
+            row_stride: self.col_stride,
+            col_stride: self.row_stride,
+        }
+    }
+
+    pub fn buffer(&self) -> &'a Buffer<T> {
+        self.buffer
+    }
+
+    pub fn shape(&self) -> Dims {
+        Dims::Two(self.rows, self.cols)
+    }
+
+    pub fn strides(&self) -> Dims {
+        Dims::Two(self.row_stride, self.col_stride)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The index of the item at (row, col) in the Buffer.
+    pub fn index(&self, row: usize, col: usize) -> usize {
+        row * self.row_stride + col * self.col_stride
+    }
+}
+
+fn two_dims(dims: Dims) -> Output<(usize, usize)> {
+    match dims {
+        Dims::Two(x, y) => Ok((x, y)),
+        other => Err(AlgorithmError::ShapeMismatch(format!(
+            "expected 2 dimensions, got {:?}",
+            other
+        )))?,
+    }
+}
+
+fn check_len(name: &str, expected: usize, len: usize) -> Output<()> {
+    if expected != len {
+        return Err(AlgorithmError::ShapeMismatch(format!(
+            "{} has length {}, expected {}",
+            name, len, expected
+        )))?;
+    }
+    Ok(())
+}
+
+fn blas_template<T: BlasNumber>(tile: usize) -> crate::KernelTemplate {
+    template_with_defines(
+        &[
+            ("IS_DOUBLE", (T::IS_DOUBLE as u8).to_string()),
+            ("TILE", tile.to_string()),
+        ],
+        BLAS_SRC,
+    )
+}
+
+impl Session {
+    /// `y = alpha * x + y`.
+    pub fn axpy<T: BlasNumber>(&self, alpha: T, x: &Buffer<T>, y: &Buffer<T>) -> Output<()> {
+        check_len("y", x.len(), y.len())?;
+        let template = blas_template::<T>(1);
+        let kernel_op = KernelOperation::new("axpy")
+            .add_arg(&alpha)
+            .add_arg(x)
+            .add_arg(y)
+            .with_work(x.len());
+        self.execute_sync_template_operation::<T>(&template, kernel_op)
+    }
+
+    /// The dot product of x and y.
+    pub fn dot<T: BlasNumber>(&self, x: &Buffer<T>, y: &Buffer<T>) -> Output<T> {
+        check_len("y", x.len(), y.len())?;
+        let template = blas_template::<T>(1);
+        let products = self.create_buffer::<T, usize>(x.len())?;
+        let kernel_op = KernelOperation::new("multiply")
+            .add_arg(x)
+            .add_arg(y)
+            .add_arg(&products)
+            .with_work(x.len());
+        self.execute_sync_template_operation::<T>(&template, kernel_op)?;
+        self.reduce(&products, ReduceOp::Sum)
+    }
+
+    /// `y = alpha * A * x + beta * y`. y is not read when beta is 0.
+    pub fn gemv<T: BlasNumber>(
+        &self,
+        alpha: T,
+        a: &Matrix<T>,
+        x: &Buffer<T>,
+        beta: T,
+        y: &Buffer<T>,
+    ) -> Output<()> {
+        check_len("x", a.cols(), x.len())?;
+        check_len("y", a.rows(), y.len())?;
+        if a.buffer() == y || x == y {
+            return Err(AlgorithmError::AliasedOutput)?;
+        }
+        let template = blas_template::<T>(1);
+        let rows = a.rows() as u64;
+        let cols = a.cols() as u64;
+        let a_rs = a.row_stride as u64;
+        let a_cs = a.col_stride as u64;
+        let kernel_op = KernelOperation::new("gemv")
+            .add_arg(&rows)
+            .add_arg(&cols)
+            .add_arg(&alpha)
+            .add_arg(a.buffer())
+            .add_arg(&a_rs)
+            .add_arg(&a_cs)
+            .add_arg(x)
+            .add_arg(&beta)
+            .add_arg(y)
+            .with_work(a.rows());
+        self.execute_sync_template_operation::<T>(&template, kernel_op)
+    }
+
+    /// `C = alpha * A * B + beta * C` with a tiled kernel. C is not read when beta is 0.
+    ///
+    /// The tile size is the largest power of two (up to 32) whose square fits in the
+    /// device's max work group size and whose A and B tiles fit in its local memory.
+    pub fn gemm<T: BlasNumber>(
+        &self,
+        alpha: T,
+        a: &Matrix<T>,
+        b: &Matrix<T>,
+        beta: T,
+        c: &Matrix<T>,
+    ) -> Output<()> {
+        check_len("B rows", a.cols(), b.rows())?;
+        check_len("C rows", a.rows(), c.rows())?;
+        check_len("C cols", b.cols(), c.cols())?;
+        if a.buffer() == c.buffer() || b.buffer() == c.buffer() {
+            return Err(AlgorithmError::AliasedOutput)?;
+        }
+        let tile = self.gemm_tile_size::<T>()?;
+        let template = blas_template::<T>(tile);
+        let (m, n, k) = (a.rows() as u64, b.cols() as u64, a.cols() as u64);
+        let (a_rs, a_cs) = (a.row_stride as u64, a.col_stride as u64);
+        let (b_rs, b_cs) = (b.row_stride as u64, b.col_stride as u64);
+        let (c_rs, c_cs) = (c.row_stride as u64, c.col_stride as u64);
+        let global = Dims::Two(
+            div_ceil(a.rows(), tile) * tile,
+            div_ceil(b.cols(), tile) * tile,
+        );
+        let kernel_op = KernelOperation::new("gemm")
+            .add_arg(&m)
+            .add_arg(&n)
+            .add_arg(&k)
+            .add_arg(&alpha)
+            .add_arg(a.buffer())
+            .add_arg(&a_rs)
+            .add_arg(&a_cs)
+            .add_arg(b.buffer())
+            .add_arg(&b_rs)
+            .add_arg(&b_cs)
+            .add_arg(&beta)
+            .add_arg(c.buffer())
+            .add_arg(&c_rs)
+            .add_arg(&c_cs)
+            .with_work(Work::new(global).with_local_size(Dims::Two(tile, tile)));
+        self.execute_sync_template_operation::<T>(&template, kernel_op)
+    }
+
+    fn gemm_tile_size<T: BlasNumber>(&self) -> Output<usize> {
+        let device = self.device();
+        let max_work_group_size = device.max_work_group_size()?;
+        let local_mem_size = device.local_mem_size()? as usize;
+        let fits = |tile: usize| {
+            tile <= MAX_TILE
+                && tile * tile <= max_work_group_size
+                && 2 * tile * tile * std::mem::size_of::<T>() <= local_mem_size
+        };
+        let mut tile = 1;
+        while fits(tile * 2) {
+            tile *= 2;
+        }
+        Ok(tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithms::{Layout, Matrix};
+    use crate::testing;
+
+    #[test]
+    fn axpy_and_dot_work() {
+        let session = testing::get_noop_session();
+        let x = session
+            .create_buffer::<f32, &[f32]>(&[1.0, 2.0, 3.0][..])
+            .unwrap();
+        let y = session
+            .create_buffer::<f32, &[f32]>(&[1.0, 1.0, 1.0][..])
+            .unwrap();
+        session.axpy(2.0, &x, &y).unwrap();
+        assert_eq!(testing::read_buffer(&session, &y), vec![3.0, 5.0, 7.0]);
+        assert_eq!(session.dot(&x, &y).unwrap(), 34.0);
+        assert_eq!(session.dot(&x, &x).unwrap(), 14.0);
+        session.axpy(1.0, &x, &x).unwrap();
+        assert_eq!(testing::read_buffer(&session, &x), vec![2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn gemv_works_with_both_layouts() {
+        let session = testing::get_noop_session();
+        // [[1, 2, 3], [4, 5, 6]]
+        let row_major = session
+            .create_buffer::<f64, &[f64]>(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0][..])
+            .unwrap();
+        let col_major = session
+            .create_buffer::<f64, &[f64]>(&[1.0, 4.0, 2.0, 5.0, 3.0, 6.0][..])
+            .unwrap();
+        let x = session
+            .create_buffer::<f64, &[f64]>(&[1.0, 0.0, -1.0][..])
+            .unwrap();
+        for (buffer, layout) in [
+            (&row_major, Layout::RowMajor),
+            (&col_major, Layout::ColumnMajor),
+        ]
+        .iter()
+        {
+            let a = Matrix::new(buffer, (2, 3), *layout).unwrap();
+            let y = session.create_buffer::<f64, usize>(2).unwrap();
+            session.gemv(1.0, &a, &x, 0.0, &y).unwrap();
+            assert_eq!(testing::read_buffer(&session, &y), vec![-2.0, -2.0]);
+        }
+    }
+
+    #[test]
+    fn gemm_matches_a_host_gemm() {
+        let session = testing::get_noop_session();
+        let (m, n, k) = (37, 29, 41);
+        let a_data: Vec<f32> = (0..m * k).map(|i| (i % 13) as f32 - 6.0).collect();
+        let b_data: Vec<f32> = (0..k * n).map(|i| (i % 7) as f32 * 0.5).collect();
+        let a_buffer = session.create_buffer::<f32, &[f32]>(&a_data[..]).unwrap();
+        let b_buffer = session.create_buffer::<f32, &[f32]>(&b_data[..]).unwrap();
+        let c_buffer = session.create_buffer::<f32, usize>(m * n).unwrap();
+        let a = Matrix::new(&a_buffer, (m, k), Layout::RowMajor).unwrap();
+        let b = Matrix::new(&b_buffer, (k, n), Layout::ColumnMajor).unwrap();
+        let c = Matrix::new(&c_buffer, (m, n), Layout::RowMajor).unwrap();
+        session.gemm(1.0, &a, &b, 0.0, &c).unwrap();
+
+        let mut expected = vec![0.0f32; m * n];
+        for row in 0..m {
+            for col in 0..n {
+                expected[row * n + col] = (0..k)
+                    .map(|i| a_data[a.index(row, i)] * b_data[b.index(i, col)])
+                    .sum();
+            }
+        }
+        assert_eq!(testing::read_buffer(&session, &c_buffer), expected);
+    }
+
+    #[test]
+    fn gemm_works_with_a_transposed_view_of_the_same_buffer() {
+        let session = testing::get_noop_session();
+        // [[1, 2], [3, 4]] * [[1, 3], [2, 4]]
+        let a_buffer = session
+            .create_buffer::<f32, &[f32]>(&[1.0, 2.0, 3.0, 4.0][..])
+            .unwrap();
+        let c_buffer = session.create_buffer::<f32, usize>(4).unwrap();
+        let a = Matrix::new(&a_buffer, (2, 2), Layout::RowMajor).unwrap();
+        let c = Matrix::new(&c_buffer, (2, 2), Layout::RowMajor).unwrap();
+        session.gemm(1.0, &a, &a.transpose(), 0.0, &c).unwrap();
+        assert_eq!(
+            testing::read_buffer(&session, &c_buffer),
+            vec![5.0, 11.0, 11.0, 25.0]
+        );
+    }
+
+    #[test]
+    fn matrices_must_fit_in_their_buffer() {
+        let session = testing::get_noop_session();
+        let buffer = session.create_buffer::<f32, usize>(5).unwrap();
+        assert!(Matrix::new(&buffer, (2, 3), Layout::RowMajor).is_err());
+        assert!(Matrix::new(&buffer, (5,), Layout::RowMajor).is_err());
+    }
+}
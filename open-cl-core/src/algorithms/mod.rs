@@ -0,0 +1,89 @@
+//! Device-side algorithms over Buffers.
+//!
+//! The kernels are generated from KernelTemplates for each number type (and device
+//! work group size) and are cached per Session like any other KernelTemplate. See
+//! `Session::build_template`.
+use crate::ll::ErrorT;
+use crate::{HasDeviceInfo, KernelTemplate, Output, Session};
+
+pub mod primitives;
+pub use primitives::{ReduceOp, ScanKind};
+
+pub mod sort;
+pub use sort::{RadixSortKey, SortOrder};
+
+pub mod random;
+pub use random::{fill_random_host, Distribution, RandomNumber};
+
+pub mod blas;
+pub use blas::{BlasNumber, Layout, Matrix};
+
+/// An error related to the algorithms of a Session.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum AlgorithmError {
+    #[error("The algorithm requires a non-empty Buffer")]
+    EmptyBuffer,
+
+    #[error("ReduceOp {0} has no identity value")]
+    NoIdentity(String),
+
+    #[error("Buffer length mismatch (expected {0}, got {1})")]
+    LengthMismatch(usize, usize),
+
+    #[error("Invalid distribution {0}")]
+    InvalidDistribution(String),
+
+    #[error("Shape mismatch: {0}")]
+    ShapeMismatch(String),
+
+    #[error("The output Buffer cannot also be an input")]
+    AliasedOutput,
+
+    #[error("The algorithm requires a work group size of at least {1}, but the device allows {0}")]
+    WorkGroupTooSmall(usize, usize),
+}
+
+/// The largest work group size the algorithms use.
+const MAX_WORK_GROUP_SIZE: usize = 256;
+
+/// The work group size of the algorithm kernels on the Session's device.
+///
+/// This is a power of two no larger than the device's max work group size for which
+/// `work group size * bytes_per_item` bytes fit in the device's local memory.
+pub(crate) fn work_group_size(session: &Session, bytes_per_item: usize) -> Output<usize> {
+    let device = session.device();
+    let max = device.max_work_group_size()?.min(MAX_WORK_GROUP_SIZE);
+    let local_mem_size = device.local_mem_size()? as usize;
+    let mut size = 1;
+    while size * 2 <= max && size * 2 * bytes_per_item <= local_mem_size {
+        size *= 2;
+    }
+    Ok(size)
+}
+
+/// The `#pragma OPENCL EXTENSION` lines kernel source using the OpenCL C types needs:
+/// cl_khr_fp64 for doubles and cl_khr_fp16 for halfs (and vectors of either).
+pub(crate) fn extension_pragmas(cl_type_names: &[&str]) -> String {
+    let mut pragmas = String::new();
+    if cl_type_names.iter().any(|name| name.starts_with("double")) {
+        pragmas.push_str("#pragma OPENCL EXTENSION cl_khr_fp64 : enable\n");
+    }
+    if cl_type_names.iter().any(|name| name.starts_with("half")) {
+        pragmas.push_str("#pragma OPENCL EXTENSION cl_khr_fp16 : enable\n");
+    }
+    pragmas
+}
+
+/// A KernelTemplate of `src` with each of `defines` prepended as a `#define`.
+pub(crate) fn template_with_defines(defines: &[(&str, String)], src: &str) -> KernelTemplate {
+    let mut full_src = String::new();
+    for (name, value) in defines.iter() {
+        full_src.push_str(&format!("#define {} {}\n", name, value));
+    }
+    full_src.push_str(src);
+    KernelTemplate::new(&full_src[..])
+}
+
+pub(crate) fn div_ceil(a: usize, b: usize) -> usize {
+    (a + b - 1) / b
+}
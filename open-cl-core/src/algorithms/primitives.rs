@@ -0,0 +1,463 @@
+use super::{div_ceil, extension_pragmas, template_with_defines, work_group_size, AlgorithmError};
+use crate::{
+    Buffer, ClTypeName, KernelOperation, KernelTemplate, Number, NumberTypedT, Output, Session,
+    Work,
+};
+use std::fmt;
+
+const REDUCE_SRC: &'static str = "
+__kernel void reduce(
+    __global const ${T} *src,
+    __global ${T} *dst,
+    const ulong n,
+    const ulong group_chunk
+) {
+    __local ${T} scratch[WG_SIZE];
+    __local char valid[WG_SIZE];
+    size_t lid = get_local_id(0);
+    ulong group_start = get_group_id(0) * group_chunk;
+    ulong group_end = min(group_start + group_chunk, n);
+    ulong chunk = (group_end - group_start + WG_SIZE - 1) / WG_SIZE;
+    ulong start = group_start + lid * chunk;
+    ulong end = min(start + chunk, group_end);
+    ${T} acc = src[min(start, n - 1)];
+    for (ulong i = start + 1; i < end; i++) {
+        acc = OP(acc, src[i]);
+    }
+    scratch[lid] = acc;
+    valid[lid] = start < end;
+    barrier(CLK_LOCAL_MEM_FENCE);
+    // Each step combines neighbouring runs of the previous step in order, so OP does not
+    // have to be commutative. The valid lanes are a prefix of the work group.
+    for (size_t stride = 1; stride < WG_SIZE; stride <<= 1) {
+        size_t other = lid + stride;
+        if (lid % (2 * stride) == 0 && other < WG_SIZE && valid[other]) {
+            scratch[lid] = OP(scratch[lid], scratch[other]);
+        }
+        barrier(CLK_LOCAL_MEM_FENCE);
+    }
+    if (lid == 0) {
+        dst[get_group_id(0)] = scratch[0];
+    }
+}
+";
+
+const SCAN_SRC: &'static str = "
+__kernel void scan_blocks(
+    __global const ${T} *src,
+    __global ${T} *dst,
+    __global ${T} *block_sums,
+    const ulong n
+) {
+    __local ${T} scratch[WG_SIZE];
+    size_t lid = get_local_id(0);
+    size_t gid = get_global_id(0);
+    scratch[lid] = src[gid < n ? gid : n - 1];
+    barrier(CLK_LOCAL_MEM_FENCE);
+    for (size_t offset = 1; offset < WG_SIZE; offset <<= 1) {
+        ${T} value = scratch[lid];
+        if (lid >= offset) {
+            value = OP(scratch[lid - offset], value);
+        }
+        barrier(CLK_LOCAL_MEM_FENCE);
+        scratch[lid] = value;
+        barrier(CLK_LOCAL_MEM_FENCE);
+    }
+    if (gid < n) {
+        dst[gid] = scratch[lid];
+    }
+    if (lid == WG_SIZE - 1) {
+        block_sums[get_group_id(0)] = scratch[lid];
+    }
+}
+
+__kernel void scan_add(__global ${T} *dst, __global const ${T} *scanned_sums, const ulong n) {
+    size_t gid = get_global_id(0);
+    size_t group = get_group_id(0);
+    if (group > 0 && gid < n) {
+        dst[gid] = OP(scanned_sums[group - 1], dst[gid]);
+    }
+}
+";
+
+const SCAN_SHIFT_SRC: &'static str = "
+__kernel void scan_shift(__global const ${T} *src, __global ${T} *dst) {
+    size_t gid = get_global_id(0);
+    dst[gid] = gid == 0 ? (${T})(IDENTITY) : src[gid - 1];
+}
+";
+
+const MAP_SRC: &'static str = "
+__kernel void map(__global const ${T} *src, __global ${U} *dst) {
+    size_t i = get_global_id(0);
+    ${T} x = src[i];
+    dst[i] = (MAP_EXPR);
+}
+";
+
+/// An associative binary operation of a reduce or a scan.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReduceOp {
+    Sum,
+    Product,
+    Min,
+    Max,
+    /// An OpenCL C expression of `a` and `b` (e.g. `a * b + a`) and optionally the
+    /// identity of the operation (required by exclusive scans). The operation must be
+    /// associative; it does not need to be commutative.
+    Custom {
+        expr: String,
+        identity: Option<String>,
+    },
+}
+
+impl ReduceOp {
+    pub fn custom(expr: &str) -> ReduceOp {
+        ReduceOp::Custom {
+            expr: expr.to_owned(),
+            identity: None,
+        }
+    }
+
+    pub fn custom_with_identity(expr: &str, identity: &str) -> ReduceOp {
+        ReduceOp::Custom {
+            expr: expr.to_owned(),
+            identity: Some(identity.to_owned()),
+        }
+    }
+
+    /// The operation as an OpenCL C expression of `a` and `b`.
+    pub fn cl_expr(&self) -> String {
+        match self {
+            ReduceOp::Sum => "((a) + (b))".to_owned(),
+            ReduceOp::Product => "((a) * (b))".to_owned(),
+            ReduceOp::Min => "min((a), (b))".to_owned(),
+            ReduceOp::Max => "max((a), (b))".to_owned(),
+            ReduceOp::Custom { expr, .. } => format!("({})", expr),
+        }
+    }
+
+    /// The identity of the operation as an OpenCL C expression, if it has one that does
+    /// not depend on the number type.
+    pub fn cl_identity(&self) -> Option<String> {
+        match self {
+            ReduceOp::Sum => Some("0".to_owned()),
+            ReduceOp::Product => Some("1".to_owned()),
+            ReduceOp::Min | ReduceOp::Max => None,
+            ReduceOp::Custom { identity, .. } => identity.clone(),
+        }
+    }
+
+    fn op_define(&self) -> (&'static str, String) {
+        ("OP(a, b)", self.cl_expr())
+    }
+}
+
+impl fmt::Display for ReduceOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReduceOp::Custom { expr, .. } => write!(f, "Custom({})", expr),
+            op => write!(f, "{:?}", op),
+        }
+    }
+}
+
+/// A KernelTemplate of `src` with `defines` that enables the extensions the OpenCL C
+/// types need (e.g. cl_khr_fp64 for doubles).
+fn template_for(cl_type_names: &[&str], defines: &[(&str, String)], src: &str) -> KernelTemplate {
+    let src = format!("{}{}", extension_pragmas(cl_type_names), src);
+    template_with_defines(defines, &src[..])
+}
+
+/// Whether the i-th output of a scan includes the i-th input (inclusive) or only the
+/// inputs before it (exclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScanKind {
+    Inclusive,
+    Exclusive,
+}
+
+impl Session {
+    /// Reduces the Buffer to a single value with the associative operation.
+    pub fn reduce<T: Number + NumberTypedT + ClTypeName>(
+        &self,
+        buffer: &Buffer<T>,
+        op: ReduceOp,
+    ) -> Output<T> {
+        let n = buffer.len();
+        if n == 0 {
+            return Err(AlgorithmError::EmptyBuffer)?;
+        }
+        let wg = work_group_size(self, std::mem::size_of::<T>() + 1)?;
+        let template = template_for(
+            &[T::CL_TYPE_NAME],
+            &[("WG_SIZE", wg.to_string()), op.op_define()],
+            REDUCE_SRC,
+        );
+        let groups = div_ceil(n, wg).min(wg);
+        let group_chunk = div_ceil(n, groups);
+        // Every group must have at least one item.
+        let groups = div_ceil(n, group_chunk);
+
+        let partials = self.create_buffer::<T, usize>(groups)?;
+        let n_arg = n as u64;
+        let group_chunk_arg = group_chunk as u64;
+        let kernel_op = KernelOperation::new("reduce")
+            .add_arg(buffer)
+            .add_arg(&partials)
+            .add_arg(&n_arg)
+            .add_arg(&group_chunk_arg)
+            .with_work(Work::new(groups * wg).with_local_size(wg));
+        self.execute_sync_template_operation::<T>(&template, kernel_op)?;
+
+        let result = self.create_buffer::<T, usize>(1)?;
+        let groups_arg = groups as u64;
+        let kernel_op = KernelOperation::new("reduce")
+            .add_arg(&partials)
+            .add_arg(&result)
+            .add_arg(&groups_arg)
+            .add_arg(&groups_arg)
+            .with_work(Work::new(wg).with_local_size(wg));
+        self.execute_sync_template_operation::<T>(&template, kernel_op)?;
+
+        let output = self.sync_read_buffer(&result, vec![T::zero()], None)?;
+        Ok(output.unwrap()[0])
+    }
+
+    /// Scans (computes the prefix sums of) the Buffer with the associative operation
+    /// into a new Buffer. Exclusive scans require an operation with an identity.
+    pub fn scan<T: Number + NumberTypedT + ClTypeName>(
+        &self,
+        buffer: &Buffer<T>,
+        op: ReduceOp,
+        kind: ScanKind,
+    ) -> Output<Buffer<T>> {
+        let n = buffer.len();
+        if n == 0 {
+            return Err(AlgorithmError::EmptyBuffer)?;
+        }
+        let identity = match (kind, op.cl_identity()) {
+            (ScanKind::Exclusive, None) => {
+                return Err(AlgorithmError::NoIdentity(op.to_string()))?;
+            }
+            (_, identity) => identity,
+        };
+        let wg = work_group_size(self, std::mem::size_of::<T>())?;
+        let inclusive = self.create_buffer::<T, usize>(n)?;
+        self.scan_inclusive_into(buffer, &inclusive, &op, wg)?;
+        match kind {
+            ScanKind::Inclusive => Ok(inclusive),
+            ScanKind::Exclusive => {
+                let template = template_for(
+                    &[T::CL_TYPE_NAME],
+                    &[("IDENTITY", identity.unwrap())],
+                    SCAN_SHIFT_SRC,
+                );
+                let exclusive = self.create_buffer::<T, usize>(n)?;
+                let kernel_op = KernelOperation::new("scan_shift")
+                    .add_arg(&inclusive)
+                    .add_arg(&exclusive)
+                    .with_work(n);
+                self.execute_sync_template_operation::<T>(&template, kernel_op)?;
+                Ok(exclusive)
+            }
+        }
+    }
+
+    fn scan_inclusive_into<T: Number + NumberTypedT + ClTypeName>(
+        &self,
+        src: &Buffer<T>,
+        dst: &Buffer<T>,
+        op: &ReduceOp,
+        wg: usize,
+    ) -> Output<()> {
+        let n = src.len();
+        let groups = div_ceil(n, wg);
+        let template = template_for(
+            &[T::CL_TYPE_NAME],
+            &[("WG_SIZE", wg.to_string()), op.op_define()],
+            SCAN_SRC,
+        );
+        let block_sums = self.create_buffer::<T, usize>(groups)?;
+        let n_arg = n as u64;
+        let kernel_op = KernelOperation::new("scan_blocks")
+            .add_arg(src)
+            .add_arg(dst)
+            .add_arg(&block_sums)
+            .add_arg(&n_arg)
+            .with_work(Work::new(groups * wg).with_local_size(wg));
+        self.execute_sync_template_operation::<T>(&template, kernel_op)?;
+        if groups > 1 {
+            let scanned_sums = self.create_buffer::<T, usize>(groups)?;
+            self.scan_inclusive_into(&block_sums, &scanned_sums, op, wg)?;
+            let kernel_op = KernelOperation::new("scan_add")
+                .add_arg(dst)
+                .add_arg(&scanned_sums)
+                .add_arg(&n_arg)
+                .with_work(Work::new(groups * wg).with_local_size(wg));
+            self.execute_sync_template_operation::<T>(&template, kernel_op)?;
+        }
+        Ok(())
+    }
+
+    /// Maps each item `x` of the Buffer with an OpenCL C expression (e.g. `x * 2 + 1`)
+    /// into a new Buffer of U.
+    pub fn map<T, U>(&self, buffer: &Buffer<T>, expr: &str) -> Output<Buffer<U>>
+    where
+        T: Number + NumberTypedT + ClTypeName,
+        U: Number + NumberTypedT + ClTypeName,
+    {
+        let n = buffer.len();
+        if n == 0 {
+            return Err(AlgorithmError::EmptyBuffer)?;
+        }
+        let template = template_for(
+            &[T::CL_TYPE_NAME, U::CL_TYPE_NAME],
+            &[("MAP_EXPR", format!("({})", expr))],
+            MAP_SRC,
+        );
+        let output = self.create_buffer::<U, usize>(n)?;
+        let kernel_op = KernelOperation::new("map")
+            .add_arg(buffer)
+            .add_arg(&output)
+            .with_work(n);
+        self.execute_sync_template_operation_with(
+            &template,
+            &[("T", T::CL_TYPE_NAME), ("U", U::CL_TYPE_NAME)],
+            kernel_op,
+        )?;
+        Ok(output)
+    }
+
+    /// Counts the items `x` of the Buffer for which the OpenCL C predicate (e.g.
+    /// `x > 0`) is true.
+    pub fn count_if<T: Number + NumberTypedT + ClTypeName>(
+        &self,
+        buffer: &Buffer<T>,
+        predicate: &str,
+    ) -> Output<usize> {
+        let flags = self.map::<T, u64>(buffer, &format!("({}) ? 1 : 0", predicate))?;
+        Ok(self.reduce(&flags, ReduceOp::Sum)? as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{template_for, AlgorithmError, REDUCE_SRC};
+    use crate::{testing, ReduceOp, ScanKind};
+
+    #[test]
+    fn reduce_works() {
+        let session = testing::get_noop_session();
+        let data: Vec<i32> = (1..=10_000).collect();
+        let buffer = session.create_buffer::<i32, &[i32]>(&data[..]).unwrap();
+        assert_eq!(session.reduce(&buffer, ReduceOp::Sum).unwrap(), 50_005_000);
+        assert_eq!(session.reduce(&buffer, ReduceOp::Min).unwrap(), 1);
+        assert_eq!(session.reduce(&buffer, ReduceOp::Max).unwrap(), 10_000);
+
+        let data = vec![3.0f32, -1.5, 8.25, 0.0];
+        let buffer = session.create_buffer::<f32, &[f32]>(&data[..]).unwrap();
+        assert_eq!(session.reduce(&buffer, ReduceOp::Max).unwrap(), 8.25);
+    }
+
+    #[test]
+    fn templates_enable_the_extensions_of_their_types() {
+        let plain = template_for(&["int"], &[], REDUCE_SRC);
+        assert!(!plain.src().contains("#pragma"));
+        let fp64 = template_for(&["double"], &[], REDUCE_SRC);
+        assert!(fp64
+            .src()
+            .contains("#pragma OPENCL EXTENSION cl_khr_fp64 : enable\n"));
+        let fp16 = template_for(&["float", "half"], &[], REDUCE_SRC);
+        assert!(fp16
+            .src()
+            .contains("#pragma OPENCL EXTENSION cl_khr_fp16 : enable\n"));
+    }
+
+    #[test]
+    fn reduce_scan_and_map_work_for_doubles() {
+        let session = testing::get_noop_session();
+        let data: Vec<f64> = (1..=1_000).map(|i| i as f64 * 0.5).collect();
+        let buffer = session.create_buffer::<f64, &[f64]>(&data[..]).unwrap();
+        assert_eq!(session.reduce(&buffer, ReduceOp::Sum).unwrap(), 250_250.0);
+
+        let inclusive = session
+            .scan(&buffer, ReduceOp::Sum, ScanKind::Inclusive)
+            .unwrap();
+        let scanned = testing::read_buffer(&session, &inclusive);
+        assert_eq!(scanned[3], 5.0);
+        assert_eq!(scanned[999], 250_250.0);
+
+        let mapped = session.map::<f64, f64>(&buffer, "x * 2.0").unwrap();
+        assert_eq!(testing::read_buffer(&session, &mapped)[999], 1_000.0);
+        assert_eq!(session.count_if(&buffer, "x > 250.0").unwrap(), 500);
+    }
+
+    #[test]
+    fn reduce_keeps_the_order_of_custom_ops() {
+        let session = testing::get_noop_session();
+        // Keeps the first item: associative, but not commutative.
+        let data: Vec<i32> = (7..1_000).collect();
+        let buffer = session.create_buffer::<i32, &[i32]>(&data[..]).unwrap();
+        let first = session.reduce(&buffer, ReduceOp::custom("a")).unwrap();
+        assert_eq!(first, 7);
+
+        // Keeps the last item of a length that is not a power of two.
+        let data: Vec<i32> = (0..10_007).collect();
+        let buffer = session.create_buffer::<i32, &[i32]>(&data[..]).unwrap();
+        let last = session.reduce(&buffer, ReduceOp::custom("b")).unwrap();
+        assert_eq!(last, 10_006);
+    }
+
+    #[test]
+    fn scan_matches_a_host_scan() {
+        let session = testing::get_noop_session();
+        let data: Vec<i64> = (0..5_000).map(|i| (i % 7) - 3).collect();
+        let buffer = session.create_buffer::<i64, &[i64]>(&data[..]).unwrap();
+
+        let mut expected = Vec::with_capacity(data.len());
+        let mut acc = 0;
+        for x in data.iter() {
+            acc += x;
+            expected.push(acc);
+        }
+        let inclusive = session
+            .scan(&buffer, ReduceOp::Sum, ScanKind::Inclusive)
+            .unwrap();
+        assert_eq!(testing::read_buffer(&session, &inclusive), expected);
+
+        let exclusive = session
+            .scan(&buffer, ReduceOp::Sum, ScanKind::Exclusive)
+            .unwrap();
+        let mut expected_exclusive = vec![0];
+        expected_exclusive.extend_from_slice(&expected[..expected.len() - 1]);
+        assert_eq!(
+            testing::read_buffer(&session, &exclusive),
+            expected_exclusive
+        );
+    }
+
+    #[test]
+    fn exclusive_scan_requires_an_identity() {
+        let session = testing::get_noop_session();
+        let buffer = session.create_buffer::<i32, usize>(4).unwrap();
+        let err = session
+            .scan(&buffer, ReduceOp::Min, ScanKind::Exclusive)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<AlgorithmError>(),
+            Some(&AlgorithmError::NoIdentity("Min".to_string()))
+        );
+    }
+
+    #[test]
+    fn map_and_count_if_work() {
+        let session = testing::get_noop_session();
+        let data: Vec<i32> = (0..100).collect();
+        let buffer = session.create_buffer::<i32, &[i32]>(&data[..]).unwrap();
+        let mapped = session.map::<i32, f32>(&buffer, "(float)x * 0.5f").unwrap();
+        let expected: Vec<f32> = data.iter().map(|x| *x as f32 * 0.5).collect();
+        assert_eq!(testing::read_buffer(&session, &mapped), expected);
+        assert_eq!(session.count_if(&buffer, "x % 3 == 0").unwrap(), 34);
+    }
+}
@@ -0,0 +1,480 @@
+use super::{template_with_defines, AlgorithmError};
+use crate::{Buffer, ClTypeName, KernelOperation, Number, NumberTypedT, Output, Session};
+
+const RANDOM_SRC: &'static str = "
+#pragma OPENCL FP_CONTRACT OFF
+#if IS_DOUBLE
+#pragma OPENCL EXTENSION cl_khr_fp64 : enable
+#define REAL double
+#define TWO_PI 6.283185307179586
+#else
+#define REAL float
+#define TWO_PI 6.2831855f
+#endif
+
+uint4 philox4x32_10(uint4 ctr, uint2 key) {
+    for (int round = 0; round < 10; round++) {
+        uint hi0 = mul_hi(0xD2511F53u, ctr.x);
+        uint lo0 = 0xD2511F53u * ctr.x;
+        uint hi1 = mul_hi(0xCD9E8D57u, ctr.z);
+        uint lo1 = 0xCD9E8D57u * ctr.z;
+        ctr = (uint4)(hi1 ^ ctr.y ^ key.x, lo1, hi0 ^ ctr.w ^ key.y, lo0);
+        key += (uint2)(0x9E3779B9u, 0xBB67AE85u);
+    }
+    return ctr;
+}
+
+uint4 random_bits(ulong i, uint seed_lo, uint seed_hi) {
+    return philox4x32_10((uint4)((uint)i, (uint)(i >> 32), 0, 0), (uint2)(seed_lo, seed_hi));
+}
+
+#if IS_DOUBLE
+REAL unit(uint a, uint b, uint plus) {
+    return (REAL)((((ulong)(a >> 5) << 26) | (b >> 6)) + plus) * 0x1.0p-53;
+}
+#define UNIT(r) unit(r.x, r.y, 0)
+#define UNIT_NONZERO(r) unit(r.x, r.y, 1)
+#define UNIT_2(r) unit(r.z, r.w, 0)
+#else
+#define UNIT(r) ((REAL)(r.x >> 8) * 0x1.0p-24f)
+#define UNIT_NONZERO(r) ((REAL)((r.x >> 8) + 1) * 0x1.0p-24f)
+#define UNIT_2(r) ((REAL)(r.y >> 8) * 0x1.0p-24f)
+#endif
+
+__kernel void random_uniform(
+    __global ${T} *dst,
+    const uint seed_lo,
+    const uint seed_hi,
+    const REAL low,
+    const REAL high
+) {
+    ulong i = get_global_id(0);
+    uint4 r = random_bits(i, seed_lo, seed_hi);
+    dst[i] = (${T})(low + UNIT(r) * (high - low));
+}
+
+__kernel void random_uniform_int(
+    __global ${T} *dst,
+    const uint seed_lo,
+    const uint seed_hi,
+    const long low,
+    const ulong range
+) {
+    ulong i = get_global_id(0);
+    uint4 r = random_bits(i, seed_lo, seed_hi);
+    ulong bits = ((ulong)r.x << 32) | r.y;
+    dst[i] = (${T})(low + (long)(bits % range));
+}
+
+__kernel void random_normal(
+    __global ${T} *dst,
+    const uint seed_lo,
+    const uint seed_hi,
+    const REAL mean,
+    const REAL std_dev
+) {
+    ulong i = get_global_id(0);
+    uint4 r = random_bits(i, seed_lo, seed_hi);
+    REAL z = sqrt((REAL)-2 * log(UNIT_NONZERO(r))) * cos(TWO_PI * UNIT_2(r));
+    dst[i] = CONVERT(mean + std_dev * z);
+}
+
+__kernel void random_bernoulli(
+    __global ${T} *dst,
+    const uint seed_lo,
+    const uint seed_hi,
+    const ulong threshold
+) {
+    ulong i = get_global_id(0);
+    uint4 r = random_bits(i, seed_lo, seed_hi);
+    dst[i] = (ulong)r.x < threshold ? (${T})1 : (${T})0;
+}
+";
+
+/// The distribution of the numbers generated by `Session::fill_random`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// Uniform in `[low, high)`. Integer types use the bounds truncated to integers, which
+    /// must still be a non-empty range.
+    Uniform { low: f64, high: f64 },
+    /// Normal (Gaussian) via the Box-Muller transform. Integer types are rounded to
+    /// the nearest integer.
+    Normal { mean: f64, std_dev: f64 },
+    /// 1 with probability `p` and 0 otherwise.
+    Bernoulli { p: f64 },
+}
+
+impl Distribution {
+    fn check<T: RandomNumber>(&self) -> Output<()> {
+        let valid = match *self {
+            Distribution::Uniform { low, high } if T::IS_INTEGER => (low as i64) < (high as i64),
+            Distribution::Uniform { low, high } => low < high,
+            Distribution::Normal { std_dev, .. } => std_dev >= 0.0,
+            Distribution::Bernoulli { p } => p >= 0.0 && p <= 1.0,
+        };
+        if !valid {
+            return Err(AlgorithmError::InvalidDistribution(format!("{:?}", self)))?;
+        }
+        Ok(())
+    }
+
+    // The first 32 random bits of an item are below the threshold with probability p.
+    fn bernoulli_threshold(p: f64) -> u64 {
+        (p * 4_294_967_296.0) as u64
+    }
+}
+
+/// A Number that `Session::fill_random` can generate.
+pub trait RandomNumber: Number + NumberTypedT + ClTypeName {
+    const IS_INTEGER: bool;
+    const IS_DOUBLE: bool;
+
+    /// The value converted like the device: saturated and rounded to the nearest even
+    /// integer for integer types.
+    fn from_f64(val: f64) -> Self;
+
+    /// The value converted like a C cast (wrapping) for integer types.
+    fn from_i64(val: i64) -> Self;
+}
+
+macro_rules! impl_random_number_for_ints {
+    ($( $t:ty ),*) => {
+        $(
+            impl RandomNumber for $t {
+                const IS_INTEGER: bool = true;
+                const IS_DOUBLE: bool = false;
+
+                fn from_f64(val: f64) -> $t {
+                    round_ties_even(val) as $t
+                }
+
+                fn from_i64(val: i64) -> $t {
+                    val as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_random_number_for_ints!(u8, i8, u16, i16, u32, i32, u64, i64);
+
+impl RandomNumber for f32 {
+    const IS_INTEGER: bool = false;
+    const IS_DOUBLE: bool = false;
+
+    fn from_f64(val: f64) -> f32 {
+        val as f32
+    }
+
+    fn from_i64(val: i64) -> f32 {
+        val as f32
+    }
+}
+
+impl RandomNumber for f64 {
+    const IS_INTEGER: bool = false;
+    const IS_DOUBLE: bool = true;
+
+    fn from_f64(val: f64) -> f64 {
+        val
+    }
+
+    fn from_i64(val: i64) -> f64 {
+        val as f64
+    }
+}
+
+fn round_ties_even(val: f64) -> f64 {
+    let rounded = val.round();
+    if (val - val.trunc()).abs() == 0.5 {
+        2.0 * (val / 2.0).round()
+    } else {
+        rounded
+    }
+}
+
+/// The Philox4x32-10 counter-based generator of Salmon et al., "Parallel Random Numbers:
+/// As Easy as 1, 2, 3". The same function runs on the device.
+pub fn philox4x32_10(mut ctr: [u32; 4], mut key: [u32; 2]) -> [u32; 4] {
+    const M0: u64 = 0xD251_1F53;
+    const M1: u64 = 0xCD9E_8D57;
+    for _ in 0..10 {
+        let p0 = M0 * ctr[0] as u64;
+        let p1 = M1 * ctr[2] as u64;
+        ctr = [
+            ((p1 >> 32) as u32) ^ ctr[1] ^ key[0],
+            p1 as u32,
+            ((p0 >> 32) as u32) ^ ctr[3] ^ key[1],
+            p0 as u32,
+        ];
+        key = [
+            key[0].wrapping_add(0x9E37_79B9),
+            key[1].wrapping_add(0xBB67_AE85),
+        ];
+    }
+    ctr
+}
+
+fn random_bits(i: u64, seed: u64) -> [u32; 4] {
+    philox4x32_10(
+        [i as u32, (i >> 32) as u32, 0, 0],
+        [seed as u32, (seed >> 32) as u32],
+    )
+}
+
+/// The host reference of `Session::fill_random`. Uniform and Bernoulli numbers match
+/// the device exactly; normal numbers match to the accuracy of the device's `log`, `sqrt`
+/// and `cos`.
+pub fn fill_random_host<T: RandomNumber>(
+    dst: &mut [T],
+    distribution: Distribution,
+    seed: u64,
+) -> Output<()> {
+    distribution.check::<T>()?;
+    for (i, item) in dst.iter_mut().enumerate() {
+        let r = random_bits(i as u64, seed);
+        *item = match distribution {
+            Distribution::Uniform { low, high } if T::IS_INTEGER => {
+                let (low, range) = (low as i64, (high as i64).wrapping_sub(low as i64) as u64);
+                let bits = ((r[0] as u64) << 32) | r[1] as u64;
+                T::from_i64(low.wrapping_add((bits % range) as i64))
+            }
+            Distribution::Uniform { low, high } if T::IS_DOUBLE => {
+                T::from_f64(low + unit_f64(r[0], r[1], 0) * (high - low))
+            }
+            Distribution::Uniform { low, high } => {
+                let (low, high) = (low as f32, high as f32);
+                T::from_f64((low + unit_f32(r[0], 0) * (high - low)) as f64)
+            }
+            Distribution::Normal { mean, std_dev } if T::IS_DOUBLE => {
+                let z = (-2.0 * unit_f64(r[0], r[1], 1).ln()).sqrt()
+                    * (std::f64::consts::PI * 2.0 * unit_f64(r[2], r[3], 0)).cos();
+                T::from_f64(mean + std_dev * z)
+            }
+            Distribution::Normal { mean, std_dev } => {
+                let z = (-2.0 * unit_f32(r[0], 1).ln()).sqrt()
+                    * (std::f32::consts::PI * 2.0 * unit_f32(r[1], 0)).cos();
+                T::from_f64((mean as f32 + std_dev as f32 * z) as f64)
+            }
+            Distribution::Bernoulli { p } => {
+                if (r[0] as u64) < Distribution::bernoulli_threshold(p) {
+                    T::one()
+                } else {
+                    T::zero()
+                }
+            }
+        };
+    }
+    Ok(())
+}
+
+fn unit_f32(a: u32, plus: u32) -> f32 {
+    ((a >> 8) + plus) as f32 / 16_777_216.0
+}
+
+fn unit_f64(a: u32, b: u32, plus: u64) -> f64 {
+    let bits = (((a >> 5) as u64) << 26) | (b >> 6) as u64;
+    (bits + plus) as f64 / 9_007_199_254_740_992.0
+}
+
+impl Session {
+    /// Fills the Buffer with random numbers of the distribution.
+    ///
+    /// The numbers come from the counter-based Philox4x32-10 generator: the i-th number
+    /// depends only on the seed and i, so the output is the same on every device and
+    /// for every work size. See `fill_random_host` for the host reference.
+    pub fn fill_random<T: RandomNumber>(
+        &self,
+        buffer: &Buffer<T>,
+        distribution: Distribution,
+        seed: u64,
+    ) -> Output<()> {
+        distribution.check::<T>()?;
+        let n = buffer.len();
+        if n == 0 {
+            return Err(AlgorithmError::EmptyBuffer)?;
+        }
+        let convert = if T::IS_INTEGER {
+            "convert_${T}_sat_rte(x)"
+        } else {
+            "((${T})(x))"
+        };
+        let template = template_with_defines(
+            &[
+                ("IS_DOUBLE", (T::IS_DOUBLE as u8).to_string()),
+                ("CONVERT(x)", convert.to_string()),
+            ],
+            RANDOM_SRC,
+        );
+        let seed_lo = seed as u32;
+        let seed_hi = (seed >> 32) as u32;
+        let kernel_op = |name: &str| {
+            KernelOperation::new(name)
+                .add_arg(buffer)
+                .add_arg(&seed_lo)
+                .add_arg(&seed_hi)
+                .with_work(n)
+        };
+
+        // The REAL args are float or double to match the kernel.
+        let (a, b) = match distribution {
+            Distribution::Uniform { low, high } => (low, high),
+            Distribution::Normal { mean, std_dev } => (mean, std_dev),
+            Distribution::Bernoulli { p } => (p, 0.0),
+        };
+        let (a_f32, b_f32) = (a as f32, b as f32);
+        let int_low = a as i64;
+        let int_range = (b as i64).wrapping_sub(int_low) as u64;
+        let threshold = Distribution::bernoulli_threshold(a);
+
+        let kernel_op = match distribution {
+            Distribution::Uniform { .. } if T::IS_INTEGER => kernel_op("random_uniform_int")
+                .add_arg(&int_low)
+                .add_arg(&int_range),
+            Distribution::Uniform { .. } | Distribution::Normal { .. } => {
+                let name = match distribution {
+                    Distribution::Uniform { .. } => "random_uniform",
+                    _ => "random_normal",
+                };
+                if T::IS_DOUBLE {
+                    kernel_op(name).add_arg(&a).add_arg(&b)
+                } else {
+                    kernel_op(name).add_arg(&a_f32).add_arg(&b_f32)
+                }
+            }
+            Distribution::Bernoulli { .. } => kernel_op("random_bernoulli").add_arg(&threshold),
+        };
+        self.execute_sync_template_operation::<T>(&template, kernel_op)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fill_random_host, philox4x32_10, AlgorithmError, Distribution};
+    use crate::testing;
+
+    #[test]
+    fn philox_matches_the_known_answers() {
+        assert_eq!(
+            philox4x32_10([0; 4], [0; 2]),
+            [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]
+        );
+        assert_eq!(
+            philox4x32_10([0xffff_ffff; 4], [0xffff_ffff; 2]),
+            [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd]
+        );
+        assert_eq!(
+            philox4x32_10(
+                [0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344],
+                [0xa4093822, 0x299f31d0]
+            ),
+            [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1]
+        );
+    }
+
+    #[test]
+    fn host_distributions_have_the_expected_mean() {
+        let n = 100_000;
+        let mut uniform = vec![0.0f64; n];
+        fill_random_host(
+            &mut uniform[..],
+            Distribution::Uniform {
+                low: 2.0,
+                high: 4.0,
+            },
+            7,
+        )
+        .unwrap();
+        assert!(uniform.iter().all(|x| *x >= 2.0 && *x < 4.0));
+        let mean = uniform.iter().sum::<f64>() / n as f64;
+        assert!((mean - 3.0).abs() < 0.01);
+
+        let mut normal = vec![0.0f32; n];
+        let dist = Distribution::Normal {
+            mean: -1.0,
+            std_dev: 2.0,
+        };
+        fill_random_host(&mut normal[..], dist, 7).unwrap();
+        let mean = normal.iter().sum::<f32>() / n as f32;
+        assert!((mean + 1.0).abs() < 0.05);
+
+        let mut coins = vec![0u8; n];
+        fill_random_host(&mut coins[..], Distribution::Bernoulli { p: 0.25 }, 7).unwrap();
+        let heads = coins.iter().filter(|c| **c == 1).count();
+        assert!((heads as f64 / n as f64 - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn invalid_distributions_are_errors() {
+        let mut data = vec![0.0f32; 4];
+        let err =
+            fill_random_host(&mut data[..], Distribution::Bernoulli { p: 1.5 }, 0).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<AlgorithmError>(),
+            Some(&AlgorithmError::InvalidDistribution(
+                "Bernoulli { p: 1.5 }".to_string()
+            ))
+        );
+
+        // The bounds truncate to the empty range [0, 0) for integers.
+        let mut ints = vec![0i32; 4];
+        let dist = Distribution::Uniform {
+            low: 0.2,
+            high: 0.7,
+        };
+        let err = fill_random_host(&mut ints[..], dist, 0).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<AlgorithmError>(),
+            Some(&AlgorithmError::InvalidDistribution(format!("{:?}", dist)))
+        );
+        assert!(fill_random_host(&mut data[..], dist, 0).is_ok());
+    }
+
+    #[test]
+    fn fill_random_matches_the_host_reference() {
+        let session = testing::get_noop_session();
+        let n = 4_096;
+
+        let buffer = session.create_buffer::<f32, usize>(n).unwrap();
+        let dist = Distribution::Uniform {
+            low: -1.0,
+            high: 1.0,
+        };
+        session.fill_random(&buffer, dist, 42).unwrap();
+        let mut expected = vec![0.0f32; n];
+        fill_random_host(&mut expected[..], dist, 42).unwrap();
+        assert_eq!(testing::read_buffer(&session, &buffer), expected);
+
+        let buffer = session.create_buffer::<u32, usize>(n).unwrap();
+        let dist = Distribution::Uniform {
+            low: 10.0,
+            high: 1_000.0,
+        };
+        session.fill_random(&buffer, dist, 42).unwrap();
+        let mut expected = vec![0u32; n];
+        fill_random_host(&mut expected[..], dist, 42).unwrap();
+        assert_eq!(testing::read_buffer(&session, &buffer), expected);
+
+        let buffer = session.create_buffer::<i32, usize>(n).unwrap();
+        let dist = Distribution::Bernoulli { p: 0.5 };
+        session.fill_random(&buffer, dist, 42).unwrap();
+        let mut expected = vec![0i32; n];
+        fill_random_host(&mut expected[..], dist, 42).unwrap();
+        assert_eq!(testing::read_buffer(&session, &buffer), expected);
+
+        let buffer = session.create_buffer::<f32, usize>(n).unwrap();
+        let dist = Distribution::Normal {
+            mean: 0.0,
+            std_dev: 1.0,
+        };
+        session.fill_random(&buffer, dist, 42).unwrap();
+        let mut expected = vec![0.0f32; n];
+        fill_random_host(&mut expected[..], dist, 42).unwrap();
+        for (device, host) in testing::read_buffer(&session, &buffer)
+            .iter()
+            .zip(expected.iter())
+        {
+            assert!((device - host).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use super::{
+    div_ceil, extension_pragmas, template_with_defines, work_group_size, AlgorithmError, ScanKind,
+};
+use crate::ll::numbers::{
+    Char, Double, Float, Half, Int, Long, Short, SizeT, Uchar, Uint, Ulong, Ushort,
+};
+use crate::{
+    Buffer, ClTypeName, KernelOperation, Number, NumberTypedT, Output, ReduceOp, Session, Work,
+};
+
+/// The number of bits of a key sorted by each pass of the radix sort.
+const RADIX_BITS: u32 = 4;
+const RADIX: usize = 1 << RADIX_BITS;
+
+const RADIX_SORT_SRC: &'static str = "
+#define RADIX 16
+#define DIGIT(x, shift) ((uint)((ORDER_KEY(x) >> (shift)) & (RADIX - 1)))
+
+__kernel void radix_histogram(
+    __global const ${K} *keys,
+    __global uint *hist,
+    const ulong n,
+    const uint shift
+) {
+    __local uint counts[RADIX];
+    size_t lid = get_local_id(0);
+    size_t gid = get_global_id(0);
+    if (lid < RADIX) {
+        counts[lid] = 0;
+    }
+    barrier(CLK_LOCAL_MEM_FENCE);
+    if (gid < n) {
+        atomic_inc(&counts[DIGIT(keys[gid], shift)]);
+    }
+    barrier(CLK_LOCAL_MEM_FENCE);
+    if (lid < RADIX) {
+        hist[lid * get_num_groups(0) + get_group_id(0)] = counts[lid];
+    }
+}
+
+__kernel void radix_scatter(
+    __global const ${K} *keys,
+    __global ${K} *keys_out,
+#if HAS_VALUES
+    __global const ${V} *values,
+    __global ${V} *values_out,
+#endif
+    __global const uint *offsets,
+    const ulong n,
+    const uint shift
+) {
+    __local uint ranks[WG_SIZE];
+    size_t lid = get_local_id(0);
+    size_t gid = get_global_id(0);
+    uint digit = RADIX;
+    if (gid < n) {
+        digit = DIGIT(keys[gid], shift);
+    }
+    // The rank among the items of the group with the same digit keeps the sort stable.
+    // It is found with an inclusive scan of the group's flags for each digit.
+    uint rank = 0;
+    for (uint d = 0; d < RADIX; d++) {
+        uint flag = digit == d ? 1 : 0;
+        ranks[lid] = flag;
+        barrier(CLK_LOCAL_MEM_FENCE);
+        for (size_t stride = 1; stride < WG_SIZE; stride <<= 1) {
+            uint before = lid >= stride ? ranks[lid - stride] : 0;
+            barrier(CLK_LOCAL_MEM_FENCE);
+            ranks[lid] += before;
+            barrier(CLK_LOCAL_MEM_FENCE);
+        }
+        if (flag) {
+            rank = ranks[lid] - 1;
+        }
+        barrier(CLK_LOCAL_MEM_FENCE);
+    }
+    if (gid < n) {
+        uint dst = offsets[digit * get_num_groups(0) + get_group_id(0)] + rank;
+        keys_out[dst] = keys[gid];
+#if HAS_VALUES
+        values_out[dst] = values[gid];
+#endif
+    }
+}
+";
+
+/// The order of a sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// A Number that can be sorted with a radix sort.
+///
+/// The key of a number is an unsigned integer of the same size whose order matches the
+/// order of the numbers; signed integers flip their sign bit and floats flip their sign
+/// bit (positive) or all of their bits (negative).
+pub trait RadixSortKey: Number + NumberTypedT + ClTypeName {
+    /// The unsigned OpenCL C type of the key.
+    const CL_KEY_TYPE: &'static str;
+
+    /// The key of `x` as an OpenCL C expression.
+    const CL_KEY_EXPR: &'static str;
+
+    const KEY_BITS: u32;
+}
+
+macro_rules! impl_radix_sort_key {
+    ($key_type:expr, $bits:expr, $expr:expr, [$( $t:ty ),*]) => {
+        $(
+            impl RadixSortKey for $t {
+                const CL_KEY_TYPE: &'static str = $key_type;
+                const CL_KEY_EXPR: &'static str = $expr;
+                const KEY_BITS: u32 = $bits;
+            }
+        )*
+    };
+}
+
+impl_radix_sort_key!("uchar", 8, "((uchar)(x))", [u8, Uchar]);
+impl_radix_sort_key!("ushort", 16, "((ushort)(x))", [u16, Ushort]);
+impl_radix_sort_key!("uint", 32, "((uint)(x))", [u32, Uint]);
+impl_radix_sort_key!("ulong", 64, "((ulong)(x))", [u64, Ulong]);
+// size_t may be 32 bits on the device; the upper digits of its key are then 0.
+impl_radix_sort_key!("ulong", 64, "((ulong)(x))", [usize, SizeT]);
+impl_radix_sort_key!("uchar", 8, "((uchar)(x) ^ (uchar)0x80)", [i8, Char]);
+impl_radix_sort_key!("ushort", 16, "((ushort)(x) ^ (ushort)0x8000)", [i16, Short]);
+impl_radix_sort_key!("uint", 32, "((uint)(x) ^ 0x80000000u)", [i32, Int]);
+impl_radix_sort_key!(
+    "ulong",
+    64,
+    "((ulong)(x) ^ 0x8000000000000000ul)",
+    [i64, Long]
+);
+impl_radix_sort_key!(
+    "ulong",
+    64,
+    "((ulong)(long)(x) ^ 0x8000000000000000ul)",
+    [isize]
+);
+impl_radix_sort_key!(
+    "ushort",
+    16,
+    "(as_ushort(x) ^ ((as_ushort(x) >> 15) ? (ushort)0xFFFF : (ushort)0x8000))",
+    [Half]
+);
+impl_radix_sort_key!(
+    "uint",
+    32,
+    "(as_uint(x) ^ ((as_uint(x) >> 31) ? 0xFFFFFFFFu : 0x80000000u))",
+    [f32, Float]
+);
+impl_radix_sort_key!(
+    "ulong",
+    64,
+    "(as_ulong(x) ^ ((as_ulong(x) >> 63) ? 0xFFFFFFFFFFFFFFFFul : 0x8000000000000000ul))",
+    [f64, Double]
+);
+
+impl Session {
+    /// Sorts the Buffer in place with a stable radix sort.
+    pub fn sort<T: RadixSortKey>(&self, buffer: &Buffer<T>, order: SortOrder) -> Output<()> {
+        self.radix_sort::<T, T>(buffer, None, order)
+    }
+
+    /// Sorts the keys in place with a stable radix sort and moves the values along with
+    /// their keys.
+    pub fn sort_by_key<K, V>(
+        &self,
+        keys: &Buffer<K>,
+        values: &Buffer<V>,
+        order: SortOrder,
+    ) -> Output<()>
+    where
+        K: RadixSortKey,
+        V: Number + NumberTypedT + ClTypeName,
+    {
+        if keys.len() != values.len() {
+            return Err(AlgorithmError::LengthMismatch(keys.len(), values.len()))?;
+        }
+        self.radix_sort(keys, Some(values), order)
+    }
+
+    fn radix_sort<K, V>(
+        &self,
+        keys: &Buffer<K>,
+        values: Option<&Buffer<V>>,
+        order: SortOrder,
+    ) -> Output<()>
+    where
+        K: RadixSortKey,
+        V: Number + NumberTypedT + ClTypeName,
+    {
+        let n = keys.len();
+        if n == 0 {
+            return Err(AlgorithmError::EmptyBuffer)?;
+        }
+        // Each item of a group takes a uint of local memory to rank its digit.
+        let wg = work_group_size(self, std::mem::size_of::<u32>())?;
+        if wg < RADIX {
+            return Err(AlgorithmError::WorkGroupTooSmall(wg, RADIX))?;
+        }
+        let groups = div_ceil(n, wg);
+        let order_key = match order {
+            SortOrder::Ascending => "((KEY_T)(KEY(x)))",
+            SortOrder::Descending => "((KEY_T)(~(KEY(x))))",
+        };
+        let has_values = if values.is_some() { "1" } else { "0" };
+        let src = format!(
+            "{}{}",
+            extension_pragmas(&[K::CL_TYPE_NAME, V::CL_TYPE_NAME]),
+            RADIX_SORT_SRC
+        );
+        let template = template_with_defines(
+            &[
+                ("WG_SIZE", wg.to_string()),
+                ("KEY_T", K::CL_KEY_TYPE.to_string()),
+                ("KEY(x)", K::CL_KEY_EXPR.to_string()),
+                ("ORDER_KEY(x)", order_key.to_string()),
+                ("HAS_VALUES", has_values.to_string()),
+            ],
+            &src[..],
+        );
+        let bindings = [("K", K::CL_TYPE_NAME), ("V", V::CL_TYPE_NAME)];
+
+        let hist = self.create_buffer::<u32, usize>(RADIX * groups)?;
+        let tmp_keys = self.create_buffer::<K, usize>(n)?;
+        let tmp_values = match values {
+            Some(_) => Some(self.create_buffer::<V, usize>(n)?),
+            None => None,
+        };
+        let n_arg = n as u64;
+        // KEY_BITS is a multiple of 2 * RADIX_BITS so the sorted data ends in the input.
+        for pass in 0..(K::KEY_BITS / RADIX_BITS) {
+            let shift = pass * RADIX_BITS;
+            let (keys_in, keys_out, values_in, values_out) = if pass % 2 == 0 {
+                (keys, &tmp_keys, values, tmp_values.as_ref())
+            } else {
+                (&tmp_keys, keys, tmp_values.as_ref(), values)
+            };
+
+            let kernel_op = KernelOperation::new("radix_histogram")
+                .add_arg(keys_in)
+                .add_arg(&hist)
+                .add_arg(&n_arg)
+                .add_arg(&shift)
+                .with_work(Work::new(groups * wg).with_local_size(wg));
+            self.execute_sync_template_operation_with(&template, &bindings[..], kernel_op)?;
+
+            let offsets = self.scan(&hist, ReduceOp::Sum, ScanKind::Exclusive)?;
+            let mut kernel_op = KernelOperation::new("radix_scatter")
+                .add_arg(keys_in)
+                .add_arg(keys_out);
+            if let (Some(values_in), Some(values_out)) = (values_in, values_out) {
+                kernel_op = kernel_op.add_arg(values_in).add_arg(values_out);
+            }
+            let kernel_op = kernel_op
+                .add_arg(&offsets)
+                .add_arg(&n_arg)
+                .add_arg(&shift)
+                .with_work(Work::new(groups * wg).with_local_size(wg));
+            self.execute_sync_template_operation_with(&template, &bindings[..], kernel_op)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing, SortOrder};
+
+    // A small LCG so the tests do not need a rand dependency.
+    fn pseudo_random(n: usize) -> Vec<u32> {
+        let mut state = 12345u32;
+        (0..n)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                state
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sort_matches_a_host_sort() {
+        let session = testing::get_noop_session();
+        let data = pseudo_random(10_000);
+        let buffer = session.create_buffer::<u32, &[u32]>(&data[..]).unwrap();
+        session.sort(&buffer, SortOrder::Ascending).unwrap();
+        let mut expected = data.clone();
+        expected.sort();
+        assert_eq!(testing::read_buffer(&session, &buffer), expected);
+
+        let data: Vec<i32> = data.iter().map(|x| *x as i32).collect();
+        let buffer = session.create_buffer::<i32, &[i32]>(&data[..]).unwrap();
+        session.sort(&buffer, SortOrder::Descending).unwrap();
+        let mut expected = data.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(testing::read_buffer(&session, &buffer), expected);
+    }
+
+    #[test]
+    fn sort_orders_floats_correctly() {
+        let session = testing::get_noop_session();
+        let data = vec![3.5f32, -0.5, 0.0, -100.25, 7.0, -2.0, 1.0e-3];
+        let buffer = session.create_buffer::<f32, &[f32]>(&data[..]).unwrap();
+        session.sort(&buffer, SortOrder::Ascending).unwrap();
+        let mut expected = data.clone();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(testing::read_buffer(&session, &buffer), expected);
+    }
+
+    #[test]
+    fn sort_works_for_pointer_sized_integers() {
+        let session = testing::get_noop_session();
+        let data: Vec<isize> = pseudo_random(1_000)
+            .iter()
+            .map(|x| *x as i32 as isize)
+            .collect();
+        let buffer = session.create_buffer::<isize, &[isize]>(&data[..]).unwrap();
+        session.sort(&buffer, SortOrder::Ascending).unwrap();
+        let mut expected = data.clone();
+        expected.sort();
+        assert_eq!(testing::read_buffer(&session, &buffer), expected);
+    }
+
+    #[test]
+    fn sort_by_key_is_stable() {
+        let session = testing::get_noop_session();
+        let keys: Vec<u8> = pseudo_random(2_000)
+            .iter()
+            .map(|x| (x >> 24) as u8 % 10)
+            .collect();
+        let values: Vec<u32> = (0..2_000).collect();
+        let key_buffer = session.create_buffer::<u8, &[u8]>(&keys[..]).unwrap();
+        let value_buffer = session.create_buffer::<u32, &[u32]>(&values[..]).unwrap();
+        session
+            .sort_by_key(&key_buffer, &value_buffer, SortOrder::Ascending)
+            .unwrap();
+
+        let mut expected: Vec<(u8, u32)> = keys.iter().cloned().zip(values).collect();
+        expected.sort_by_key(|(k, _)| *k);
+        let (expected_keys, expected_values): (Vec<u8>, Vec<u32>) = expected.into_iter().unzip();
+        assert_eq!(testing::read_buffer(&session, &key_buffer), expected_keys);
+        assert_eq!(
+            testing::read_buffer(&session, &value_buffer),
+            expected_values
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 use std::fmt::Debug;
+use std::marker::PhantomData;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use crate::ll::{Context as ClContext, Mem as ClMem, MemPtr};
@@ -8,63 +9,64 @@ use crate::{
     Number, NumberType, NumberTyped, NumberTypedT, Output,
 };
 
-pub struct Buffer {
-    _t: NumberType,
-    _mem: Arc<RwLock<ClMem>>,
-    _context: Context,
+/// A device memory buffer of T.
+///
+/// The number type of a Buffer is checked at compile time. A Buffer can be erased into
+/// an UntypedBuffer to store buffers of different types together.
+pub struct Buffer<T: Number> {
+    _inner: UntypedBuffer,
+    _phantom: PhantomData<T>,
 }
 
-impl NumberTyped for Buffer {
+impl<T: Number + NumberTypedT> NumberTyped for Buffer<T> {
     fn number_type(&self) -> NumberType {
-        self._t
+        T::number_type()
     }
 }
 
-unsafe impl Send for Buffer {}
-unsafe impl Sync for Buffer {}
+unsafe impl<T: Number> Send for Buffer<T> {}
+unsafe impl<T: Number> Sync for Buffer<T> {}
 
-impl Clone for Buffer {
-    fn clone(&self) -> Buffer {
+impl<T: Number> Clone for Buffer<T> {
+    fn clone(&self) -> Buffer<T> {
         Buffer {
-            _t: self._t,
-            _mem: self._mem.clone(),
-            _context: self._context.clone(),
+            _inner: self._inner.clone(),
+            _phantom: PhantomData,
         }
     }
 }
 
-impl Debug for Buffer {
+impl<T: Number> Debug for Buffer<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Buffer{{{:?}}}", self._mem)
+        write!(f, "Buffer{{{:?}}}", self._inner._mem)
     }
 }
 
-impl PartialEq for Buffer {
+impl<T: Number> PartialEq for Buffer<T> {
     fn eq(&self, other: &Self) -> bool {
-        unsafe {
-            let left = self._mem.read().unwrap().mem_ptr().as_ptr();
-            let right = other._mem.read().unwrap().mem_ptr().as_ptr();
-            std::ptr::eq(left, right)
-        }
+        self._inner == other._inner
     }
 }
 
-impl Buffer {
-    pub fn new(ll_mem: ClMem, context: Context) -> Buffer {
-        Buffer {
-            _t: ll_mem.number_type(),
-            _mem: Arc::new(RwLock::new(ll_mem)),
-            _context: context,
-        }
+impl<T: Number> AsRef<UntypedBuffer> for Buffer<T> {
+    fn as_ref(&self) -> &UntypedBuffer {
+        &self._inner
+    }
+}
+
+impl<T: Number + NumberTypedT> Buffer<T> {
+    /// Wraps the ClMem in a Buffer. Errors if the ClMem is not a mem of T.
+    pub fn new(ll_mem: ClMem, context: Context) -> Output<Buffer<T>> {
+        UntypedBuffer::new(ll_mem, context).downcast()
     }
 
-    pub fn create<T: Number + NumberTypedT, B: BufferBuilder>(
+    pub fn create<B: BufferBuilder>(
         context: &Context,
         creator: B,
         host_access: HostAccess,
         kernel_access: KernelAccess,
         mem_allocation: MemAllocation,
-    ) -> Output<Buffer> {
+    ) -> Output<Buffer<T>> {
         let ll_mem = ClMem::create::<T, B>(
             context.low_level_context(),
             creator,
@@ -72,31 +74,23 @@ impl Buffer {
             kernel_access,
             mem_allocation,
         )?;
-        Ok(Buffer::new(ll_mem, context.clone()))
+        Ok(Buffer::from_untyped(UntypedBuffer::new(
+            ll_mem,
+            context.clone(),
+        )))
     }
 
-    // pub fn create_with_len<T: Number>(context: &Context, len: usize) -> Output<Buffer> {
-    //     Buffer::create_from::<T, usize>(context, len)
-    // }
-
-    // pub fn create_from_slice<T: Number>(context: &Context, data: &[T]) -> Output<Buffer> {
-    //     Buffer::create_from::<T, &[T]>(context, data)
-    // }
-
-    pub fn create_from<T: Number, B: BufferBuilder>(
-        context: &Context,
-        creator: B,
-    ) -> Output<Buffer> {
+    pub fn create_from<B: BufferBuilder>(context: &Context, creator: B) -> Output<Buffer<T>> {
         let mem_config = { creator.mem_config() };
-        Buffer::create_with_config::<T, B>(context, creator, mem_config)
+        Buffer::create_with_config::<B>(context, creator, mem_config)
     }
 
-    pub fn create_with_config<T: Number, B: BufferBuilder>(
+    pub fn create_with_config<B: BufferBuilder>(
         context: &Context,
         creator: B,
         mem_config: MemConfig,
-    ) -> Output<Buffer> {
-        Buffer::create::<T, B>(
+    ) -> Output<Buffer<T>> {
+        Buffer::create::<B>(
             context,
             creator,
             mem_config.host_access(),
@@ -105,13 +99,13 @@ impl Buffer {
         )
     }
 
-    pub fn create_from_low_level_context<T: Number, B: BufferBuilder>(
+    pub fn create_from_low_level_context<B: BufferBuilder>(
         ll_context: &ClContext,
         creator: B,
         host_access: HostAccess,
         kernel_access: KernelAccess,
         mem_allocation: MemAllocation,
-    ) -> Output<Buffer> {
+    ) -> Output<Buffer<T>> {
         let ll_mem = ClMem::create::<T, B>(
             ll_context,
             creator,
@@ -120,7 +114,138 @@ impl Buffer {
             mem_allocation,
         )?;
         let context = Context::from_low_level_context(ll_context)?;
-        Ok(Buffer::new(ll_mem, context))
+        Ok(Buffer::from_untyped(UntypedBuffer::new(ll_mem, context)))
+    }
+}
+
+impl<T: Number> Buffer<T> {
+    // The caller must know that the UntypedBuffer holds T.
+    fn from_untyped(inner: UntypedBuffer) -> Buffer<T> {
+        Buffer {
+            _inner: inner,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Erases the number type of the Buffer.
+    pub fn erase(self) -> UntypedBuffer {
+        self._inner
+    }
+
+    /// The Buffer as an UntypedBuffer without consuming the Buffer.
+    pub fn as_untyped(&self) -> &UntypedBuffer {
+        &self._inner
+    }
+
+    pub fn read_lock(&self) -> RwLockReadGuard<ClMem> {
+        self._inner.read_lock()
+    }
+
+    pub fn write_lock(&self) -> RwLockWriteGuard<ClMem> {
+        self._inner.write_lock()
+    }
+
+    pub fn context(&self) -> &Context {
+        self._inner.context()
+    }
+
+    pub fn reference_count(&self) -> Output<u32> {
+        self._inner.reference_count()
+    }
+
+    pub fn size(&self) -> Output<usize> {
+        self._inner.size()
+    }
+
+    /// A non-panicking version of len.
+    pub fn length(&self) -> Output<usize> {
+        self._inner.length()
+    }
+
+    /// A method for getting the len of the device memory buffer.
+    /// Panics if the buffer size info returns an error.
+    pub fn len(&self) -> usize {
+        self._inner.len()
+    }
+
+    pub fn offset(&self) -> Output<usize> {
+        self._inner.offset()
+    }
+
+    pub fn flags(&self) -> Output<MemFlags> {
+        self._inner.flags()
+    }
+
+    pub fn mem_config(&self) -> MemConfig {
+        self._inner.mem_config()
+    }
+}
+
+/// A device memory buffer whose number type is only known at runtime.
+///
+/// An UntypedBuffer is made with `Buffer::erase` and turned back into a Buffer<T> with
+/// `UntypedBuffer::downcast`.
+pub struct UntypedBuffer {
+    _t: NumberType,
+    _mem: Arc<RwLock<ClMem>>,
+    _context: Context,
+}
+
+impl NumberTyped for UntypedBuffer {
+    fn number_type(&self) -> NumberType {
+        self._t
+    }
+}
+
+unsafe impl Send for UntypedBuffer {}
+unsafe impl Sync for UntypedBuffer {}
+
+impl Clone for UntypedBuffer {
+    fn clone(&self) -> UntypedBuffer {
+        UntypedBuffer {
+            _t: self._t,
+            _mem: self._mem.clone(),
+            _context: self._context.clone(),
+        }
+    }
+}
+
+impl Debug for UntypedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "UntypedBuffer{{{:?}}}", self._mem)
+    }
+}
+
+impl PartialEq for UntypedBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        unsafe {
+            let left = self._mem.read().unwrap().mem_ptr().as_ptr();
+            let right = other._mem.read().unwrap().mem_ptr().as_ptr();
+            std::ptr::eq(left, right)
+        }
+    }
+}
+
+impl AsRef<UntypedBuffer> for UntypedBuffer {
+    fn as_ref(&self) -> &UntypedBuffer {
+        self
+    }
+}
+
+impl UntypedBuffer {
+    pub fn new(ll_mem: ClMem, context: Context) -> UntypedBuffer {
+        UntypedBuffer {
+            _t: ll_mem.number_type(),
+            _mem: Arc::new(RwLock::new(ll_mem)),
+            _context: context,
+        }
+    }
+
+    /// Turns the UntypedBuffer back into a Buffer<T>. Errors if the number type of the
+    /// UntypedBuffer is not T.
+    pub fn downcast<T: Number + NumberTypedT>(self) -> Output<Buffer<T>> {
+        self._t.type_check(&T::number_type())?;
+        Ok(Buffer::from_untyped(self))
     }
 
     pub fn read_lock(&self) -> RwLockReadGuard<ClMem> {
@@ -131,6 +256,11 @@ impl Buffer {
         self._mem.write().unwrap()
     }
 
+    /// Identifies the lock of the buffer. Clones of an UntypedBuffer share one lock.
+    pub(crate) fn lock_id(&self) -> usize {
+        Arc::as_ptr(&self._mem) as usize
+    }
+
     pub fn context(&self) -> &Context {
         &self._context
     }
@@ -146,7 +276,6 @@ impl Buffer {
     /// A non-panicking version of len.
     pub fn length(&self) -> Output<usize> {
         unsafe { self.read_lock().len() }
-        // Ok(size / std::mem::size_of::<T>())
     }
 
     /// A method for getting the len of the device memory buffer.
@@ -177,14 +306,14 @@ mod tests {
     fn buffer_can_be_created_with_a_length() {
         let context = testing::get_context();
         let mem_config = MemConfig::for_size();
-        let _buffer = Buffer::create_with_config::<u32, usize>(&context, 10, mem_config).unwrap();
+        let _buffer = Buffer::<u32>::create_with_config(&context, 10, mem_config).unwrap();
     }
 
     #[test]
     fn buffer_can_be_created_with_a_slice_of_data() {
         let context = testing::get_context();
         let data = vec![0i32, 1, 2, 3, 4, 5, 6, 7, 8, 9];
-        let _buffer = Buffer::create::<i32, &[i32]>(
+        let _buffer = Buffer::<i32>::create(
             &context,
             &data[..],
             HostAccess::NoAccess,
@@ -230,6 +359,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn buffer_can_be_erased_and_downcast() {
+        let buffer = testing::get_buffer::<u32>(10);
+        let untyped: UntypedBuffer = buffer.clone().erase();
+        assert_eq!(untyped.number_type(), NumberType::new::<u32>());
+        let downcast = untyped.downcast::<u32>().unwrap();
+        assert_eq!(downcast, buffer);
+    }
+
+    #[test]
+    fn untyped_buffer_cannot_be_downcast_to_another_type() {
+        let untyped = testing::get_buffer::<u32>(10).erase();
+        let err = untyped.downcast::<f32>().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<NumberTypeError>(),
+            Some(&NumberTypeError::Mismatch(
+                NumberType::new::<u32>(),
+                NumberType::new::<f32>()
+            ))
+        );
+    }
+
     #[test]
     fn buffer_offset_works() {
         let buffer = testing::get_buffer::<u32>(10);
@@ -2,9 +2,10 @@ use std::fmt;
 use std::iter::Iterator;
 use std::mem::ManuallyDrop;
 
-use crate::ll::cl::ContextProperties;
 use crate::ll::vec_or_slice::VecOrSlice;
-use crate::ll::{Context as ClContext, ContextPtr, Device as ClDeviceID};
+use crate::ll::{
+    Context as ClContext, ContextErrorCallback, ContextProperty, ContextPtr, Device as ClDeviceID,
+};
 
 use crate::{Device, Output};
 
@@ -46,11 +47,31 @@ impl Context {
     }
 
     pub fn create<'a, D: Into<VecOrSlice<'a, Device>>>(devices: D) -> Output<Context> {
+        Context::create_with_error_callback(devices, ContextErrorCallback::default())
+    }
+
+    /// Creates a context that calls the error callback when the driver reports an error
+    /// in the context. `Context::create` forwards the errors to the `log` crate.
+    pub fn create_with_error_callback<'a, D: Into<VecOrSlice<'a, Device>>>(
+        devices: D,
+        error_callback: ContextErrorCallback,
+    ) -> Output<Context> {
+        Context::create_with_properties(devices, &[], error_callback)
+    }
+
+    /// Creates a context with the given properties, e.g. `ContextProperty::Platform`.
+    /// Each property may be given at most once.
+    pub fn create_with_properties<'a, D: Into<VecOrSlice<'a, Device>>>(
+        devices: D,
+        properties: &[ContextProperty],
+        error_callback: ContextErrorCallback,
+    ) -> Output<Context> {
         let devices: Vec<Device> = devices.into().to_vec();
         // let device_ptrs: Vec<cl_device_id> =
         //     devices.iter().map(|d| unsafe { d.device_ptr() }).collect();
 
-        let ll_context: ClContext = unsafe { ClContext::create(&devices[..]) }?;
+        let ll_context: ClContext =
+            unsafe { ClContext::create_with_properties(&devices[..], properties, error_callback) }?;
         Ok(Context::build(ll_context, devices))
     }
 
@@ -62,7 +83,7 @@ impl Context {
         unsafe { self.inner.reference_count() }
     }
 
-    pub fn properties(&self) -> Output<Vec<ContextProperties>> {
+    pub fn properties(&self) -> Output<Vec<ContextProperty>> {
         unsafe { self.inner.properties() }
     }
 
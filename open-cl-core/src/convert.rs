@@ -0,0 +1,224 @@
+use crate::algorithms::extension_pragmas;
+use crate::{
+    Buffer, ClTypeName, KernelOperation, KernelTemplate, Number, NumberTypedT, Output, Session,
+};
+use thiserror::Error as ErrorT;
+
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum ConvertError {
+    #[error("Cannot convert {0} ({1} components) to {2} ({3} components)")]
+    ComponentCountMismatch(&'static str, usize, &'static str, usize),
+}
+
+/// The rounding mode of a device-side conversion. These are the `_rte`, `_rtz`, `_rtp`
+/// and `_rtn` suffixes of OpenCL C's `convert_<type>` functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// OpenCL C's default: toward zero for conversions to integers and to nearest even
+    /// for conversions to floats.
+    Default,
+    ToNearestEven,
+    TowardZero,
+    TowardPositiveInfinity,
+    TowardNegativeInfinity,
+}
+
+impl Rounding {
+    /// The suffix of the rounding mode in OpenCL C (e.g. `_rte`).
+    pub fn cl_suffix(&self) -> &'static str {
+        match self {
+            Rounding::Default => "",
+            Rounding::ToNearestEven => "_rte",
+            Rounding::TowardZero => "_rtz",
+            Rounding::TowardPositiveInfinity => "_rtp",
+            Rounding::TowardNegativeInfinity => "_rtn",
+        }
+    }
+}
+
+impl Default for Rounding {
+    fn default() -> Rounding {
+        Rounding::Default
+    }
+}
+
+/// The name of the OpenCL C conversion function to T (e.g. `convert_uchar4_sat_rte`).
+///
+/// OpenCL C only saturates conversions to integer types, so `saturate` is ignored when
+/// T is a float type.
+pub fn convert_fn_name<T: ClTypeName>(saturate: bool, rounding: Rounding) -> String {
+    let to = T::CL_TYPE_NAME;
+    let is_float = to.starts_with("float") || to.starts_with("double") || to.starts_with("half");
+    let sat = if saturate && !is_float { "_sat" } else { "" };
+    format!("convert_{}{}{}", to, sat, rounding.cl_suffix())
+}
+
+/// The number of components of T in OpenCL C (e.g. 1 for `int` and 3 for `float3`).
+///
+/// This is read from the OpenCL C type name rather than from the size, because a 3
+/// component vector has the size of a 4 component vector.
+pub fn cl_components<T: ClTypeName>() -> usize {
+    let name = T::CL_TYPE_NAME;
+    let digits = name.trim_start_matches(|c: char| !c.is_ascii_digit());
+    digits.parse().unwrap_or(1)
+}
+
+/// The conversion kernel from F to T. Doubles and halfs need their extension enabled.
+fn convert_template<F: ClTypeName, T: ClTypeName>(fn_name: &str) -> KernelTemplate {
+    KernelTemplate::new(&format!(
+        "{}__kernel void convert_buffer(__global ${{From}} *src, __global ${{To}} *dst) {{
+            size_t i = get_global_id(0);
+            dst[i] = {}(src[i]);
+        }}",
+        extension_pragmas(&[F::CL_TYPE_NAME, T::CL_TYPE_NAME]),
+        fn_name
+    ))
+}
+
+impl Session {
+    /// Converts a Buffer of F into a new Buffer of T on the device.
+    ///
+    /// The conversion kernel is generated with the matching OpenCL C `convert_<type>`
+    /// function and cached per type pair (and saturation and rounding mode) like a
+    /// KernelTemplate. F and T must have the same number of components (e.g. `int4` to
+    /// `float4`); a `ConvertError::ComponentCountMismatch` is returned otherwise.
+    pub fn convert_buffer<F, T>(
+        &self,
+        buffer: &Buffer<F>,
+        saturate: bool,
+        rounding: Rounding,
+    ) -> Output<Buffer<T>>
+    where
+        F: Number + NumberTypedT + ClTypeName,
+        T: Number + NumberTypedT + ClTypeName,
+    {
+        let (from_components, to_components) = (cl_components::<F>(), cl_components::<T>());
+        if from_components != to_components {
+            Err(ConvertError::ComponentCountMismatch(
+                F::CL_TYPE_NAME,
+                from_components,
+                T::CL_TYPE_NAME,
+                to_components,
+            ))?;
+        }
+        let template = convert_template::<F, T>(&convert_fn_name::<T>(saturate, rounding)[..]);
+        let len = buffer.len();
+        let output = self.create_buffer::<T, usize>(len)?;
+        let kernel_op = KernelOperation::new("convert_buffer")
+            .add_arg(buffer)
+            .add_arg(&output)
+            .with_work(len);
+        self.execute_sync_template_operation_with(
+            &template,
+            &[("From", F::CL_TYPE_NAME), ("To", T::CL_TYPE_NAME)],
+            kernel_op,
+        )?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cl_components, convert_fn_name, convert_template, ConvertError};
+    use crate::ll::Half;
+    use crate::number_types::{Double4, Float3, Float4, Int4, Uchar16, Uchar4};
+    use crate::{testing, Rounding};
+
+    #[test]
+    fn convert_fn_name_includes_saturation_and_rounding() {
+        assert_eq!(
+            convert_fn_name::<u8>(false, Rounding::Default),
+            "convert_uchar"
+        );
+        assert_eq!(
+            convert_fn_name::<Uchar4>(true, Rounding::ToNearestEven),
+            "convert_uchar4_sat_rte"
+        );
+        assert_eq!(
+            convert_fn_name::<Float4>(true, Rounding::TowardZero),
+            "convert_float4_rtz"
+        );
+    }
+
+    #[test]
+    fn cl_components_counts_the_components_of_the_cl_type() {
+        assert_eq!(cl_components::<f64>(), 1);
+        assert_eq!(cl_components::<Half>(), 1);
+        assert_eq!(cl_components::<Float3>(), 3);
+        assert_eq!(cl_components::<Float4>(), 4);
+        assert_eq!(cl_components::<Uchar16>(), 16);
+    }
+
+    #[test]
+    fn convert_template_enables_the_extensions_of_its_types() {
+        let plain = convert_template::<i32, f32>("convert_float");
+        assert!(!plain.src().contains("#pragma"));
+        let fp64 = convert_template::<Double4, Float4>("convert_float4");
+        assert!(fp64
+            .src()
+            .starts_with("#pragma OPENCL EXTENSION cl_khr_fp64 : enable\n"));
+        let fp16 = convert_template::<f32, Half>("convert_half");
+        assert!(fp16
+            .src()
+            .starts_with("#pragma OPENCL EXTENSION cl_khr_fp16 : enable\n"));
+    }
+
+    #[test]
+    fn session_can_convert_buffers_on_the_device() {
+        let session = testing::get_session("__kernel void noop() {}");
+        let data = vec![-1i32, 300, 5];
+        let buffer = session.create_buffer::<i32, &[i32]>(&data[..]).unwrap();
+        let converted = session
+            .convert_buffer::<i32, u8>(&buffer, true, Rounding::Default)
+            .unwrap();
+        let output = session
+            .sync_read_buffer(&converted, vec![0u8; 3], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(output, vec![0, 255, 5]);
+
+        let data = vec![1.5f32, 2.5, -0.5];
+        let buffer = session.create_buffer::<f32, &[f32]>(&data[..]).unwrap();
+        let converted = session
+            .convert_buffer::<f32, i32>(&buffer, false, Rounding::ToNearestEven)
+            .unwrap();
+        let output = session
+            .sync_read_buffer(&converted, vec![0i32; 3], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(output, vec![2, 2, 0]);
+    }
+
+    #[test]
+    fn session_rejects_conversions_between_different_component_counts() {
+        let session = testing::get_session("__kernel void noop() {}");
+        let buffer = session.create_buffer::<Float3, usize>(4).unwrap();
+        let err = session
+            .convert_buffer::<Float3, Int4>(&buffer, false, Rounding::Default)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ConvertError>(),
+            Some(&ConvertError::ComponentCountMismatch(
+                "float3", 3, "int4", 4
+            ))
+        );
+        assert_eq!(session.template_cache_len(), 0);
+    }
+
+    #[test]
+    fn session_caches_conversion_kernels_per_type_pair() {
+        let session = testing::get_session("__kernel void noop() {}");
+        let buffer = session.create_buffer::<i32, usize>(4).unwrap();
+        let _ = session
+            .convert_buffer::<i32, f32>(&buffer, false, Rounding::Default)
+            .unwrap();
+        let _ = session
+            .convert_buffer::<i32, f32>(&buffer, false, Rounding::Default)
+            .unwrap();
+        assert_eq!(session.template_cache_len(), 1);
+        let _ = session
+            .convert_buffer::<i32, u8>(&buffer, true, Rounding::Default)
+            .unwrap();
+        assert_eq!(session.template_cache_len(), 2);
+    }
+}
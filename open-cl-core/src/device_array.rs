@@ -0,0 +1,483 @@
+use std::ops::Range;
+
+use crate::ll::ErrorT;
+use crate::{Buffer, BufferRect, Number, Output, Session};
+
+/// An error related to DeviceArray shapes and views.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum DeviceArrayError {
+    #[error("Shape {0:?} has {1} items but {2} items were given")]
+    ShapeMismatch(Vec<usize>, usize, usize),
+
+    #[error("Got {0} strides for an array of rank {1}")]
+    StridesMismatch(usize, usize),
+
+    #[error("Axis {0} is out of bounds for an array of rank {1}")]
+    AxisOutOfBounds(usize, usize),
+
+    #[error("Range {0}..{1} is out of bounds for an axis of length {2}")]
+    RangeOutOfBounds(usize, usize, usize),
+
+    #[error("{0:?} is not a permutation of the axes of an array of rank {1}")]
+    InvalidPermutation(Vec<usize>, usize),
+
+    #[error("The view ends at item {0} but the Buffer has length {1}")]
+    ViewOutOfBounds(usize, usize),
+
+    #[error("Only contiguous arrays can be reshaped")]
+    NotContiguous,
+}
+
+/// A Buffer with an n-dimensional shape.
+///
+/// The item at index `[i0, i1, ...]` is at `offset + i0 * strides[0] + i1 * strides[1] + ...`
+/// in the Buffer. Slicing, indexing and transposing return views of the same Buffer without
+/// copying. Host data is always in row-major order of the array's shape.
+#[derive(Debug, Clone)]
+pub struct DeviceArray<T: Number> {
+    buffer: Buffer<T>,
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    offset: usize,
+}
+
+impl<T: Number> DeviceArray<T> {
+    /// A row-major array over the whole Buffer.
+    pub fn new(buffer: Buffer<T>, shape: &[usize]) -> Output<DeviceArray<T>> {
+        let len: usize = shape.iter().product();
+        if len != buffer.len() {
+            return Err(DeviceArrayError::ShapeMismatch(
+                shape.to_vec(),
+                len,
+                buffer.len(),
+            ))?;
+        }
+        Ok(DeviceArray {
+            buffer,
+            strides: row_major_strides(shape),
+            shape: shape.to_vec(),
+            offset: 0,
+        })
+    }
+
+    /// A view of the Buffer with the given shape, strides (in items) and offset.
+    pub fn with_strides(
+        buffer: Buffer<T>,
+        shape: &[usize],
+        strides: &[usize],
+        offset: usize,
+    ) -> Output<DeviceArray<T>> {
+        if shape.len() != strides.len() {
+            return Err(DeviceArrayError::StridesMismatch(
+                strides.len(),
+                shape.len(),
+            ))?;
+        }
+        let array = DeviceArray {
+            buffer,
+            shape: shape.to_vec(),
+            strides: strides.to_vec(),
+            offset,
+        };
+        let end = array.view_end();
+        if end > array.buffer.len() {
+            return Err(DeviceArrayError::ViewOutOfBounds(end, array.buffer.len()))?;
+        }
+        Ok(array)
+    }
+
+    /// Creates a Buffer from row-major host data.
+    pub fn from_host(session: &Session, shape: &[usize], data: &[T]) -> Output<DeviceArray<T>> {
+        let len: usize = shape.iter().product();
+        if len != data.len() {
+            return Err(DeviceArrayError::ShapeMismatch(
+                shape.to_vec(),
+                len,
+                data.len(),
+            ))?;
+        }
+        let buffer = session.create_buffer::<T, &[T]>(data)?;
+        DeviceArray::new(buffer, shape)
+    }
+
+    /// Reads the array into a row-major Vec.
+    ///
+    /// Views that do not cover their whole Buffer are read with rect reads.
+    pub fn to_host(&self, session: &Session) -> Output<Vec<T>> {
+        let mut data = vec![T::zero(); self.len()];
+        if self.covers_buffer() {
+            session.sync_read_buffer(&self.buffer, &mut data[..], None)?;
+        } else {
+            for rect in self.rects() {
+                session.sync_read_buffer_rect(&self.buffer, &mut data[..], &rect)?;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Writes row-major host data into the array. Only the items of a view are written.
+    pub fn copy_from_host(&self, session: &Session, data: &[T]) -> Output<()> {
+        if data.len() != self.len() {
+            return Err(DeviceArrayError::ShapeMismatch(
+                self.shape.clone(),
+                self.len(),
+                data.len(),
+            ))?;
+        }
+        if self.covers_buffer() {
+            session.sync_write_buffer(&self.buffer, data, None)
+        } else {
+            for rect in self.rects() {
+                session.sync_write_buffer_rect(&self.buffer, data, &rect)?;
+            }
+            Ok(())
+        }
+    }
+
+    pub fn buffer(&self) -> &Buffer<T> {
+        &self.buffer
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape[..]
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides[..]
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Determines if the items of the array are packed in row-major order.
+    pub fn is_contiguous(&self) -> bool {
+        self.shape
+            .iter()
+            .zip(row_major_strides(&self.shape[..]))
+            .zip(self.strides.iter())
+            .all(|((&n, expected), &stride)| n == 1 || stride == expected)
+    }
+
+    /// A view of the `range` of items along `axis`.
+    pub fn slice(&self, axis: usize, range: Range<usize>) -> Output<DeviceArray<T>> {
+        self.check_axis(axis)?;
+        let n = self.shape[axis];
+        if range.start > range.end || range.end > n {
+            return Err(DeviceArrayError::RangeOutOfBounds(
+                range.start,
+                range.end,
+                n,
+            ))?;
+        }
+        let mut view = self.clone();
+        view.offset += range.start * self.strides[axis];
+        view.shape[axis] = range.end - range.start;
+        Ok(view)
+    }
+
+    /// A view of the item `index` of `axis` with the axis removed.
+    pub fn index_axis(&self, axis: usize, index: usize) -> Output<DeviceArray<T>> {
+        let mut view = self.slice(axis, index..index + 1)?;
+        view.shape.remove(axis);
+        view.strides.remove(axis);
+        Ok(view)
+    }
+
+    /// A view with the order of the axes reversed.
+    pub fn transpose(&self) -> DeviceArray<T> {
+        let mut view = self.clone();
+        view.shape.reverse();
+        view.strides.reverse();
+        view
+    }
+
+    /// A view whose axis `i` is axis `axes[i]` of this array.
+    pub fn permute_axes(&self, axes: &[usize]) -> Output<DeviceArray<T>> {
+        let mut seen = vec![false; self.ndim()];
+        let valid = axes.len() == self.ndim()
+            && axes
+                .iter()
+                .all(|&a| a < seen.len() && !std::mem::replace(&mut seen[a], true));
+        if !valid {
+            return Err(DeviceArrayError::InvalidPermutation(
+                axes.to_vec(),
+                self.ndim(),
+            ))?;
+        }
+        let mut view = self.clone();
+        view.shape = axes.iter().map(|&a| self.shape[a]).collect();
+        view.strides = axes.iter().map(|&a| self.strides[a]).collect();
+        Ok(view)
+    }
+
+    /// A view with a new shape of the same length. The array must be contiguous.
+    pub fn reshape(&self, shape: &[usize]) -> Output<DeviceArray<T>> {
+        let len: usize = shape.iter().product();
+        if len != self.len() {
+            return Err(DeviceArrayError::ShapeMismatch(
+                shape.to_vec(),
+                len,
+                self.len(),
+            ))?;
+        }
+        if !self.is_contiguous() {
+            return Err(DeviceArrayError::NotContiguous)?;
+        }
+        let mut view = self.clone();
+        view.shape = shape.to_vec();
+        view.strides = row_major_strides(shape);
+        Ok(view)
+    }
+
+    /// The rect transfers that move the array to or from row-major host data.
+    pub fn rects(&self) -> Vec<BufferRect> {
+        let item_size = std::mem::size_of::<T>();
+        if self.is_contiguous() {
+            plan_rects(&[self.len()], &[1], self.offset, item_size)
+        } else {
+            plan_rects(&self.shape[..], &self.strides[..], self.offset, item_size)
+        }
+    }
+
+    fn check_axis(&self, axis: usize) -> Output<()> {
+        if axis >= self.ndim() {
+            return Err(DeviceArrayError::AxisOutOfBounds(axis, self.ndim()))?;
+        }
+        Ok(())
+    }
+
+    fn covers_buffer(&self) -> bool {
+        self.offset == 0 && self.len() == self.buffer.len() && self.is_contiguous()
+    }
+
+    fn view_end(&self) -> usize {
+        if self.is_empty() {
+            return self.offset;
+        }
+        let last: usize = self
+            .shape
+            .iter()
+            .zip(self.strides.iter())
+            .map(|(n, stride)| (n - 1) * stride)
+            .sum();
+        self.offset + last + 1
+    }
+}
+
+fn row_major_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for i in (0..shape.len().saturating_sub(1)).rev() {
+        strides[i] = strides[i + 1] * shape[i + 1];
+    }
+    strides
+}
+
+/// Splits a strided view into rect transfers against row-major host data.
+///
+/// The innermost axis is the width of each rect when its stride is 1, the next two axes
+/// are the rows and slices when their pitches are valid for a rect, and one rect is made
+/// for each index of the remaining outer axes.
+fn plan_rects(
+    shape: &[usize],
+    strides: &[usize],
+    offset: usize,
+    item_size: usize,
+) -> Vec<BufferRect> {
+    if shape.iter().any(|&n| n == 0) {
+        return vec![];
+    }
+    let host_strides = row_major_strides(shape);
+    // (length, buffer stride, host stride) of the axes that affect addressing.
+    let mut axes: Vec<(usize, usize, usize)> = (0..shape.len())
+        .filter(|&i| shape[i] != 1)
+        .map(|i| (shape[i], strides[i], host_strides[i]))
+        .collect();
+
+    let mut width = 1;
+    if let Some(&(n, 1, _)) = axes.last() {
+        let rows_fit = axes.len() < 2 || axes[axes.len() - 2].1 >= n;
+        if rows_fit {
+            width = n;
+            axes.pop();
+        }
+    }
+    let (rows, row_stride, host_row_stride) = axes.pop().unwrap_or((1, width, width));
+    let (slices, slice_stride, host_slice_stride) = match axes.last() {
+        Some(&(n, stride, host_stride))
+            if stride >= rows * row_stride && stride % row_stride == 0 =>
+        {
+            axes.pop();
+            (n, stride, host_stride)
+        }
+        _ => (1, rows * row_stride, rows * host_row_stride),
+    };
+
+    let outer_len: usize = axes.iter().map(|a| a.0).product();
+    let mut rects = Vec::with_capacity(outer_len);
+    let mut index = vec![0; axes.len()];
+    for _ in 0..outer_len {
+        let mut buffer_start = offset;
+        let mut host_start = 0;
+        for (i, &(_, stride, host_stride)) in index.iter().zip(axes.iter()) {
+            buffer_start += i * stride;
+            host_start += i * host_stride;
+        }
+        rects.push(BufferRect {
+            buffer_origin: [buffer_start * item_size, 0, 0],
+            host_origin: [host_start * item_size, 0, 0],
+            region: [width * item_size, rows, slices],
+            buffer_row_pitch: row_stride * item_size,
+            buffer_slice_pitch: slice_stride * item_size,
+            host_row_pitch: host_row_stride * item_size,
+            host_slice_pitch: host_slice_stride * item_size,
+        });
+        for axis in (0..index.len()).rev() {
+            index[axis] += 1;
+            if index[axis] < axes[axis].0 {
+                break;
+            }
+            index[axis] = 0;
+        }
+    }
+    rects
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: Number> DeviceArray<T> {
+    /// Creates a Buffer from an ndarray array of any layout.
+    pub fn from_ndarray<S, D>(
+        session: &Session,
+        array: &ndarray::ArrayBase<S, D>,
+    ) -> Output<DeviceArray<T>>
+    where
+        S: ndarray::Data<Elem = T>,
+        D: ndarray::Dimension,
+    {
+        let data: Vec<T> = array.iter().cloned().collect();
+        DeviceArray::from_host(session, array.shape(), &data[..])
+    }
+
+    /// Reads the array into an ndarray array of the same shape.
+    pub fn to_ndarray(&self, session: &Session) -> Output<ndarray::ArrayD<T>> {
+        let data = self.to_host(session)?;
+        let array = ndarray::ArrayD::from_shape_vec(ndarray::IxDyn(&self.shape[..]), data)?;
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_rects, DeviceArray, DeviceArrayError};
+    use crate::{testing, BufferRect};
+
+    fn host_read(shape: &[usize], strides: &[usize], offset: usize, data: &[i32]) -> Vec<i32> {
+        let len: usize = shape.iter().product();
+        let mut out = vec![0; len];
+        for rect in plan_rects(shape, strides, offset, 1) {
+            for z in 0..rect.region[2] {
+                for y in 0..rect.region[1] {
+                    for x in 0..rect.region[0] {
+                        let b = rect.buffer_origin[0]
+                            + z * rect.buffer_slice_pitch
+                            + y * rect.buffer_row_pitch
+                            + x;
+                        let h = rect.host_origin[0]
+                            + z * rect.host_slice_pitch
+                            + y * rect.host_row_pitch
+                            + x;
+                        out[h] = data[b];
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn plan_rects_covers_a_contiguous_view_with_one_rect() {
+        let rects = plan_rects(&[6], &[1], 2, 4);
+        assert_eq!(
+            rects,
+            vec![BufferRect {
+                buffer_origin: [8, 0, 0],
+                host_origin: [0, 0, 0],
+                region: [24, 1, 1],
+                buffer_row_pitch: 24,
+                buffer_slice_pitch: 24,
+                host_row_pitch: 24,
+                host_slice_pitch: 24,
+            }]
+        );
+    }
+
+    #[test]
+    fn plan_rects_reads_sliced_and_transposed_views() {
+        let data: Vec<i32> = (0..24).collect();
+        // rows 1..3 and cols 1..3 of a 4x6 matrix
+        assert_eq!(
+            host_read(&[2, 2], &[6, 1], 7, &data[..]),
+            vec![7, 8, 13, 14]
+        );
+        // the transpose of a 2x3 matrix
+        assert_eq!(
+            host_read(&[3, 2], &[1, 3], 0, &data[..]),
+            vec![0, 3, 1, 4, 2, 5]
+        );
+        // a permuted 2x3x4 array with more than 3 effective axes worth of rects
+        let expected: Vec<i32> = (0..4)
+            .flat_map(|k| (0..2).flat_map(move |i| (0..3).map(move |j| i * 12 + j * 4 + k)))
+            .collect();
+        assert_eq!(host_read(&[4, 2, 3], &[1, 12, 4], 0, &data[..]), expected);
+    }
+
+    #[test]
+    fn views_are_checked() {
+        let session = testing::get_noop_session();
+        let data: Vec<f32> = (0..12).map(|i| i as f32).collect();
+        let array = DeviceArray::from_host(&session, &[3, 4], &data[..]).unwrap();
+        let err = array.slice(1, 2..5).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeviceArrayError>(),
+            Some(&DeviceArrayError::RangeOutOfBounds(2, 5, 4))
+        );
+        assert!(array.permute_axes(&[0, 0]).is_err());
+        assert!(array.transpose().reshape(&[12]).is_err());
+        assert!(DeviceArray::from_host(&session, &[5], &data[..]).is_err());
+    }
+
+    #[test]
+    fn views_are_read_and_written_without_copying_the_buffer() {
+        let session = testing::get_noop_session();
+        let data: Vec<i32> = (0..12).collect();
+        let array = DeviceArray::from_host(&session, &[3, 4], &data[..]).unwrap();
+        assert_eq!(array.to_host(&session).unwrap(), data);
+
+        let transposed = array.transpose();
+        assert_eq!(transposed.shape(), &[4, 3]);
+        assert_eq!(
+            transposed.to_host(&session).unwrap(),
+            vec![0, 4, 8, 1, 5, 9, 2, 6, 10, 3, 7, 11]
+        );
+
+        let column = array.index_axis(1, 2).unwrap();
+        assert_eq!(column.to_host(&session).unwrap(), vec![2, 6, 10]);
+        column.copy_from_host(&session, &[-1, -2, -3]).unwrap();
+        assert_eq!(
+            array.to_host(&session).unwrap(),
+            vec![0, 1, -1, 3, 4, 5, -2, 7, 8, 9, -3, 11]
+        );
+    }
+}
@@ -6,14 +6,71 @@
 
 // use crate::ll::Kernel as ClKernel;
 
-use crate::{Buffer, CommandQueueOptions, Dims, Output, Work};
+use std::ops::Deref;
 
-use crate::ll::{KernelArg as ClKernelArg, KernelArgPtr, KernelError, Number};
+use crate::{Buffer, ClTypeName, CommandQueueOptions, Dims, Output, UntypedBuffer, Work};
+
+use crate::ll::{KernelArg as ClKernelArg, KernelArgPtr, KernelError, Number, NumberTyped};
+
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::ll::SvmKernelArg;
+
+/// A Buffer kernel arg. The arg keeps the OpenCL C name of the Buffer's number type,
+/// which is checked against the type the kernel declares for the arg when the
+/// KernelOperation asks for it (see `KernelOperation::with_arg_type_checks`).
+#[derive(Debug, Clone, Copy)]
+pub struct BufferArg<'a> {
+    buffer: &'a UntypedBuffer,
+    cl_type_name: Option<&'static str>,
+}
+
+impl<'a> BufferArg<'a> {
+    pub fn buffer(&self) -> &'a UntypedBuffer {
+        self.buffer
+    }
+
+    /// The OpenCL C name of the Buffer's number type. None for an UntypedBuffer of a
+    /// type that is not a built-in Number.
+    pub fn cl_type_name(&self) -> Option<&'static str> {
+        self.cl_type_name
+    }
+}
+
+impl<'a> Deref for BufferArg<'a> {
+    type Target = UntypedBuffer;
+
+    fn deref(&self) -> &UntypedBuffer {
+        self.buffer
+    }
+}
+
+impl<'a, T: Number + ClTypeName> From<&'a Buffer<T>> for BufferArg<'a> {
+    fn from(buf: &'a Buffer<T>) -> BufferArg<'a> {
+        BufferArg {
+            buffer: buf.as_untyped(),
+            cl_type_name: Some(T::CL_TYPE_NAME),
+        }
+    }
+}
+
+impl<'a> From<&'a UntypedBuffer> for BufferArg<'a> {
+    fn from(buf: &'a UntypedBuffer) -> BufferArg<'a> {
+        BufferArg {
+            buffer: buf,
+            cl_type_name: buf.cl_type_name(),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum KernelArg<'a> {
     Num(ClKernelArg<'a>),
-    Buffer(&'a Buffer),
+    Buffer(BufferArg<'a>),
+    /// A Buffer that a MultiSession splits across devices. A Session passes the
+    /// whole Buffer.
+    SplitBuffer(BufferArg<'a>),
+    #[cfg(feature = "opencl_version_2_0_0")]
+    Svm(SvmKernelArg<'a>),
 }
 
 #[derive(Debug)]
@@ -21,6 +78,7 @@ pub struct KernelOperation<'a> {
     _name: String,
     _args: Vec<KernelArg<'a>>,
     _work: Option<Work>,
+    _check_arg_types: bool,
     pub command_queue_opts: Option<CommandQueueOptions>,
 }
 
@@ -30,6 +88,7 @@ impl<'a> KernelOperation<'a> {
             _name: name.to_owned(),
             _args: vec![],
             _work: None,
+            _check_arg_types: false,
             command_queue_opts: None,
         }
     }
@@ -65,11 +124,34 @@ impl<'a> KernelOperation<'a> {
         self
     }
 
+    /// Adds a Buffer arg that is split along with the global work size when the
+    /// KernelOperation is executed by a MultiSession.
+    pub fn add_split_arg<B: Into<BufferArg<'a>>>(mut self, buffer: B) -> KernelOperation<'a> {
+        self._args.push(KernelArg::SplitBuffer(buffer.into()));
+        self
+    }
+
     pub fn with_command_queue_options(mut self, opts: CommandQueueOptions) -> KernelOperation<'a> {
         self.command_queue_opts = Some(opts);
         self
     }
 
+    /// Checks the number type of each typed Buffer arg against the type the kernel
+    /// declares for the arg before the kernel is launched.
+    ///
+    /// The declared types are only available if the program was built with
+    /// `-cl-kernel-arg-info`; otherwise nothing is checked. Types are compared by name, so
+    /// kernels that declare an arg with a typedef of the Buffer's type, or with the scalar
+    /// type of a vector Buffer, are rejected.
+    pub fn with_arg_type_checks(mut self) -> KernelOperation<'a> {
+        self._check_arg_types = true;
+        self
+    }
+
+    pub fn checks_arg_types(&self) -> bool {
+        self._check_arg_types
+    }
+
     pub fn argc(&self) -> usize {
         self._args.len()
     }
@@ -82,9 +164,30 @@ impl<'a> KernelOperation<'a> {
     }
 }
 
-impl<'a> From<&'a Buffer> for KernelArg<'a> {
-    fn from(buf: &'a Buffer) -> KernelArg<'a> {
-        KernelArg::Buffer(buf)
+impl<'a, T: Number + ClTypeName> From<&'a Buffer<T>> for KernelArg<'a> {
+    fn from(buf: &'a Buffer<T>) -> KernelArg<'a> {
+        KernelArg::Buffer(buf.into())
+    }
+}
+
+impl<'a> From<&'a UntypedBuffer> for KernelArg<'a> {
+    fn from(buf: &'a UntypedBuffer) -> KernelArg<'a> {
+        KernelArg::Buffer(buf.into())
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+impl<'a> From<SvmKernelArg<'a>> for KernelArg<'a> {
+    fn from(svm_arg: SvmKernelArg<'a>) -> KernelArg<'a> {
+        KernelArg::Svm(svm_arg)
+    }
+}
+
+/// Allows low-level kernel args (e.g. `ll::KernelArg::from_pipe`) to be passed
+/// to a KernelOperation.
+impl<'a> From<ClKernelArg<'a>> for KernelArg<'a> {
+    fn from(arg: ClKernelArg<'a>) -> KernelArg<'a> {
+        KernelArg::Num(arg)
     }
 }
 
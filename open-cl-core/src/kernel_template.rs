@@ -0,0 +1,115 @@
+use crate::ll::ErrorT;
+use crate::{ClTypeName, Output};
+
+/// An error related to a KernelTemplate.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum KernelTemplateError {
+    #[error("KernelTemplate placeholder ${{{0}}} has no bound type")]
+    UnboundPlaceholder(String),
+
+    #[error("KernelTemplate placeholder starting at byte {0} is not closed with '}}'")]
+    UnclosedPlaceholder(usize),
+}
+
+/// Kernel source with `${T}`-style placeholders for OpenCL C type names.
+///
+/// A placeholder is replaced by the OpenCL C name of the type bound to it (e.g.
+/// `${T}` becomes `int` for i32 and `float4` for Float4), so one template serves
+/// every number type that implements ClTypeName. Use `Session::build_template` to build (and cache) the
+/// rendered source on a Session.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KernelTemplate {
+    src: String,
+}
+
+impl KernelTemplate {
+    pub fn new(src: &str) -> KernelTemplate {
+        KernelTemplate {
+            src: src.to_owned(),
+        }
+    }
+
+    pub fn src(&self) -> &str {
+        &self.src[..]
+    }
+
+    /// Renders the template with `${T}` bound to T.
+    pub fn render<T: ClTypeName>(&self) -> Output<String> {
+        self.render_with(&[("T", T::CL_TYPE_NAME)])
+    }
+
+    /// Renders the template with each placeholder name bound to an OpenCL C type name
+    /// (e.g. `("T", i32::CL_TYPE_NAME)`).
+    pub fn render_with(&self, bindings: &[(&str, &str)]) -> Output<String> {
+        let mut rendered = String::with_capacity(self.src.len());
+        let mut rest = &self.src[..];
+        let mut consumed = 0;
+        while let Some(start) = rest.find("${") {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = match after.find('}') {
+                Some(end) => end,
+                None => return Err(KernelTemplateError::UnclosedPlaceholder(consumed + start))?,
+            };
+            let name = after[..end].trim();
+            match bindings.iter().find(|(n, _)| *n == name) {
+                Some((_, cl_type_name)) => rendered.push_str(cl_type_name),
+                None => return Err(KernelTemplateError::UnboundPlaceholder(name.to_owned()))?,
+            }
+            let placeholder_len = start + 2 + end + 1;
+            consumed += placeholder_len;
+            rest = &rest[placeholder_len..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::number_types::Float4;
+    use crate::{ClTypeName, KernelTemplate, KernelTemplateError};
+
+    const SRC: &'static str = "__kernel void add_one(__global ${T} *data) {
+        data[get_global_id(0)] += (${T})1;
+    }";
+
+    #[test]
+    fn kernel_template_renders_the_cl_type_name_of_t() {
+        let template = KernelTemplate::new(SRC);
+        let rendered = template.render::<i32>().unwrap();
+        assert!(rendered.contains("__global int *data"));
+        assert!(rendered.contains("(int)1"));
+        let rendered = template.render::<Float4>().unwrap();
+        assert!(rendered.contains("__global float4 *data"));
+    }
+
+    #[test]
+    fn kernel_template_renders_named_placeholders() {
+        let template = KernelTemplate::new("${ T } ${U}");
+        let rendered = template
+            .render_with(&[("T", u8::CL_TYPE_NAME), ("U", f64::CL_TYPE_NAME)])
+            .unwrap();
+        assert_eq!(rendered, "uchar double");
+    }
+
+    #[test]
+    fn kernel_template_errors_on_unbound_placeholders() {
+        let template = KernelTemplate::new("${T} ${U}");
+        let err = template.render::<i32>().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<KernelTemplateError>(),
+            Some(&KernelTemplateError::UnboundPlaceholder("U".to_string()))
+        );
+    }
+
+    #[test]
+    fn kernel_template_errors_on_unclosed_placeholders() {
+        let template = KernelTemplate::new("int ${T");
+        let err = template.render::<i32>().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<KernelTemplateError>(),
+            Some(&KernelTemplateError::UnclosedPlaceholder(4))
+        );
+    }
+}
@@ -45,17 +45,44 @@ pub mod context;
 pub use context::Context;
 
 pub mod program;
-pub use program::{Program, UnbuiltProgram};
+pub use program::{PendingProgram, Program, UnbuiltProgram};
 
 pub mod buffer;
-pub use buffer::Buffer;
+pub use buffer::{Buffer, UntypedBuffer};
 
 pub mod kernel;
-pub use kernel::{KernelArg, KernelOperation};
+pub use kernel::{BufferArg, KernelArg, KernelOperation};
+
+pub mod kernel_template;
+pub use kernel_template::{KernelTemplate, KernelTemplateError};
 
 pub mod session;
 pub use session::Session;
 
+pub mod convert;
+pub use convert::{ConvertError, Rounding};
+
+pub mod device_array;
+pub use device_array::{DeviceArray, DeviceArrayError};
+
+pub mod npy;
+pub use npy::{NpyError, NpyHeader};
+
+pub mod algorithms;
+pub use algorithms::{
+    AlgorithmError, BlasNumber, Distribution, Layout, Matrix, RadixSortKey, RandomNumber,
+    ReduceOp, ScanKind, SortOrder,
+};
+
+pub mod profiler;
+pub use profiler::{CommandKind, KernelStats, ProfiledCommand, Profiler};
+
+pub mod session_pool;
+pub use session_pool::{PoolPolicy, PooledSession, SessionPool, SessionPoolError};
+
+pub mod multi_session;
+pub use multi_session::{MultiSession, MultiSessionError, Partition, PartitionStrategy};
+
 // pub mod command_queue;
 
 // #[cfg(test)]
@@ -64,26 +91,36 @@ pub use session::Session;
 // pub use command_queue::CommandQueue;
 
 pub use ll::cl::{
-    ClObject, CommandQueueProperties, DeviceAffinityDomain, DeviceType, MemFlags, StatusCodeError,
+    ClError, ClErrorSite, ClObject, CommandQueueProperties, DeviceAffinityDomain, DeviceType,
+    MemFlags, MemMigrationFlags, StatusCodeError,
 };
-pub use ll::numbers::{Number, NumberType, NumberTyped, NumberTypedT};
+pub use ll::numbers::{
+    ClAlignment, ClStruct, ClTypeName, Number, NumberType, NumberTyped, NumberTypedT,
+};
+
+#[cfg(feature = "opencl_version_2_0_0")]
+pub use ll::cl::{DeviceSvmCapabilities, MapFlags, PipeInfo, SvmMemFlags};
+#[cfg(feature = "opencl_version_2_0_0")]
+pub use ll::{Pipe, PipeError, SvmBuffer, SvmError, SvmKernelArg};
 
 pub use ll::vec_or_slice::{MutVecOrSlice, VecOrSlice};
 pub use ll::{
-    AsPtr, BufferBuilder, CommandQueueOptions, Dims, HasDeviceInfo, HostAccess, KernelAccess,
-    KernelArgPtr, Mem, MemAllocation, MemConfig, MemConfigBuilder, MemPtr, NumCastFrom,
-    NumCastInto, NumberTypeError, Waitlist, Work,
+    AsPtr, BufferBuilder, BufferRect, CommandQueueOptions, ContextErrorCallback, ContextErrorFn,
+    ContextPropertiesError, ContextProperty, DeviceBuildLog, Dims, HasDeviceInfo, HostAccess,
+    KernelAccess, KernelArgPtr, Mem, MemAllocation, MemConfig, MemConfigBuilder, MemPtr,
+    NumCastFrom, NumCastInto, NumberTypeError, ProgramBuildError, Waitlist, Work,
 };
 
 pub mod number_types {
-    pub use crate::ll::numbers::{Char, Char16, Char2, Char4, Char8};
-    pub use crate::ll::numbers::{Double, Double16, Double2, Double4, Double8};
-    pub use crate::ll::numbers::{Float, Float16, Float2, Float4, Float8};
-    pub use crate::ll::numbers::{Int, Int16, Int2, Int4, Int8};
-    pub use crate::ll::numbers::{Long, Long16, Long2, Long4, Long8};
-    pub use crate::ll::numbers::{Short, Short16, Short2, Short4, Short8};
-    pub use crate::ll::numbers::{Uchar, Uchar16, Uchar2, Uchar4, Uchar8};
-    pub use crate::ll::numbers::{Uint, Uint16, Uint2, Uint4, Uint8};
-    pub use crate::ll::numbers::{Ulong, Ulong16, Ulong2, Ulong4, Ulong8};
-    pub use crate::ll::numbers::{Ushort, Ushort16, Ushort2, Ushort4, Ushort8};
+    pub use crate::ll::numbers::{Char, Char16, Char2, Char3, Char4, Char8};
+    pub use crate::ll::numbers::{Double, Double16, Double2, Double3, Double4, Double8};
+    pub use crate::ll::numbers::{Float, Float16, Float2, Float3, Float4, Float8};
+    pub use crate::ll::numbers::{Half, Half16, Half2, Half3, Half4, Half8};
+    pub use crate::ll::numbers::{Int, Int16, Int2, Int3, Int4, Int8};
+    pub use crate::ll::numbers::{Long, Long16, Long2, Long3, Long4, Long8};
+    pub use crate::ll::numbers::{Short, Short16, Short2, Short3, Short4, Short8};
+    pub use crate::ll::numbers::{Uchar, Uchar16, Uchar2, Uchar3, Uchar4, Uchar8};
+    pub use crate::ll::numbers::{Uint, Uint16, Uint2, Uint3, Uint4, Uint8};
+    pub use crate::ll::numbers::{Ulong, Ulong16, Ulong2, Ulong3, Ulong4, Ulong8};
+    pub use crate::ll::numbers::{Ushort, Ushort16, Ushort2, Ushort3, Ushort4, Ushort8};
 }
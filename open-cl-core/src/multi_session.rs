@@ -0,0 +1,542 @@
+use std::sync::{RwLock, RwLockWriteGuard};
+use std::time::Duration;
+
+use crate::ll::{
+    ErrorT, Event as ClEvent, Kernel as ClKernel, KernelArg as ClKernelArg, Mem, WorkError,
+};
+use crate::{
+    Buffer, BufferBuilder, CommandKind, CommandQueueProperties, Dims, HasDeviceInfo, KernelArg,
+    KernelOperation, MemPtr, Number, NumberTyped, Output, Session, Waitlist, Work,
+};
+
+/// An error related to a MultiSession.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum MultiSessionError {
+    #[error("A MultiSession requires at least one Session")]
+    NoSessions,
+
+    #[error("The Sessions of a MultiSession must share a single context")]
+    ContextMismatch,
+
+    #[error("A MultiSession can only split 1 dimensional work")]
+    RequiresOneDimensionalWork,
+
+    #[error("A MultiSession cannot split work that has a global offset")]
+    GlobalOffsetNotSupported,
+
+    #[error("Split buffer len {0} is not a multiple of the global work size {1}")]
+    SplitBufferLenMismatch(usize, usize),
+
+    #[error("A split buffer cannot also be passed as a buffer arg that is not split")]
+    SplitBufferIsShared,
+}
+
+/// The way a MultiSession divides the global work size between its devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionStrategy {
+    /// Work is divided in proportion to each device's MAX_COMPUTE_UNITS.
+    ComputeUnits,
+
+    /// Work is divided in proportion to each device's throughput as measured by
+    /// previous executions. The throughput of a device that has not been measured yet is
+    /// estimated from the measured devices' throughput per compute unit, and ComputeUnits
+    /// is used until any device has been measured. Measuring requires command queues with
+    /// PROFILING_ENABLE (the default).
+    Throughput,
+}
+
+impl Default for PartitionStrategy {
+    fn default() -> PartitionStrategy {
+        PartitionStrategy::ComputeUnits
+    }
+}
+
+/// The part of the global work size that was executed by one Session of a MultiSession.
+/// The offset is the index of the partition's first work item in the global work size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    pub session_index: usize,
+    pub offset: usize,
+    pub len: usize,
+    pub duration: Option<Duration>,
+}
+
+/// A group of Sessions that share a context, one per device, that executes a single
+/// KernelOperation data-parallel across all of its devices.
+///
+/// The global work size is partitioned between the devices. Each split arg (see
+/// `KernelOperation::add_split_arg`) is passed to a device as a sub-buffer of the
+/// device's partition so every partition indexes its buffers from 0, and no two devices
+/// access the same memory of a split buffer. The other buffer args are passed whole to
+/// every device and must only be read by the kernel. All partitions are enqueued before
+/// any of them is waited on; once execution returns the results are in the split buffers.
+#[derive(Debug)]
+pub struct MultiSession {
+    sessions: Vec<Session>,
+    strategy: PartitionStrategy,
+    throughputs: RwLock<Vec<Option<f64>>>,
+}
+
+impl MultiSession {
+    pub fn new(sessions: Vec<Session>) -> Output<MultiSession> {
+        let first = sessions.first().ok_or(MultiSessionError::NoSessions)?;
+        for session in sessions.iter() {
+            if session.low_level_context() != first.low_level_context() {
+                return Err(MultiSessionError::ContextMismatch)?;
+            }
+        }
+        let throughputs = RwLock::new(vec![None; sessions.len()]);
+        Ok(MultiSession {
+            sessions,
+            strategy: PartitionStrategy::default(),
+            throughputs,
+        })
+    }
+
+    pub fn create(src: &str, cq_props: Option<CommandQueueProperties>) -> Output<MultiSession> {
+        MultiSession::new(Session::create(src, cq_props)?)
+    }
+
+    pub fn with_strategy(mut self, strategy: PartitionStrategy) -> MultiSession {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn strategy(&self) -> PartitionStrategy {
+        self.strategy
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions[..]
+    }
+
+    /// Creates a Buffer in the context shared by all of the MultiSession's devices.
+    pub fn create_buffer<T: Number, B: BufferBuilder>(
+        &self,
+        buffer_creator: B,
+    ) -> Output<Buffer<T>> {
+        self.sessions[0].create_buffer::<T, B>(buffer_creator)
+    }
+
+    /// The relative share of the work each Session receives.
+    pub fn weights(&self) -> Output<Vec<f64>> {
+        let compute_units = self
+            .sessions
+            .iter()
+            .map(|s| s.low_level_device().max_compute_units().map(|u| u as f64))
+            .collect::<Output<Vec<f64>>>()?;
+        if self.strategy == PartitionStrategy::Throughput {
+            let throughputs = self.throughputs.read().unwrap();
+            if let Some(weights) = estimate_throughputs(&throughputs[..], &compute_units[..]) {
+                return Ok(weights);
+            }
+        }
+        Ok(compute_units)
+    }
+
+    /// Splits the KernelOperation's 1 dimensional global work size between the devices,
+    /// executes all of the partitions concurrently, and waits for all of them to complete.
+    pub fn execute_sync_kernel_operation<'a>(
+        &self,
+        mut kernel_op: KernelOperation<'a>,
+    ) -> Output<Vec<Partition>> {
+        let work = kernel_op.work()?;
+        if work.global_offset.is_some() {
+            return Err(MultiSessionError::GlobalOffsetNotSupported)?;
+        }
+        let global_size = match work.global_size {
+            Dims::One(x) => x,
+            _ => return Err(MultiSessionError::RequiresOneDimensionalWork)?,
+        };
+        let local_size = match work.local_size {
+            None => None,
+            Some(Dims::One(x)) => Some(x),
+            Some(_) => return Err(MultiSessionError::RequiresOneDimensionalWork)?,
+        };
+        // Checked up front like a Session's enqueue would, since the granule and the
+        // partition sizes divide by them.
+        let is_valid_local_size = local_size.map_or(true, |l| l > 0 && global_size % l == 0);
+        if global_size == 0 || !is_valid_local_size {
+            return Err(WorkError::InvalidWorkSize)?;
+        }
+        let granule = self.granule(kernel_op.args(), global_size, local_size)?;
+        let ranges = split_range(global_size, granule, &self.weights()?[..]);
+        let command_queue_opts = kernel_op.command_queue_opts();
+
+        // A buffer passed as several args is locked once. Locks are held until every
+        // partition has completed.
+        let mut mem_locks: Vec<(usize, bool, RwLockWriteGuard<Mem>)> = Vec::new();
+        let mut arg_locks: Vec<Option<usize>> = Vec::with_capacity(kernel_op.argc());
+        for arg in kernel_op.args().iter() {
+            let (buffer, is_split) = match *arg {
+                KernelArg::Buffer(buffer) => (buffer, false),
+                KernelArg::SplitBuffer(buffer) => (buffer, true),
+                _ => {
+                    arg_locks.push(None);
+                    continue;
+                }
+            };
+            let lock_id = buffer.buffer().lock_id();
+            match mem_locks.iter().position(|(id, _, _)| *id == lock_id) {
+                Some(position) => {
+                    if mem_locks[position].1 != is_split {
+                        return Err(MultiSessionError::SplitBufferIsShared)?;
+                    }
+                    arg_locks.push(Some(position));
+                }
+                None => {
+                    arg_locks.push(Some(mem_locks.len()));
+                    mem_locks.push((lock_id, is_split, buffer.buffer().write_lock()));
+                }
+            }
+        }
+        let mut sub_mems: Vec<Mem> = Vec::new();
+        let mut events: Vec<ClEvent> = Vec::with_capacity(ranges.len());
+
+        unsafe {
+            for (range_index, (session_index, offset, len)) in ranges.iter().cloned().enumerate() {
+                // Every session builds the same source, so the arg types are checked once.
+                let check_arg_types = kernel_op.checks_arg_types() && range_index == 0;
+                let session = &self.sessions[session_index];
+                let mut kernel = ClKernel::create(session.low_level_program(), kernel_op.name())?;
+                for (arg_index, arg) in kernel_op.mut_args().iter_mut().enumerate() {
+                    match arg {
+                        KernelArg::Num(ref mut cl_arg) => kernel.set_arg(arg_index, cl_arg)?,
+                        KernelArg::Buffer(buffer) => {
+                            if let (true, Some(cl_type_name)) =
+                                (check_arg_types, buffer.cl_type_name())
+                            {
+                                kernel.check_arg_type(arg_index, cl_type_name)?;
+                            }
+                            let (_, _, mem) = &mem_locks[arg_locks[arg_index].unwrap()];
+                            kernel.set_arg(arg_index, &mut ClKernelArg::new(&**mem))?;
+                        }
+                        KernelArg::SplitBuffer(buffer) => {
+                            if let (true, Some(cl_type_name)) =
+                                (check_arg_types, buffer.cl_type_name())
+                            {
+                                kernel.check_arg_type(arg_index, cl_type_name)?;
+                            }
+                            let (_, _, mem) = &mem_locks[arg_locks[arg_index].unwrap()];
+                            let items_per_work_item = mem.len()? / global_size;
+                            let sub_mem = mem.create_sub_buffer(
+                                offset * items_per_work_item,
+                                len * items_per_work_item,
+                            )?;
+                            kernel.set_arg(arg_index, &mut ClKernelArg::new(&sub_mem))?;
+                            sub_mems.push(sub_mem);
+                        }
+                        #[cfg(feature = "opencl_version_2_0_0")]
+                        KernelArg::Svm(ref svm_arg) => kernel.set_svm_arg(arg_index, svm_arg)?,
+                    }
+                }
+                let partition_work = match local_size {
+                    Some(local) => Work::new(len).with_local_size(local),
+                    None => Work::new(len),
+                };
+                let mut queue = session.write_queue();
+                let event = queue.enqueue_kernel(
+                    &mut kernel,
+                    &partition_work,
+                    command_queue_opts.clone(),
+                )?;
+                session.track_in_flight(&event);
+                events.push(event);
+            }
+            // Waiting on all of the events at once flushes every queue.
+            (&events[..]).wait()?;
+        }
+        let name = kernel_op.name();
+        for ((session_index, _, _), event) in ranges.iter().zip(events.iter()) {
+            let session = &self.sessions[*session_index];
+            let queue = session.read_queue();
+            session.record_command(&queue, CommandKind::Kernel, name, None, event.profiling());
+        }
+
+        let partitions: Vec<Partition> = ranges
+            .iter()
+            .zip(events.iter())
+            .map(|((session_index, offset, len), event)| Partition {
+                session_index: *session_index,
+                offset: *offset,
+                len: *len,
+                duration: event.profiling().duration_of_execution(),
+            })
+            .collect();
+        self.record_throughputs(&partitions[..]);
+        Ok(partitions)
+    }
+
+    /// The smallest number of work items a partition can be a multiple of. Partition
+    /// boundaries must respect the local work size and every device's MEM_BASE_ADDR_ALIGN
+    /// for the origin of each split arg's sub-buffer.
+    fn granule(
+        &self,
+        args: &[KernelArg],
+        global_size: usize,
+        local_size: Option<usize>,
+    ) -> Output<usize> {
+        let mut granule = local_size.unwrap_or(1);
+        let mut align_in_bytes = 1;
+        for session in self.sessions.iter() {
+            let align_in_bits = session.low_level_device().mem_base_addr_align()? as usize;
+            align_in_bytes = lcm(align_in_bytes, std::cmp::max(align_in_bits / 8, 1));
+        }
+        for arg in args.iter() {
+            if let KernelArg::SplitBuffer(buffer) = arg {
+                let len = buffer.length()?;
+                if len % global_size != 0 {
+                    return Err(MultiSessionError::SplitBufferLenMismatch(len, global_size))?;
+                }
+                let bytes_per_work_item =
+                    (len / global_size) * buffer.number_type().number_type_size_of();
+                let items_per_alignment = align_in_bytes / gcd(align_in_bytes, bytes_per_work_item);
+                granule = lcm(granule, items_per_alignment);
+            }
+        }
+        Ok(granule)
+    }
+
+    fn record_throughputs(&self, partitions: &[Partition]) {
+        let mut throughputs = self.throughputs.write().unwrap();
+        for partition in partitions.iter() {
+            if let Some(duration) = partition.duration {
+                let secs = duration.as_secs_f64();
+                if secs > 0.0 {
+                    throughputs[partition.session_index] = Some(partition.len as f64 / secs);
+                }
+            }
+        }
+    }
+}
+
+/// The throughputs with every unmeasured device estimated from the measured devices'
+/// throughput per compute unit, or None if no device has been measured.
+fn estimate_throughputs(throughputs: &[Option<f64>], compute_units: &[f64]) -> Option<Vec<f64>> {
+    let (measured, units) = throughputs
+        .iter()
+        .zip(compute_units.iter())
+        .filter_map(|(throughput, units)| throughput.map(|t| (t, *units)))
+        .fold((0.0, 0.0), |(t_sum, u_sum), (t, u)| (t_sum + t, u_sum + u));
+    if units == 0.0 {
+        return None;
+    }
+    let per_unit = measured / units;
+    Some(
+        throughputs
+            .iter()
+            .zip(compute_units.iter())
+            .map(|(throughput, units)| throughput.unwrap_or(per_unit * units))
+            .collect(),
+    )
+}
+
+/// Splits `0..total` into contiguous (index, offset, len) ranges in proportion to the
+/// weights. Every range except the last is a multiple of the granule and empty ranges
+/// are skipped.
+fn split_range(total: usize, granule: usize, weights: &[f64]) -> Vec<(usize, usize, usize)> {
+    let weight_sum: f64 = weights.iter().sum();
+    let last = weights.len() - 1;
+    let mut ranges = Vec::with_capacity(weights.len());
+    let mut offset = 0;
+    for (index, weight) in weights.iter().enumerate() {
+        let remaining = total - offset;
+        let len = if index == last {
+            remaining
+        } else {
+            let share = (total as f64 * weight / weight_sum) as usize;
+            std::cmp::min(share / granule * granule, remaining)
+        };
+        if len > 0 {
+            ranges.push((index, offset, len));
+        }
+        offset += len;
+    }
+    ranges
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate_throughputs, split_range};
+    use crate::ll::WorkError;
+    use crate::{
+        testing, KernelOperation, MultiSession, MultiSessionError, PartitionStrategy, Work,
+    };
+
+    const SRC: &'static str = "
+    __kernel void add_one(__global int *data) {
+        data[get_global_id(0)] += 1;
+    }
+
+    __kernel void copy_first(__global int *data, __global const int *first) {
+        data[get_global_id(0)] = first[0];
+    }";
+
+    #[test]
+    fn split_range_divides_work_in_proportion_to_weights() {
+        let ranges = split_range(100, 1, &[1.0, 3.0]);
+        assert_eq!(ranges, vec![(0, 0, 25), (1, 25, 75)]);
+    }
+
+    #[test]
+    fn split_range_respects_the_granule() {
+        let ranges = split_range(100, 16, &[1.0, 1.0, 1.0]);
+        assert_eq!(ranges, vec![(0, 0, 32), (1, 32, 32), (2, 64, 36)]);
+    }
+
+    #[test]
+    fn split_range_skips_empty_ranges() {
+        let ranges = split_range(10, 16, &[1.0, 1.0]);
+        assert_eq!(ranges, vec![(1, 0, 10)]);
+    }
+
+    #[test]
+    fn estimate_throughputs_scales_the_measured_throughput_per_compute_unit() {
+        assert_eq!(estimate_throughputs(&[None, None], &[4.0, 8.0]), None);
+        assert_eq!(
+            estimate_throughputs(&[Some(100.0), None], &[4.0, 8.0]),
+            Some(vec![100.0, 200.0])
+        );
+        assert_eq!(
+            estimate_throughputs(&[Some(100.0), Some(50.0)], &[4.0, 8.0]),
+            Some(vec![100.0, 50.0])
+        );
+    }
+
+    #[test]
+    fn multi_session_executes_a_split_kernel_operation() {
+        let multi = MultiSession::new(testing::get_sessions(SRC)).unwrap();
+        let data: Vec<i32> = (0..1024).collect();
+        let buffer = multi.create_buffer::<i32, &[i32]>(&data[..]).unwrap();
+        let op = KernelOperation::new("add_one")
+            .with_dims(1024)
+            .add_split_arg(&buffer);
+        let partitions = multi.execute_sync_kernel_operation(op).unwrap();
+        let covered: usize = partitions.iter().map(|p| p.len).sum();
+        assert_eq!(covered, 1024);
+
+        let output = multi.sessions()[0]
+            .sync_read_buffer(&buffer, vec![0i32; 1024], None)
+            .unwrap()
+            .unwrap();
+        let expected: Vec<i32> = (1..1025).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn multi_session_records_each_partition_with_the_sessions_profiler() {
+        use crate::{CommandKind, Profiler};
+
+        let profiler = Profiler::new();
+        let sessions = testing::get_sessions(SRC)
+            .into_iter()
+            .map(|session| session.with_profiler(profiler.clone()))
+            .collect();
+        let multi = MultiSession::new(sessions).unwrap();
+        let buffer = multi.create_buffer::<i32, usize>(1024).unwrap();
+        let op = KernelOperation::new("add_one")
+            .with_dims(1024)
+            .add_split_arg(&buffer);
+        let partitions = multi.execute_sync_kernel_operation(op).unwrap();
+
+        let records = profiler.records();
+        assert_eq!(records.len(), partitions.len());
+        for record in records.iter() {
+            assert_eq!(record.kind, CommandKind::Kernel);
+            assert_eq!(record.name, "add_one");
+        }
+    }
+
+    #[test]
+    fn multi_session_passes_buffer_args_that_are_not_split_to_every_device() {
+        let multi = MultiSession::new(testing::get_sessions(SRC)).unwrap();
+        let first = multi.create_buffer::<i32, &[i32]>(&[7][..]).unwrap();
+        let buffer = multi.create_buffer::<i32, usize>(1024).unwrap();
+        let op = KernelOperation::new("copy_first")
+            .with_dims(1024)
+            .add_split_arg(&buffer)
+            .add_arg(&first);
+        multi.execute_sync_kernel_operation(op).unwrap();
+        let output = multi.sessions()[0]
+            .sync_read_buffer(&buffer, vec![0i32; 1024], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(output, vec![7; 1024]);
+    }
+
+    #[test]
+    fn multi_session_rejects_a_split_buffer_that_is_also_passed_whole() {
+        let multi = MultiSession::new(testing::get_sessions(SRC)).unwrap();
+        let buffer = multi.create_buffer::<i32, usize>(1024).unwrap();
+        let op = KernelOperation::new("copy_first")
+            .with_dims(1024)
+            .add_split_arg(&buffer)
+            .add_arg(&buffer);
+        let err = multi.execute_sync_kernel_operation(op).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<MultiSessionError>(),
+            Some(&MultiSessionError::SplitBufferIsShared)
+        );
+    }
+
+    #[test]
+    fn multi_session_rejects_a_global_offset() {
+        let multi = MultiSession::new(testing::get_sessions(SRC)).unwrap();
+        let buffer = multi.create_buffer::<i32, usize>(1024).unwrap();
+        let op = KernelOperation::new("add_one")
+            .with_work(Work::new(1000).with_global_offset(24))
+            .add_split_arg(&buffer);
+        let err = multi.execute_sync_kernel_operation(op).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<MultiSessionError>(),
+            Some(&MultiSessionError::GlobalOffsetNotSupported)
+        );
+    }
+
+    #[test]
+    fn multi_session_rejects_invalid_work_sizes() {
+        let multi = MultiSession::new(testing::get_sessions(SRC)).unwrap();
+        let buffer = multi.create_buffer::<i32, usize>(1024).unwrap();
+        for work in vec![
+            Work::new(0),
+            Work::new(1024).with_local_size(0),
+            Work::new(1024).with_local_size(48),
+        ] {
+            let op = KernelOperation::new("add_one")
+                .with_work(work)
+                .add_split_arg(&buffer);
+            let err = multi.execute_sync_kernel_operation(op).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<WorkError>(),
+                Some(&WorkError::InvalidWorkSize)
+            );
+        }
+    }
+
+    #[test]
+    fn multi_session_can_partition_by_throughput() {
+        let multi = MultiSession::new(testing::get_sessions(SRC))
+            .unwrap()
+            .with_strategy(PartitionStrategy::Throughput);
+        let buffer = multi.create_buffer::<i32, usize>(256).unwrap();
+        for _ in 0..2 {
+            let op = KernelOperation::new("add_one")
+                .with_dims(256)
+                .add_split_arg(&buffer);
+            multi.execute_sync_kernel_operation(op).unwrap();
+        }
+        assert_eq!(multi.weights().unwrap().len(), multi.sessions().len());
+    }
+}
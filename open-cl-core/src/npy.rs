@@ -0,0 +1,416 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::ll::ErrorT;
+use crate::{Buffer, BufferRect, ClTypeName, Dims, Number, Output, Session};
+
+/// An error related to reading or writing .npy files.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum NpyError {
+    #[error("The number type {0} has no npy dtype")]
+    UnsupportedType(String),
+
+    #[error("Invalid npy header: {0}")]
+    InvalidHeader(String),
+
+    #[error("Expected an npy file of {0} but the file is {1}")]
+    TypeMismatch(String, String),
+
+    #[error("Shape {0:?} has {1} items but the Buffer has {2} items")]
+    ShapeMismatch(Vec<usize>, usize, usize),
+
+    #[error("Fortran ordered npy files are not supported")]
+    FortranOrder,
+}
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// The number of bytes moved between the file and the device at a time, so that buffers
+/// larger than host memory can be saved and loaded.
+const NPY_CHUNK_BYTES: usize = 1 << 26;
+
+/// The header of an .npy file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NpyHeader {
+    /// The dtype of the array (e.g. `<f4`).
+    pub descr: String,
+    pub fortran_order: bool,
+    pub shape: Vec<usize>,
+}
+
+impl NpyHeader {
+    /// The header of a C ordered array of T. Vector types get a trailing dimension of
+    /// their width (4 for 3 component vectors, which are padded to 4 components).
+    pub fn new<T: Number + ClTypeName>(shape: &[usize]) -> Output<NpyHeader> {
+        let (descr, width) = npy_dtype::<T>()?;
+        let mut shape = shape.to_vec();
+        if width > 1 {
+            shape.push(width);
+        }
+        Ok(NpyHeader {
+            descr,
+            fortran_order: false,
+            shape,
+        })
+    }
+
+    pub fn n_items(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// Writes the magic string, version and header padded to a multiple of 64 bytes.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Output<()> {
+        let shape = match self.shape.len() {
+            1 => format!("({},)", self.shape[0]),
+            _ => {
+                let dims: Vec<String> = self.shape.iter().map(|n| n.to_string()).collect();
+                format!("({})", dims.join(", "))
+            }
+        };
+        let fortran_order = if self.fortran_order { "True" } else { "False" };
+        let mut dict = format!(
+            "{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}",
+            self.descr, fortran_order, shape
+        );
+        let unpadded = NPY_MAGIC.len() + 2 + 2 + dict.len() + 1;
+        let padding = (64 - unpadded % 64) % 64;
+        dict.extend(std::iter::repeat(' ').take(padding));
+        dict.push('\n');
+        if dict.len() > u16::max_value() as usize {
+            return Err(NpyError::InvalidHeader(format!(
+                "the header is {} bytes long",
+                dict.len()
+            )))?;
+        }
+        writer.write_all(NPY_MAGIC)?;
+        writer.write_all(&[1, 0])?;
+        writer.write_all(&(dict.len() as u16).to_le_bytes())?;
+        writer.write_all(dict.as_bytes())?;
+        Ok(())
+    }
+
+    /// Reads the magic string, version and header of any npy format version.
+    pub fn read_from<R: Read>(reader: &mut R) -> Output<NpyHeader> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic[..6] != NPY_MAGIC {
+            return Err(NpyError::InvalidHeader("missing magic string".to_string()))?;
+        }
+        let header_len = match magic[6] {
+            1 => {
+                let mut len = [0u8; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_le_bytes(len) as usize
+            }
+            2 | 3 => {
+                let mut len = [0u8; 4];
+                reader.read_exact(&mut len)?;
+                u32::from_le_bytes(len) as usize
+            }
+            version => {
+                return Err(NpyError::InvalidHeader(format!(
+                    "unknown version {}",
+                    version
+                )))?
+            }
+        };
+        let mut dict = vec![0u8; header_len];
+        reader.read_exact(&mut dict[..])?;
+        let dict = String::from_utf8_lossy(&dict[..]);
+        NpyHeader::parse(&dict)
+    }
+
+    fn parse(dict: &str) -> Output<NpyHeader> {
+        let descr = dict_value(dict, "descr")?
+            .trim_matches(|c| c == '\'' || c == '"')
+            .to_string();
+        let fortran_order = match dict_value(dict, "fortran_order")? {
+            "True" => true,
+            "False" => false,
+            other => {
+                return Err(NpyError::InvalidHeader(format!(
+                    "fortran_order is {}",
+                    other
+                )))?
+            }
+        };
+        let shape_value = dict_value(dict, "shape")?;
+        let shape = shape_value
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split(',')
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+            .map(|n| {
+                n.parse::<usize>()
+                    .map_err(|_| NpyError::InvalidHeader(format!("invalid shape {}", shape_value)))
+            })
+            .collect::<Result<Vec<usize>, NpyError>>()?;
+        Ok(NpyHeader {
+            descr,
+            fortran_order,
+            shape,
+        })
+    }
+}
+
+/// The value of `key` in the Python dict literal of an npy header.
+fn dict_value<'a>(dict: &'a str, key: &str) -> Output<&'a str> {
+    let missing = || NpyError::InvalidHeader(format!("missing key {}", key));
+    let quoted = format!("'{}'", key);
+    let start = dict.find(&quoted[..]).ok_or_else(missing)? + quoted.len();
+    let rest = dict[start..]
+        .trim_start()
+        .trim_start_matches(':')
+        .trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|i| i + 1)
+    } else if rest.starts_with('\'') {
+        rest[1..].find('\'').map(|i| i + 2)
+    } else {
+        rest.find(|c| c == ',' || c == '}')
+    };
+    Ok(rest[..end.ok_or_else(missing)?].trim())
+}
+
+/// The npy dtype of the scalar of a number type and the number of scalars per item.
+pub fn npy_dtype<T: Number + ClTypeName>() -> Output<(String, usize)> {
+    let cl_name = T::CL_TYPE_NAME;
+    let scalar = cl_name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let (kind, scalar_size) = match scalar {
+        "char" => ('i', 1),
+        "uchar" => ('u', 1),
+        "short" => ('i', 2),
+        "ushort" => ('u', 2),
+        "int" => ('i', 4),
+        "uint" => ('u', 4),
+        "long" => ('i', 8),
+        "ulong" => ('u', 8),
+        "ptrdiff_t" => ('i', std::mem::size_of::<isize>()),
+        "size_t" => ('u', std::mem::size_of::<usize>()),
+        "half" => ('f', 2),
+        "float" => ('f', 4),
+        "double" => ('f', 8),
+        _ => return Err(NpyError::UnsupportedType(cl_name.to_string()))?,
+    };
+    let byte_order = if scalar_size == 1 {
+        '|'
+    } else if cfg!(target_endian = "little") {
+        '<'
+    } else {
+        '>'
+    };
+    let width = std::mem::size_of::<T>() / scalar_size;
+    Ok((format!("{}{}{}", byte_order, kind, scalar_size), width))
+}
+
+fn as_bytes<T: Number>(data: &[T]) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(
+            data.as_ptr() as *const u8,
+            data.len() * std::mem::size_of::<T>(),
+        )
+    }
+}
+
+fn as_bytes_mut<T: Number>(data: &mut [T]) -> &mut [u8] {
+    unsafe {
+        std::slice::from_raw_parts_mut(
+            data.as_mut_ptr() as *mut u8,
+            data.len() * std::mem::size_of::<T>(),
+        )
+    }
+}
+
+/// The rect of `len` items starting at item `start` of a buffer.
+fn chunk_rect<T: Number>(start: usize, len: usize) -> BufferRect {
+    let item_size = std::mem::size_of::<T>();
+    let bytes = len * item_size;
+    BufferRect {
+        buffer_origin: [start * item_size, 0, 0],
+        host_origin: [0, 0, 0],
+        region: [bytes, 1, 1],
+        buffer_row_pitch: bytes,
+        buffer_slice_pitch: bytes,
+        host_row_pitch: bytes,
+        host_slice_pitch: bytes,
+    }
+}
+
+fn chunk_len<T: Number>(n_items: usize) -> usize {
+    (NPY_CHUNK_BYTES / std::mem::size_of::<T>())
+        .max(1)
+        .min(n_items)
+}
+
+fn dims_shape(dims: Dims) -> Vec<usize> {
+    match dims {
+        Dims::One(x) => vec![x],
+        Dims::Two(x, y) => vec![x, y],
+        Dims::Three(x, y, z) => vec![x, y, z],
+    }
+}
+
+impl Session {
+    /// Writes the buffer to an .npy file with a 1 dimensional shape.
+    ///
+    /// The buffer is read in chunks so it never has to fit in host memory at once.
+    pub fn save_npy<T: Number + ClTypeName, P: AsRef<Path>>(
+        &self,
+        buffer: &Buffer<T>,
+        path: P,
+    ) -> Output<()> {
+        self.save_npy_with_dims(buffer, buffer.len(), path)
+    }
+
+    /// Writes the buffer to an .npy file with the shape of `dims` in C order.
+    pub fn save_npy_with_dims<T, D, P>(&self, buffer: &Buffer<T>, dims: D, path: P) -> Output<()>
+    where
+        T: Number + ClTypeName,
+        D: Into<Dims>,
+        P: AsRef<Path>,
+    {
+        let dims = dims.into();
+        let shape = dims_shape(dims.clone());
+        if dims.n_items() != buffer.len() {
+            return Err(NpyError::ShapeMismatch(shape, dims.n_items(), buffer.len()))?;
+        }
+        let header = NpyHeader::new::<T>(&shape[..])?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        header.write_to(&mut writer)?;
+        let n_items = buffer.len();
+        let mut chunk = vec![T::zero(); chunk_len::<T>(n_items)];
+        let mut start = 0;
+        while start < n_items {
+            let len = chunk.len().min(n_items - start);
+            self.sync_read_buffer_rect(buffer, &mut chunk[..len], &chunk_rect::<T>(start, len))?;
+            writer.write_all(as_bytes(&chunk[..len]))?;
+            start += len;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Creates a buffer from an .npy file of T.
+    ///
+    /// The file is written to the buffer in chunks so it never has to fit in host memory
+    /// at once.
+    pub fn load_npy<T: Number + ClTypeName, P: AsRef<Path>>(&self, path: P) -> Output<Buffer<T>> {
+        let (buffer, _shape) = self.load_npy_with_shape::<T, P>(path)?;
+        Ok(buffer)
+    }
+
+    /// Creates a buffer from an .npy file of T with at most 3 dimensions (not counting the
+    /// trailing dimension of vector types) and returns the shape as Dims.
+    pub fn load_npy_with_dims<T: Number + ClTypeName, P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Output<(Buffer<T>, Dims)> {
+        let (buffer, shape) = self.load_npy_with_shape::<T, P>(path)?;
+        let dims = match shape[..] {
+            [] => Dims::One(1),
+            [x] => Dims::One(x),
+            [x, y] => Dims::Two(x, y),
+            [x, y, z] => Dims::Three(x, y, z),
+            _ => {
+                return Err(NpyError::InvalidHeader(format!(
+                    "shape {:?} has more than 3 dimensions",
+                    shape
+                )))?
+            }
+        };
+        Ok((buffer, dims))
+    }
+
+    fn load_npy_with_shape<T: Number + ClTypeName, P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Output<(Buffer<T>, Vec<usize>)> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let header = NpyHeader::read_from(&mut reader)?;
+        if header.fortran_order && header.shape.iter().filter(|&&n| n > 1).count() > 1 {
+            return Err(NpyError::FortranOrder)?;
+        }
+        let (descr, width) = npy_dtype::<T>()?;
+        let mut shape = header.shape.clone();
+        let found_width = if width > 1 { shape.pop() } else { Some(1) };
+        if header.descr != descr || found_width != Some(width) {
+            return Err(NpyError::TypeMismatch(
+                format!("{} with a trailing dimension of {}", descr, width),
+                format!("{} with shape {:?}", header.descr, header.shape),
+            ))?;
+        }
+        let n_items: usize = shape.iter().product();
+        let buffer = self.create_buffer::<T, usize>(n_items)?;
+        let mut chunk = vec![T::zero(); chunk_len::<T>(n_items)];
+        let mut start = 0;
+        while start < n_items {
+            let len = chunk.len().min(n_items - start);
+            reader.read_exact(as_bytes_mut(&mut chunk[..len]))?;
+            self.sync_write_buffer_rect(&buffer, &chunk[..len], &chunk_rect::<T>(start, len))?;
+            start += len;
+        }
+        Ok((buffer, shape))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{npy_dtype, NpyError, NpyHeader};
+    use crate::number_types::{Float3, Half, Int4};
+    use crate::{testing, Dims};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("open_cl_core_{}_{}.npy", name, std::process::id()))
+    }
+
+    #[test]
+    fn npy_dtypes_are_mapped_from_number_types() {
+        assert_eq!(npy_dtype::<f32>().unwrap(), ("<f4".to_string(), 1));
+        assert_eq!(npy_dtype::<u8>().unwrap(), ("|u1".to_string(), 1));
+        assert_eq!(npy_dtype::<Half>().unwrap(), ("<f2".to_string(), 1));
+        assert_eq!(npy_dtype::<Int4>().unwrap(), ("<i4".to_string(), 4));
+        assert_eq!(npy_dtype::<Float3>().unwrap(), ("<f4".to_string(), 4));
+    }
+
+    #[test]
+    fn npy_headers_round_trip() {
+        let header = NpyHeader::new::<Int4>(&[2, 3]).unwrap();
+        assert_eq!(header.shape, vec![2, 3, 4]);
+        let mut bytes = vec![];
+        header.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len() % 64, 0);
+        assert_eq!(bytes.last(), Some(&b'\n'));
+        assert_eq!(NpyHeader::read_from(&mut &bytes[..]).unwrap(), header);
+
+        let numpy = b"{'descr': '<f8', 'fortran_order': False, 'shape': (7,), }";
+        let header = NpyHeader::parse(&String::from_utf8_lossy(numpy)).unwrap();
+        assert_eq!(header.descr, "<f8");
+        assert_eq!(header.shape, vec![7]);
+    }
+
+    #[test]
+    fn session_saves_and_loads_npy_files() {
+        let session = testing::get_noop_session();
+        let data: Vec<f32> = (0..12).map(|i| i as f32 * 0.5).collect();
+        let buffer = session.create_buffer::<f32, &[f32]>(&data[..]).unwrap();
+        let path = temp_path("save_load");
+        session.save_npy_with_dims(&buffer, (3, 4), &path).unwrap();
+
+        let (loaded, dims) = session.load_npy_with_dims::<f32, _>(&path).unwrap();
+        assert_eq!(dims, Dims::Two(3, 4));
+        let loaded_data = session
+            .sync_read_buffer(&loaded, vec![0.0f32; 12], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded_data, data);
+
+        let err = session.load_npy::<i32, _>(&path).unwrap_err();
+        match err.downcast_ref::<NpyError>() {
+            Some(NpyError::TypeMismatch(..)) => (),
+            other => panic!("expected a TypeMismatch, got {:?}", other),
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
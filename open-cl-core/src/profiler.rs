@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::ll::Profiling;
+use crate::Output;
+
+/// The kind of a command recorded by a Profiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommandKind {
+    Kernel,
+    ReadBuffer,
+    WriteBuffer,
+    MigrateMems,
+}
+
+impl CommandKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommandKind::Kernel => "kernel",
+            CommandKind::ReadBuffer => "read_buffer",
+            CommandKind::WriteBuffer => "write_buffer",
+            CommandKind::MigrateMems => "migrate_mems",
+        }
+    }
+}
+
+/// A completed command and its profiling info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfiledCommand {
+    pub kind: CommandKind,
+    /// The kernel name for Kernel commands, otherwise the name of the kind.
+    pub name: String,
+    /// The number of bytes moved by the command, if the command moves bytes.
+    pub bytes: Option<usize>,
+    /// The address of the command queue the command was enqueued on.
+    pub queue: String,
+    /// The name of the device of the command queue.
+    pub device: String,
+    pub profiling: Profiling,
+}
+
+impl ProfiledCommand {
+    pub fn duration(&self) -> Option<Duration> {
+        self.profiling.duration_of_execution()
+    }
+}
+
+/// Aggregate execution durations of one kernel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelStats {
+    pub name: String,
+    pub count: usize,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+/// An opt-in recorder of the commands executed by a Session.
+///
+/// A Profiler is cheap to clone; clones share one recording. Attach a Profiler with
+/// `Session::with_profiler`. The Session's command queue must have PROFILING_ENABLE
+/// (the default) for the commands to have timings.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    records: Arc<Mutex<Vec<ProfiledCommand>>>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    pub fn record(&self, command: ProfiledCommand) {
+        self.records.lock().unwrap().push(command);
+    }
+
+    /// A copy of the recorded commands in the order they were recorded.
+    pub fn records(&self) -> Vec<ProfiledCommand> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    /// Execution duration stats for each recorded kernel, sorted by kernel name.
+    /// Kernels without timings are skipped.
+    pub fn kernel_stats(&self) -> Vec<KernelStats> {
+        let mut durations: HashMap<String, Vec<Duration>> = HashMap::new();
+        for command in self.records.lock().unwrap().iter() {
+            if command.kind != CommandKind::Kernel {
+                continue;
+            }
+            if let Some(duration) = command.duration() {
+                durations
+                    .entry(command.name.clone())
+                    .or_insert_with(Vec::new)
+                    .push(duration);
+            }
+        }
+        let mut stats: Vec<KernelStats> = durations
+            .into_iter()
+            .map(|(name, mut durations)| {
+                durations.sort();
+                let count = durations.len();
+                let total: Duration = durations.iter().sum();
+                let p95_index = ((count as f64 * 0.95).ceil() as usize).max(1) - 1;
+                KernelStats {
+                    name,
+                    count,
+                    min: durations[0],
+                    mean: total / count as u32,
+                    p95: durations[p95_index],
+                    max: durations[count - 1],
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        stats
+    }
+
+    /// Renders the recording as Chrome trace-event JSON for `chrome://tracing` or Perfetto.
+    ///
+    /// Each device is a process and each command queue is a thread. Times are in
+    /// microseconds relative to the earliest recorded start time. Commands without
+    /// timings are skipped.
+    pub fn to_chrome_trace_json(&self) -> String {
+        let records = self.records.lock().unwrap();
+        let timed: Vec<(&ProfiledCommand, u64, u64)> = records
+            .iter()
+            .filter_map(|c| Some((c, c.profiling.start_time?, c.profiling.end_time?)))
+            .collect();
+        let origin = timed.iter().map(|(_, start, _)| *start).min().unwrap_or(0);
+
+        let mut devices: Vec<&str> = Vec::new();
+        let mut queues: Vec<(&str, &str)> = Vec::new();
+        let mut events: Vec<String> = Vec::new();
+        for (command, start, end) in timed.iter() {
+            let pid = index_of_or_push(&mut devices, command.device.as_str());
+            let tid = index_of_or_push(
+                &mut queues,
+                (command.device.as_str(), command.queue.as_str()),
+            );
+            let mut args = format!("\"queue\":\"{}\"", escape_json(&command.queue));
+            if let Some(bytes) = command.bytes {
+                write!(args, ",\"bytes\":{}", bytes).unwrap();
+            }
+            events.push(format!(
+                "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":{},\"tid\":{},\"args\":{{{}}}}}",
+                escape_json(&command.name),
+                command.kind.as_str(),
+                (start - origin) as f64 / 1000.0,
+                end.saturating_sub(*start) as f64 / 1000.0,
+                pid,
+                tid,
+                args
+            ));
+        }
+        for (pid, device) in devices.iter().enumerate() {
+            events.push(format!(
+                "{{\"name\":\"process_name\",\"ph\":\"M\",\"pid\":{},\"args\":{{\"name\":\"{}\"}}}}",
+                pid,
+                escape_json(device)
+            ));
+        }
+        for (tid, (device, queue)) in queues.iter().enumerate() {
+            let pid = devices.iter().position(|d| d == device).unwrap();
+            events.push(format!(
+                "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":{},\"args\":{{\"name\":\"queue {}\"}}}}",
+                pid,
+                tid,
+                escape_json(queue)
+            ));
+        }
+        format!(
+            "{{\"traceEvents\":[{}],\"displayTimeUnit\":\"ns\"}}",
+            events.join(",")
+        )
+    }
+
+    /// Writes the Chrome trace-event JSON of the recording to the writer.
+    pub fn write_chrome_trace<W: Write>(&self, mut writer: W) -> Output<()> {
+        writer.write_all(self.to_chrome_trace_json().as_bytes())?;
+        Ok(())
+    }
+}
+
+fn index_of_or_push<T: PartialEq>(items: &mut Vec<T>, item: T) -> usize {
+    match items.iter().position(|i| *i == item) {
+        Some(index) => index,
+        None => {
+            items.push(item);
+            items.len() - 1
+        }
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kernel_command(name: &str, start: u64, end: u64) -> ProfiledCommand {
+        ProfiledCommand {
+            kind: CommandKind::Kernel,
+            name: name.to_string(),
+            bytes: None,
+            queue: "0x1".to_string(),
+            device: "Device \"A\"".to_string(),
+            profiling: Profiling {
+                queue_time: Some(start),
+                submit_time: Some(start),
+                start_time: Some(start),
+                end_time: Some(end),
+            },
+        }
+    }
+
+    #[test]
+    fn profiler_computes_kernel_stats() {
+        let profiler = Profiler::new();
+        for i in 1..=20u64 {
+            profiler.record(kernel_command("add_one", 1000 * i, 1000 * i + i));
+        }
+        profiler.record(kernel_command("add_two", 0, 50));
+        let stats = profiler.kernel_stats();
+        assert_eq!(stats.len(), 2);
+        let add_one = &stats[0];
+        assert_eq!(add_one.name, "add_one");
+        assert_eq!(add_one.count, 20);
+        assert_eq!(add_one.min, Duration::from_nanos(1));
+        assert_eq!(add_one.max, Duration::from_nanos(20));
+        assert_eq!(add_one.p95, Duration::from_nanos(19));
+        assert_eq!(add_one.mean, Duration::from_nanos(10));
+        assert_eq!(stats[1].count, 1);
+    }
+
+    #[test]
+    fn profiler_renders_chrome_trace_json() {
+        let profiler = Profiler::new();
+        profiler.record(kernel_command("add_one", 5000, 7000));
+        let mut write = kernel_command("write_buffer", 2000, 3000);
+        write.kind = CommandKind::WriteBuffer;
+        write.bytes = Some(64);
+        profiler.record(write);
+        let json = profiler.to_chrome_trace_json();
+        assert!(json.starts_with("{\"traceEvents\":["));
+        assert!(json.contains(
+            "{\"name\":\"add_one\",\"cat\":\"kernel\",\"ph\":\"X\",\"ts\":3.000,\"dur\":2.000"
+        ));
+        assert!(json.contains("\"cat\":\"write_buffer\""));
+        assert!(json.contains("\"bytes\":64"));
+        assert!(json.contains("\"args\":{\"name\":\"Device \\\"A\\\"\"}"));
+    }
+
+    #[test]
+    fn profiler_clones_share_a_recording() {
+        let profiler = Profiler::new();
+        let other = profiler.clone();
+        other.record(kernel_command("add_one", 0, 1));
+        assert_eq!(profiler.len(), 1);
+        profiler.clear();
+        assert!(other.is_empty());
+    }
+}
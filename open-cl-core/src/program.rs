@@ -1,9 +1,15 @@
 use std::fmt;
+use std::future::Future;
 use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::task::{self, Poll};
 
 use crate::ll::cl::ClObject;
 
-use crate::ll::{Context as ClContext, ContextPtr, Program as ClProgram, ProgramPtr};
+use crate::ll::{
+    Context as ClContext, ContextPtr, PendingBuild, Program as ClProgram, ProgramBuildError,
+    ProgramPtr,
+};
 use crate::{Context, Device, Output};
 
 pub struct UnbuiltProgram {
@@ -78,6 +84,18 @@ impl UnbuiltProgram {
         }
     }
 
+    /// Creates an UnbuiltProgram from an intermediate language (SPIR-V) module.
+    #[cfg(feature = "opencl_version_2_1_0")]
+    pub fn create_with_il(context: &Context, il: &[u8]) -> Output<UnbuiltProgram> {
+        unsafe {
+            let ll_prog = ClProgram::create_with_il(context.low_level_context(), il)?;
+            Ok(UnbuiltProgram::new(
+                ll_prog,
+                context.low_level_context().clone(),
+            ))
+        }
+    }
+
     pub fn build(mut self, devices: &[Device]) -> Output<Program> {
         let built_prog: Program = unsafe {
             self.inner.build(devices)?;
@@ -100,6 +118,66 @@ impl UnbuiltProgram {
         std::mem::forget(self);
         Ok(built_prog)
     }
+
+    /// Starts building the program for the devices with the given build options without
+    /// waiting for the build to complete. Several programs can be built in parallel by
+    /// starting all their builds before waiting on (or awaiting) any of them.
+    pub fn build_async(self, devices: &[Device], options: &str) -> Output<PendingProgram> {
+        let inner = self.inner.build_async(devices, options)?;
+        let context = Context::from_low_level_context(&self.context)?;
+        Ok(PendingProgram {
+            inner,
+            context,
+            devices: devices.to_vec(),
+        })
+    }
+}
+
+/// A Program whose build was started by `UnbuiltProgram::build_async`. It resolves to the
+/// Program via `wait` or as a Future once the build has completed.
+pub struct PendingProgram {
+    inner: PendingBuild,
+    context: Context,
+    devices: Vec<Device>,
+}
+
+impl PendingProgram {
+    /// Whether the build has completed, successfully or not.
+    pub fn is_complete(&self) -> bool {
+        self.inner.is_complete()
+    }
+
+    /// Blocks until the build has completed.
+    pub fn wait(self) -> Result<Program, ProgramBuildError> {
+        let PendingProgram {
+            inner,
+            context,
+            devices,
+        } = self;
+        let ll_program = inner.wait()?;
+        Ok(unsafe { Program::new(ll_program, context, devices) })
+    }
+}
+
+impl Future for PendingProgram {
+    type Output = Result<Program, ProgramBuildError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.inner).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Ready(Ok(ll_program)) => {
+                let (context, devices) = (self.context.clone(), self.devices.clone());
+                Poll::Ready(Ok(unsafe { Program::new(ll_program, context, devices) }))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for PendingProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PendingProgram{{complete: {}}}", self.is_complete())
+    }
 }
 
 pub struct Program {
@@ -122,6 +200,11 @@ impl Program {
         UnbuiltProgram::create_with_binary(context, device, binary)
     }
 
+    #[cfg(feature = "opencl_version_2_1_0")]
+    pub fn create_with_il(context: &Context, il: &[u8]) -> Output<UnbuiltProgram> {
+        UnbuiltProgram::create_with_il(context, il)
+    }
+
     pub unsafe fn new(object: ClProgram, context: Context, devices: Vec<Device>) -> Program {
         Program {
             inner: ManuallyDrop::new(object),
@@ -251,6 +334,49 @@ mod tests {
     }
     ";
 
+    #[test]
+    fn unbuilt_programs_build_in_parallel_with_build_async() {
+        let context = testing::get_context();
+        let devices: Vec<Device> = context
+            .devices()
+            .iter()
+            .map(|d| Device::new(d.clone()))
+            .collect();
+        let pending: Vec<PendingProgram> = (0..3)
+            .map(|_| {
+                UnbuiltProgram::create_with_source(&context, SRC)
+                    .unwrap()
+                    .build_async(&devices[..], "-cl-fast-relaxed-math")
+                    .unwrap()
+            })
+            .collect();
+        for p in pending.into_iter() {
+            let program: Program = p.wait().unwrap();
+            assert_eq!(program.kernel_names().unwrap(), vec!["test".to_string()]);
+            assert_eq!(program.devices().len(), devices.len());
+        }
+    }
+
+    #[test]
+    fn build_async_reports_build_failures() {
+        let context = testing::get_context();
+        let devices: Vec<Device> = context
+            .devices()
+            .iter()
+            .map(|d| Device::new(d.clone()))
+            .collect();
+        let err = UnbuiltProgram::create_with_source(&context, "__kernel void test( {")
+            .unwrap()
+            .build_async(&devices[..], "")
+            .unwrap()
+            .wait()
+            .unwrap_err();
+        match err {
+            ProgramBuildError::BuildFailed(logs) => assert_eq!(logs.len(), devices.len()),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
     #[test]
     fn program_method_reference_count_works() {
         let program: Program = testing::get_program(SRC);
@@ -1,17 +1,26 @@
+use std::collections::HashMap;
 use std::mem::ManuallyDrop;
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use crate::{
-    Buffer, BufferBuilder, CommandQueueOptions, CommandQueueProperties, Context, Device, KernelArg,
-    KernelOperation, Mem, MemConfig, MutVecOrSlice, Number, NumberTyped, NumberTypedT, Output,
-    Platform, Program, VecOrSlice, Waitlist,
+    Buffer, BufferBuilder, BufferRect, ClTypeName, CommandQueueOptions, CommandQueueProperties,
+    Context, Device, KernelArg, KernelArgPtr, KernelOperation, KernelTemplate, Mem, MemConfig,
+    MemMigrationFlags, MemPtr, MutVecOrSlice, Number, NumberType, NumberTyped, NumberTypedT,
+    Output, Platform, Program, UntypedBuffer, VecOrSlice, Waitlist,
 };
+use crate::{CommandKind, HasDeviceInfo, ProfiledCommand, Profiler};
 
 use crate::ll::cl::ClObject;
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::ll::cl::{MapFlags, SvmMemFlags};
 use crate::ll::{
     BufferReadEvent, CommandQueue as ClCommandQueue, CommandQueuePtr, Context as ClContext,
-    Device as ClDeviceID, DevicePtr, Event as ClEvent, Kernel as ClKernel, Program as ClProgram,
+    Device as ClDeviceID, DeviceError, DevicePtr, Event as ClEvent, Kernel as ClKernel, Profiling,
+    Program as ClProgram,
 };
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::ll::{Pipe, SvmBuffer};
 
 #[derive(Debug)]
 pub struct Session {
@@ -19,6 +28,16 @@ pub struct Session {
     _program: ManuallyDrop<ClProgram>,
     _context: ManuallyDrop<ClContext>,
     _queue: ManuallyDrop<Arc<RwLock<ClCommandQueue>>>,
+    _profiler: Option<Profiler>,
+    // Programs built from rendered KernelTemplates keyed by their rendered source.
+    _templates: Arc<Mutex<HashMap<String, ClProgram>>>,
+    // Whether the device supports cl_khr_fp16; queried the first time a Half type is used.
+    _supports_half: Arc<Mutex<Option<bool>>>,
+    // The device's name; queried the first time a command is recorded by a Profiler.
+    _device_name: Arc<Mutex<Option<String>>>,
+    // Commands enqueued on the queue that have not completed. Only counted for the
+    // Sessions of a SessionPool; shared by the clones of the Session, which share its queue.
+    _in_flight: Option<Arc<AtomicUsize>>,
 }
 
 unsafe impl Send for Session {}
@@ -48,6 +67,11 @@ impl Session {
                     _context: ManuallyDrop::new(context.clone()),
                     _program: ManuallyDrop::new(program.clone()),
                     _queue: ManuallyDrop::new(Arc::new(RwLock::new(queue))),
+                    _profiler: None,
+                    _templates: Arc::new(Mutex::new(HashMap::new())),
+                    _supports_half: Arc::new(Mutex::new(None)),
+                    _device_name: Arc::new(Mutex::new(None)),
+                    _in_flight: None,
                 };
                 sessions.push(session);
             }
@@ -108,20 +132,115 @@ impl Session {
             _context: cloned_context,
             _program: cloned_program,
             _queue: ManuallyDrop::new(Arc::new(RwLock::new(copied_queue))),
+            _profiler: self._profiler.clone(),
+            _templates: self._templates.clone(),
+            _supports_half: self._supports_half.clone(),
+            _device_name: self._device_name.clone(),
+            _in_flight: None,
         })
     }
 
+    /// Attaches a Profiler that records every command the Session executes.
+    pub fn with_profiler(mut self, profiler: Profiler) -> Session {
+        self._profiler = Some(profiler);
+        self
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self._profiler.as_ref()
+    }
+
+    pub(crate) fn record_command(
+        &self,
+        queue: &ClCommandQueue,
+        kind: CommandKind,
+        name: &str,
+        bytes: Option<usize>,
+        profiling: Profiling,
+    ) {
+        if let Some(profiler) = &self._profiler {
+            profiler.record(ProfiledCommand {
+                kind,
+                name: name.to_string(),
+                bytes,
+                queue: queue.address(),
+                device: self.device_name(),
+                profiling,
+            });
+        }
+    }
+
+    fn device_name(&self) -> String {
+        let mut device_name = self._device_name.lock().unwrap();
+        device_name
+            .get_or_insert_with(|| self.low_level_device().name().unwrap_or_default())
+            .clone()
+    }
+
+    /// The number of commands enqueued on the session's queue that have not completed.
+    /// Commands are only counted for the Sessions of a SessionPool; always 0 otherwise.
+    pub fn in_flight_commands(&self) -> usize {
+        self._in_flight
+            .as_ref()
+            .map_or(0, |in_flight| in_flight.load(Ordering::SeqCst))
+    }
+
+    /// Starts counting the commands the Session (and its clones) enqueue until they
+    /// complete. Used by SessionPool to balance its queues.
+    pub(crate) fn with_in_flight_tracking(mut self) -> Session {
+        self._in_flight = Some(Arc::new(AtomicUsize::new(0)));
+        self
+    }
+
+    /// Counts the event's command as in flight until the command completes. A command
+    /// whose completion callback cannot be registered is not counted.
+    pub(crate) fn track_in_flight(&self, event: &ClEvent) {
+        if let Some(in_flight) = &self._in_flight {
+            in_flight.fetch_add(1, Ordering::SeqCst);
+            let counter = in_flight.clone();
+            let registered = event.on_complete(move |_status| {
+                counter.fetch_sub(1, Ordering::SeqCst);
+            });
+            if registered.is_err() {
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
     // /// Creates a ClKernel from the session's program.
     // fn create_kernel(&self, kernel_name: &str) -> Output<ClKernel> {
     //     unsafe { Ok(Kernel::new(ll_kernel, self.program())) }
     // }
 
+    /// Checks that the session's device can use the number type. Half number types
+    /// require cl_khr_fp16; the device is only asked once per Session (and its clones).
+    fn check_number_type(&self, number_type: &NumberType) -> Output<()> {
+        if !number_type.is_half() {
+            return Ok(());
+        }
+        let mut supports_half = self._supports_half.lock().unwrap();
+        let supported = match *supports_half {
+            Some(supported) => supported,
+            None => *supports_half.get_or_insert(self.device().supports_half()?),
+        };
+        if !supported {
+            return Err(DeviceError::HalfNotSupported(
+                number_type.number_type_name(),
+            ))?;
+        }
+        Ok(())
+    }
+
     /// Creates a ClMem object in the given context, with the given buffer creator
     /// (either a length or some data). This function uses the BufferCreator's implementation
     /// to retrieve the appropriate MemConfig.
-    pub fn create_buffer<T: Number, B: BufferBuilder>(&self, buffer_creator: B) -> Output<Buffer> {
+    pub fn create_buffer<T: Number, B: BufferBuilder>(
+        &self,
+        buffer_creator: B,
+    ) -> Output<Buffer<T>> {
+        self.check_number_type(&T::number_type())?;
         let cfg = buffer_creator.mem_config();
-        Buffer::create_from_low_level_context::<T, B>(
+        Buffer::<T>::create_from_low_level_context::<B>(
             self.low_level_context(),
             buffer_creator,
             cfg.host_access(),
@@ -136,8 +255,9 @@ impl Session {
         &self,
         buffer_creator: B,
         mem_config: MemConfig,
-    ) -> Output<Buffer> {
-        Buffer::create_from_low_level_context::<T, B>(
+    ) -> Output<Buffer<T>> {
+        self.check_number_type(&T::number_type())?;
+        Buffer::<T>::create_from_low_level_context::<B>(
             self.low_level_context(),
             buffer_creator,
             mem_config.host_access(),
@@ -151,16 +271,28 @@ impl Session {
     /// operation.
     pub fn sync_write_buffer<'a, T: Number + NumberTypedT, H: Into<VecOrSlice<'a, T>>>(
         &self,
-        buffer: &Buffer,
+        buffer: &Buffer<T>,
         host_buffer: H,
         opts: Option<CommandQueueOptions>,
     ) -> Output<()> {
-        buffer.number_type().type_check(&T::number_type())?;
         let mut queue = self.write_queue();
         let mut buffer_lock = buffer.write_lock();
         unsafe {
             let event: ClEvent = queue.write_buffer(&mut (*buffer_lock), host_buffer, opts)?;
-            event.wait()
+            self.track_in_flight(&event);
+            event.wait()?;
+            if self._profiler.is_some() {
+                let name = CommandKind::WriteBuffer.as_str();
+                let bytes = buffer_lock.size().ok();
+                self.record_command(
+                    &queue,
+                    CommandKind::WriteBuffer,
+                    name,
+                    bytes,
+                    event.profiling(),
+                );
+            }
+            Ok(())
         }
     }
 
@@ -169,47 +301,300 @@ impl Session {
     /// be passed as mutable; I don't trust OpenCL.
     pub fn sync_read_buffer<'a, T: Number, H: Into<MutVecOrSlice<'a, T>>>(
         &self,
-        buffer: &Buffer,
+        buffer: &Buffer<T>,
         host_buffer: H,
         opts: Option<CommandQueueOptions>,
     ) -> Output<Option<Vec<T>>> {
-        buffer.number_type().type_check(&T::number_type())?;
         let mut queue = self.write_queue();
 
         let buffer_lock = buffer.read_lock();
         unsafe {
             let mut event: BufferReadEvent<T> =
                 queue.read_buffer(&(*buffer_lock), host_buffer, opts)?;
-            event.wait()
+            self.track_in_flight(event.event());
+            let output = event.wait()?;
+            if self._profiler.is_some() {
+                let name = CommandKind::ReadBuffer.as_str();
+                let bytes = buffer_lock.size().ok();
+                self.record_command(
+                    &queue,
+                    CommandKind::ReadBuffer,
+                    name,
+                    bytes,
+                    event.profiling(),
+                );
+            }
+            Ok(output)
+        }
+    }
+
+    /// Copies the rect region of the device mem buffer into a host slice.
+    pub fn sync_read_buffer_rect<T: Number>(
+        &self,
+        buffer: &Buffer<T>,
+        host_buffer: &mut [T],
+        rect: &BufferRect,
+    ) -> Output<()> {
+        let mut queue = self.write_queue();
+        let buffer_lock = buffer.read_lock();
+        unsafe {
+            let event: ClEvent =
+                queue.read_buffer_rect(&(*buffer_lock), host_buffer, rect, None)?;
+            self.track_in_flight(&event);
+            event.wait()?;
+            if self._profiler.is_some() {
+                let name = CommandKind::ReadBuffer.as_str();
+                self.record_command(
+                    &queue,
+                    CommandKind::ReadBuffer,
+                    name,
+                    Some(rect.byte_count()),
+                    event.profiling(),
+                );
+            }
+            Ok(())
+        }
+    }
+
+    /// Copies the rect region of a host slice into the device mem buffer.
+    pub fn sync_write_buffer_rect<T: Number>(
+        &self,
+        buffer: &Buffer<T>,
+        host_buffer: &[T],
+        rect: &BufferRect,
+    ) -> Output<()> {
+        let mut queue = self.write_queue();
+        let mut buffer_lock = buffer.write_lock();
+        unsafe {
+            let event: ClEvent =
+                queue.write_buffer_rect(&mut (*buffer_lock), host_buffer, rect, None)?;
+            self.track_in_flight(&event);
+            event.wait()?;
+            if self._profiler.is_some() {
+                let name = CommandKind::WriteBuffer.as_str();
+                self.record_command(
+                    &queue,
+                    CommandKind::WriteBuffer,
+                    name,
+                    Some(rect.byte_count()),
+                    event.profiling(),
+                );
+            }
+            Ok(())
         }
     }
 
-    pub fn execute_sync_kernel_operation<'a>(
+    /// Migrates the buffers to the session's device and waits for the migration to finish.
+    ///
+    /// Migrating a buffer before the first kernel that uses it lets the caller decide when
+    /// the transfer between devices happens.
+    pub fn migrate<B: AsRef<UntypedBuffer>>(&self, buffers: &[&B]) -> Output<()> {
+        self.migrate_with_flags(buffers, MemMigrationFlags::default(), None)
+    }
+
+    /// Migrates the buffers with the given flags and waits for the migration to finish.
+    /// Use MemMigrationFlags::HOST to migrate the buffers to the host.
+    pub fn migrate_with_flags<B: AsRef<UntypedBuffer>>(
         &self,
+        buffers: &[&B],
+        flags: MemMigrationFlags,
+        opts: Option<CommandQueueOptions>,
+    ) -> Output<()> {
+        let mut queue = self.write_queue();
+        let buffer_locks: Vec<RwLockReadGuard<Mem>> =
+            buffers.iter().map(|b| (*b).as_ref().read_lock()).collect();
+        let mems: Vec<&Mem> = buffer_locks.iter().map(|lock| &(**lock)).collect();
+        unsafe {
+            let event: ClEvent = queue.migrate_mems(&mems[..], flags, opts)?;
+            self.track_in_flight(&event);
+            event.wait()?;
+            if self._profiler.is_some() {
+                let name = CommandKind::MigrateMems.as_str();
+                let bytes = mems.iter().map(|m| m.size()).sum::<Output<usize>>().ok();
+                self.record_command(
+                    &queue,
+                    CommandKind::MigrateMems,
+                    name,
+                    bytes,
+                    event.profiling(),
+                );
+            }
+            Ok(())
+        }
+    }
+
+    /// Allocates a Shared Virtual Memory buffer of `len` T in the session's context.
+    #[cfg(feature = "opencl_version_2_0_0")]
+    pub fn create_svm_buffer<T: Number + NumberTypedT>(
+        &self,
+        len: usize,
+        flags: SvmMemFlags,
+    ) -> Output<SvmBuffer<T>> {
+        unsafe { SvmBuffer::create(self.low_level_context(), len, flags) }
+    }
+
+    /// Maps a coarse-grained SvmBuffer on the session's queue, passes the mapped
+    /// slice to the given function, and unmaps the buffer once the function returns
+    /// (or panics).
+    #[cfg(feature = "opencl_version_2_0_0")]
+    pub fn with_mapped_svm_buffer<T, F, R>(
+        &self,
+        buffer: &mut SvmBuffer<T>,
+        map_flags: MapFlags,
+        func: F,
+    ) -> Output<R>
+    where
+        T: Number,
+        F: FnOnce(&mut [T]) -> R,
+    {
+        unsafe {
+            let buffer: *mut SvmBuffer<T> = buffer;
+            let mapped = self
+                .write_queue()
+                .map_svm_buffer(&mut *buffer, map_flags, None)?;
+            let guard = SvmUnmapGuard {
+                session: self,
+                buffer,
+            };
+            let output = func(mapped);
+            let event: ClEvent = guard.unmap()?;
+            self.track_in_flight(&event);
+            event.wait()?;
+            Ok(output)
+        }
+    }
+
+    /// Creates a Pipe of `max_packets` packets of T in the session's context.
+    ///
+    /// Pass the Pipe to a kernel with `ll::KernelArg::from_pipe`.
+    #[cfg(feature = "opencl_version_2_0_0")]
+    pub fn create_pipe<T: Number + NumberTypedT>(&self, max_packets: u32) -> Output<Pipe<T>> {
+        unsafe { Pipe::create(self.low_level_context(), max_packets) }
+    }
+
+    /// Renders the KernelTemplate for T and builds it for the session's device.
+    ///
+    /// The built program is cached by its rendered source, so each template is only
+    /// built once per type. The cache is shared with clones and copies of the Session.
+    pub fn build_template<T: ClTypeName>(&self, template: &KernelTemplate) -> Output<Program> {
+        let program = self.template_program::<T>(template)?;
+        unsafe { Program::from_low_level_program(&program) }
+    }
+
+    /// Builds the KernelTemplate rendered with each placeholder name bound to an
+    /// OpenCL C type name. Cached like `Session::build_template`.
+    pub fn build_template_with(
+        &self,
+        template: &KernelTemplate,
+        bindings: &[(&str, &str)],
+    ) -> Output<Program> {
+        let program = self.rendered_program(template.render_with(bindings)?)?;
+        unsafe { Program::from_low_level_program(&program) }
+    }
+
+    /// The number of rendered KernelTemplates the Session has built.
+    pub fn template_cache_len(&self) -> usize {
+        self.templates().len()
+    }
+
+    fn template_program<T: ClTypeName>(&self, template: &KernelTemplate) -> Output<ClProgram> {
+        self.rendered_program(template.render::<T>()?)
+    }
+
+    fn templates(&self) -> MutexGuard<HashMap<String, ClProgram>> {
+        self._templates
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // The cache is not locked during the build so builds of different templates run in
+    // parallel. If two threads build the same source the first program cached is kept.
+    fn rendered_program(&self, src: String) -> Output<ClProgram> {
+        if let Some(program) = self.templates().get(&src) {
+            return Ok(program.clone());
+        }
+        let program = unsafe {
+            let mut program = ClProgram::create_with_src(self.low_level_context(), &src[..])?;
+            program.build(std::slice::from_ref(self.low_level_device()))?;
+            program
+        };
+        Ok(self.templates().entry(src).or_insert(program).clone())
+    }
+
+    /// Executes the KernelOperation with the program of the KernelTemplate rendered for T.
+    /// See `Session::build_template`.
+    pub fn execute_sync_template_operation<'a, T: ClTypeName>(
+        &self,
+        template: &KernelTemplate,
+        kernel_op: KernelOperation<'a>,
+    ) -> Output<()> {
+        let program = self.template_program::<T>(template)?;
+        self.execute_sync_kernel_operation_with_program(&program, kernel_op)
+    }
+
+    /// Executes the KernelOperation with the program of the KernelTemplate rendered with
+    /// the given bindings. See `Session::build_template_with`.
+    pub fn execute_sync_template_operation_with<'a>(
+        &self,
+        template: &KernelTemplate,
+        bindings: &[(&str, &str)],
+        kernel_op: KernelOperation<'a>,
+    ) -> Output<()> {
+        let program = self.rendered_program(template.render_with(bindings)?)?;
+        self.execute_sync_kernel_operation_with_program(&program, kernel_op)
+    }
+
+    pub fn execute_sync_kernel_operation<'a>(&self, kernel_op: KernelOperation<'a>) -> Output<()> {
+        self.execute_sync_kernel_operation_with_program(self.low_level_program(), kernel_op)
+    }
+
+    fn execute_sync_kernel_operation_with_program<'a>(
+        &self,
+        program: &ClProgram,
         mut kernel_op: KernelOperation<'a>,
-    ) -> Output<()>
-where {
+    ) -> Output<()> {
         unsafe {
-            let mut kernel = ClKernel::create(self.low_level_program(), kernel_op.name())?;
+            let mut kernel = ClKernel::create(program, kernel_op.name())?;
             let work = kernel_op.work()?;
             let command_queue_opts = kernel_op.command_queue_opts();
-            let mut mem_locks: Vec<RwLockWriteGuard<Mem>> = Vec::new();
+            let check_arg_types = kernel_op.checks_arg_types();
+            // A buffer passed as several args is locked once.
+            let mut mem_locks: Vec<(usize, RwLockWriteGuard<Mem>)> = Vec::new();
             for (arg_index, arg) in kernel_op.mut_args().iter_mut().enumerate() {
                 match arg {
-                    KernelArg::Num(ref mut cl_arg) => kernel.set_arg(arg_index, cl_arg)?,
-                    KernelArg::Buffer(ref buffer) => {
-                        let mut mem = buffer.write_lock();
-                        kernel.set_arg(arg_index, &mut *mem)?;
-                        mem_locks.push(mem);
+                    KernelArg::Num(ref mut cl_arg) => {
+                        self.check_number_type(&cl_arg.kernel_arg_number_type())?;
+                        kernel.set_arg(arg_index, cl_arg)?
+                    }
+                    KernelArg::Buffer(ref buffer) | KernelArg::SplitBuffer(ref buffer) => {
+                        self.check_number_type(&buffer.number_type())?;
+                        if let (true, Some(cl_type_name)) = (check_arg_types, buffer.cl_type_name())
+                        {
+                            kernel.check_arg_type(arg_index, cl_type_name)?;
+                        }
+                        let lock_id = buffer.buffer().lock_id();
+                        match mem_locks.iter_mut().find(|(id, _)| *id == lock_id) {
+                            Some((_, mem)) => kernel.set_arg(arg_index, &mut **mem)?,
+                            None => {
+                                let mut mem = buffer.buffer().write_lock();
+                                kernel.set_arg(arg_index, &mut *mem)?;
+                                mem_locks.push((lock_id, mem));
+                            }
+                        }
                     }
+                    #[cfg(feature = "opencl_version_2_0_0")]
+                    KernelArg::Svm(ref svm_arg) => kernel.set_svm_arg(arg_index, svm_arg)?,
                 }
             }
 
             let mut queue = self.write_queue();
             // let mut ll_kernel = kernel.write_lock();
             let event = queue.enqueue_kernel(&mut kernel, &work, command_queue_opts)?;
+            self.track_in_flight(&event);
             // Wait until queued mems finish being accessed.
             event.wait()?;
+            let name = kernel_op.name();
+            self.record_command(&queue, CommandKind::Kernel, name, None, event.profiling());
             // then drop locks.
             // std::mem::drop(mem_locks);
             Ok(())
@@ -217,6 +602,40 @@ where {
     }
 }
 
+/// Unmaps a mapped SvmBuffer when dropped so a panic in the function given to
+/// `Session::with_mapped_svm_buffer` does not leave the buffer mapped.
+#[cfg(feature = "opencl_version_2_0_0")]
+struct SvmUnmapGuard<'a, T: Number> {
+    session: &'a Session,
+    buffer: *mut SvmBuffer<T>,
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+impl<'a, T: Number> SvmUnmapGuard<'a, T> {
+    /// Unmaps the buffer, returning the unmap event instead of waiting on it.
+    unsafe fn unmap(self) -> Output<ClEvent> {
+        let guard = ManuallyDrop::new(self);
+        let buffer = guard.buffer;
+        guard
+            .session
+            .write_queue()
+            .unmap_svm_buffer(&mut *buffer, None)
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+impl<'a, T: Number> Drop for SvmUnmapGuard<'a, T> {
+    fn drop(&mut self) {
+        if let Ok(mut queue) = self.session._queue.write() {
+            unsafe {
+                if let Ok(event) = queue.unmap_svm_buffer(&mut *self.buffer, None) {
+                    let _ = event.wait();
+                }
+            }
+        }
+    }
+}
+
 impl Clone for Session {
     fn clone(&self) -> Session {
         Session {
@@ -224,6 +643,11 @@ impl Clone for Session {
             _context: self._context.clone(),
             _program: self._program.clone(),
             _queue: self._queue.clone(),
+            _profiler: self._profiler.clone(),
+            _templates: self._templates.clone(),
+            _supports_half: self._supports_half.clone(),
+            _device_name: self._device_name.clone(),
+            _in_flight: self._in_flight.clone(),
         }
     }
 }
@@ -253,7 +677,7 @@ impl Drop for Session {
 
 #[cfg(test)]
 mod tests {
-    use crate::{testing, Buffer, KernelOperation, Session};
+    use crate::{testing, Buffer, KernelOperation, KernelTemplate, Session};
 
     const SRC: &'static str = "__kernel void test(__global int *data) {
         data[get_global_id(0)] += 1;
@@ -338,7 +762,7 @@ mod tests {
     fn session_can_create_buffer_from_data() {
         let data: Vec<i32> = vec![0, 1, 2, 3, 4, 5, 6, 7];
         let session = new_session();
-        let _buffer: Buffer = session
+        let _buffer: Buffer<i32> = session
             .create_buffer::<i32, &[i32]>(&data[..])
             .unwrap_or_else(|e| panic!("Session failed to create buffer: {:?}", e));
     }
@@ -346,7 +770,7 @@ mod tests {
     #[test]
     fn session_can_create_buffer_of_a_given_length() {
         let session = new_session();
-        let buffer: Buffer = session
+        let buffer: Buffer<i32> = session
             .create_buffer::<i32, usize>(100)
             .unwrap_or_else(|e| panic!("Session failed to create buffer: {:?}", e));
         assert_eq!(buffer.len(), 100);
@@ -356,7 +780,7 @@ mod tests {
     fn session_can_write_and_read_buffer() {
         let data: Vec<i32> = vec![0, 1, 2, 3, 4, 5, 6, 7];
         let session = new_session();
-        let buffer: Buffer = session
+        let buffer: Buffer<i32> = session
             .create_buffer::<i32, &[i32]>(&data[..])
             .unwrap_or_else(|e| panic!("Session failed to create buffer: {:?}", e));
         assert_eq!(buffer.len(), 8);
@@ -377,11 +801,266 @@ mod tests {
         assert_eq!(data3, data);
     }
 
+    #[cfg(feature = "opencl_version_2_0_0")]
+    #[test]
+    fn session_can_map_and_unmap_a_coarse_grained_svm_buffer() {
+        use crate::{MapFlags, SvmMemFlags};
+        let session = new_session();
+        let mut svm = session
+            .create_svm_buffer::<i32>(8, SvmMemFlags::default())
+            .unwrap_or_else(|e| panic!("Session failed to create svm buffer: {:?}", e));
+        session
+            .with_mapped_svm_buffer(&mut svm, MapFlags::Write, |data| {
+                for (i, item) in data.iter_mut().enumerate() {
+                    *item = i as i32;
+                }
+            })
+            .unwrap();
+        let data = session
+            .with_mapped_svm_buffer(&mut svm, MapFlags::Read, |data| data.to_vec())
+            .unwrap();
+        assert_eq!(data, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[cfg(feature = "opencl_version_2_0_0")]
+    #[test]
+    fn session_unmaps_an_svm_buffer_when_the_mapped_function_panics() {
+        use crate::{MapFlags, SvmMemFlags};
+        use std::panic::{self, AssertUnwindSafe};
+        let session = new_session();
+        let mut svm = session
+            .create_svm_buffer::<i32>(4, SvmMemFlags::default())
+            .unwrap_or_else(|e| panic!("Session failed to create svm buffer: {:?}", e));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            session.with_mapped_svm_buffer(&mut svm, MapFlags::Write, |_| panic!("boom"))
+        }));
+        assert!(result.is_err());
+        let data = session
+            .with_mapped_svm_buffer(&mut svm, MapFlags::Write, |data| {
+                data.copy_from_slice(&[1, 2, 3, 4]);
+                data.to_vec()
+            })
+            .unwrap();
+        assert_eq!(data, vec![1, 2, 3, 4]);
+    }
+
+    #[cfg(feature = "opencl_version_2_0_0")]
+    #[test]
+    fn session_can_create_a_pipe() {
+        let session = new_session();
+        let pipe = session
+            .create_pipe::<f32>(32)
+            .unwrap_or_else(|e| panic!("Session failed to create pipe: {:?}", e));
+        assert_eq!(unsafe { pipe.max_packets() }.unwrap(), 32);
+        assert_eq!(unsafe { pipe.packet_size() }.unwrap(), 4);
+    }
+
+    #[test]
+    fn session_with_profiler_records_commands() {
+        use crate::{CommandKind, Profiler};
+        let data: Vec<i32> = vec![0, 1, 2, 3, 4, 5, 6, 7];
+        let profiler = Profiler::new();
+        let session = new_session().with_profiler(profiler.clone());
+        let buffer: Buffer<i32> = session.create_buffer::<i32, usize>(8).unwrap();
+        session.sync_write_buffer(&buffer, &data[..], None).unwrap();
+        let op = KernelOperation::new("test").with_dims(8).add_arg(&buffer);
+        session.execute_sync_kernel_operation(op).unwrap();
+        session
+            .sync_read_buffer(&buffer, vec![0i32; 8], None)
+            .unwrap();
+
+        let records = profiler.records();
+        let kinds: Vec<CommandKind> = records.iter().map(|r| r.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                CommandKind::WriteBuffer,
+                CommandKind::Kernel,
+                CommandKind::ReadBuffer
+            ]
+        );
+        assert_eq!(records[0].bytes, Some(32));
+        assert_eq!(records[1].name, "test");
+        let stats = profiler.kernel_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].count, 1);
+    }
+
+    #[test]
+    fn session_can_migrate_buffers() {
+        let data: Vec<i32> = vec![0, 1, 2, 3, 4, 5, 6, 7];
+        let session = new_session();
+        let buffer: Buffer<i32> = session
+            .create_buffer::<i32, &[i32]>(&data[..])
+            .unwrap_or_else(|e| panic!("Session failed to create buffer: {:?}", e));
+        session
+            .migrate(&[&buffer])
+            .unwrap_or_else(|e| panic!("Session failed to migrate buffer: {:?}", e));
+        let data2 = session
+            .sync_read_buffer(&buffer, vec![0i32; 8], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(data2, data);
+    }
+
+    #[test]
+    fn session_can_execute_kernel_with_an_erased_buffer() {
+        let data: Vec<i32> = vec![0, 1, 2, 3];
+        let session = new_session();
+        let buffer = session
+            .create_buffer::<i32, &[i32]>(&data[..])
+            .unwrap()
+            .erase();
+        let kernel_op = KernelOperation::new("test").add_arg(&buffer).with_work(4);
+        session.execute_sync_kernel_operation(kernel_op).unwrap();
+        let typed: Buffer<i32> = buffer.downcast().unwrap();
+        let data2 = session
+            .sync_read_buffer(&typed, vec![0i32; 4], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(data2, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn session_creates_half_buffers_only_on_devices_with_fp16() {
+        use crate::ll::{DeviceError, Half};
+        use crate::HasDeviceInfo;
+
+        let session = new_session();
+        let result = session.create_buffer::<Half, usize>(4);
+        if session.device().supports_half().unwrap() {
+            assert!(result.is_ok());
+        } else {
+            assert_eq!(
+                result.unwrap_err().downcast_ref::<DeviceError>(),
+                Some(&DeviceError::HalfNotSupported(std::any::type_name::<Half>()))
+            );
+        }
+    }
+
+    #[test]
+    fn session_checks_buffer_args_against_the_kernel_arg_types() {
+        use crate::ll::{cl::ClError, KernelError, KernelPtr};
+
+        let session = new_session();
+        let kernel = unsafe { crate::ll::Kernel::create(session.low_level_program(), "test") };
+        let arg_info = unsafe { kernel.unwrap().arg_type_name(0) };
+        let buffer = session.create_buffer::<f32, usize>(4).unwrap();
+        // Arg types are only checked when asked for.
+        let op = KernelOperation::new("test").with_dims(4).add_arg(&buffer);
+        session.execute_sync_kernel_operation(op).unwrap();
+        let op = KernelOperation::new("test")
+            .with_dims(4)
+            .add_arg(&buffer)
+            .with_arg_type_checks();
+        let result = session.execute_sync_kernel_operation(op);
+        match arg_info {
+            Ok(declared) => assert_eq!(
+                result.unwrap_err().downcast_ref::<KernelError>(),
+                Some(&KernelError::ArgTypeMismatch(
+                    0,
+                    declared,
+                    "float".to_string()
+                ))
+            ),
+            Err(e) => {
+                assert!(matches!(
+                    e.downcast_ref::<ClError>(),
+                    Some(ClError::KernelArgInfoNotAvailable(_))
+                ));
+                assert!(result.is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn session_queries_half_support_once_and_only_for_half_types() {
+        use crate::ll::Half;
+
+        let session = new_session();
+        let _buffer = session.create_buffer::<i32, usize>(4).unwrap();
+        assert_eq!(*session._supports_half.lock().unwrap(), None);
+        let _result = session.create_buffer::<Half, usize>(4);
+        let cached = *session._supports_half.lock().unwrap();
+        assert!(cached.is_some());
+        assert_eq!(*session.clone()._supports_half.lock().unwrap(), cached);
+    }
+
+    #[test]
+    fn session_can_execute_kernel_with_a_buffer_of_structs() {
+        use crate::ClStruct;
+
+        #[derive(ClStruct, Clone, Copy, Debug, Default, PartialEq)]
+        #[repr(C)]
+        #[cl_struct(crate = "crate::ll")]
+        struct Particle {
+            position: f32,
+            velocity: f32,
+        }
+
+        let src = "typedef struct { float position; float velocity; } Particle;
+        __kernel void step(__global Particle *particles) {
+            size_t i = get_global_id(0);
+            particles[i].position += particles[i].velocity;
+        }";
+        let session = testing::get_session(src);
+        let data: Vec<Particle> = (0..4)
+            .map(|i| Particle {
+                position: i as f32,
+                velocity: 0.5,
+            })
+            .collect();
+        let buffer: Buffer<Particle> = session.create_buffer(&data[..]).unwrap();
+        let kernel_op = KernelOperation::new("step").add_arg(&buffer).with_work(4);
+        session.execute_sync_kernel_operation(kernel_op).unwrap();
+        let output = session
+            .sync_read_buffer(&buffer, vec![Particle::default(); 4], None)
+            .unwrap()
+            .unwrap();
+        let positions: Vec<f32> = output.iter().map(|p| p.position).collect();
+        assert_eq!(positions, vec![0.5, 1.5, 2.5, 3.5]);
+    }
+
+    #[test]
+    fn session_caches_templates_per_type() {
+        let template = KernelTemplate::new(
+            "__kernel void add_one(__global ${T} *data) {
+                data[get_global_id(0)] += (${T})1;
+            }",
+        );
+        let session = new_session();
+        let ints: Buffer<i32> = session.create_buffer(&[1i32, 2, 3, 4][..]).unwrap();
+        let op = KernelOperation::new("add_one").add_arg(&ints).with_work(4);
+        session
+            .execute_sync_template_operation::<i32>(&template, op)
+            .unwrap();
+        let floats: Buffer<f32> = session.create_buffer(&[0.5f32, 1.5][..]).unwrap();
+        let op = KernelOperation::new("add_one")
+            .add_arg(&floats)
+            .with_work(2);
+        session
+            .execute_sync_template_operation::<f32>(&template, op)
+            .unwrap();
+        let _program = session.build_template::<i32>(&template).unwrap();
+        assert_eq!(session.template_cache_len(), 2);
+
+        let ints = session
+            .sync_read_buffer(&ints, vec![0i32; 4], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(ints, vec![2, 3, 4, 5]);
+        let floats = session
+            .sync_read_buffer(&floats, vec![0f32; 2], None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(floats, vec![1.5, 2.5]);
+    }
+
     #[test]
     fn session_sync_enqueue_kernel_and_read_buffer() {
         let data: Vec<i32> = vec![0, 1, 2, 3, 4, 5, 6, 7];
         let session = new_session();
-        let buffer: Buffer = session
+        let buffer: Buffer<i32> = session
             .create_buffer::<i32, &[i32]>(&data[..])
             .unwrap_or_else(|e| panic!("Session failed to create buffer: {:?}", e));
         assert_eq!(buffer.len(), 8);
@@ -0,0 +1,345 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+
+use crate::ll::ErrorT;
+use crate::{Output, Session};
+
+/// An error related to a SessionPool.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum SessionPoolError {
+    #[error("A SessionPool requires at least one Session")]
+    NoSessions,
+
+    #[error("SessionPool has no device at index {0}")]
+    NoSuchDevice(usize),
+
+    #[error("A SessionPool requires at least 1 queue per device")]
+    ZeroMaxQueues,
+}
+
+/// The way a SessionPool chooses a queue for a checkout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolPolicy {
+    /// A thread is always handed the same queue of a device. A thread's first checkout
+    /// is assigned the least loaded queue.
+    ThreadAffinity,
+
+    /// Every checkout is handed the queue with the fewest in-flight commands.
+    LeastLoaded,
+}
+
+impl Default for PoolPolicy {
+    fn default() -> PoolPolicy {
+        PoolPolicy::ThreadAffinity
+    }
+}
+
+#[derive(Debug)]
+struct PoolEntry {
+    device_index: usize,
+    session: Session,
+}
+
+impl PoolEntry {
+    fn in_flight(&self) -> usize {
+        self.session.in_flight_commands()
+    }
+}
+
+static NEXT_POOL_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // The queue each (pool id, device index) hands this thread under ThreadAffinity. The
+    // map is freed with the thread; entries of dropped pools are pruned on insert.
+    static AFFINITY: RefCell<HashMap<(usize, usize), Weak<PoolEntry>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// A pool of Sessions that gives each thread (or each command) its own command queue.
+///
+/// The pool starts with the queue of each given Session. A queue is busy while it has
+/// commands in flight (enqueued, but not completed). When every queue of a device is
+/// busy, a new queue is created lazily with `Session::create_copy` until the device has
+/// `max_queues_per_device` queues.
+#[derive(Debug)]
+pub struct SessionPool {
+    id: usize,
+    bases: Vec<Session>,
+    entries: RwLock<Vec<Arc<PoolEntry>>>,
+    policy: PoolPolicy,
+    max_queues_per_device: usize,
+}
+
+impl SessionPool {
+    pub fn new(sessions: Vec<Session>) -> Output<SessionPool> {
+        if sessions.is_empty() {
+            return Err(SessionPoolError::NoSessions)?;
+        }
+        let entries = sessions
+            .iter()
+            .enumerate()
+            .map(|(device_index, session)| {
+                Arc::new(PoolEntry {
+                    device_index,
+                    session: session.clone().with_in_flight_tracking(),
+                })
+            })
+            .collect();
+        Ok(SessionPool {
+            id: NEXT_POOL_ID.fetch_add(1, Ordering::SeqCst),
+            bases: sessions,
+            entries: RwLock::new(entries),
+            policy: PoolPolicy::default(),
+            max_queues_per_device: 4,
+        })
+    }
+
+    pub fn with_policy(mut self, policy: PoolPolicy) -> SessionPool {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_max_queues_per_device(mut self, max_queues: usize) -> Output<SessionPool> {
+        if max_queues == 0 {
+            return Err(SessionPoolError::ZeroMaxQueues)?;
+        }
+        self.max_queues_per_device = max_queues;
+        Ok(self)
+    }
+
+    pub fn policy(&self) -> PoolPolicy {
+        self.policy
+    }
+
+    pub fn max_queues_per_device(&self) -> usize {
+        self.max_queues_per_device
+    }
+
+    pub fn num_devices(&self) -> usize {
+        self.bases.len()
+    }
+
+    /// The number of queues that have been created for the device.
+    pub fn queue_count(&self, device_index: usize) -> usize {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.device_index == device_index)
+            .count()
+    }
+
+    /// The in-flight commands of each queue of the device.
+    pub fn in_flight(&self, device_index: usize) -> Vec<usize> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.device_index == device_index)
+            .map(|e| e.in_flight())
+            .collect()
+    }
+
+    /// Checks out a queue of the device with the fewest in-flight commands.
+    pub fn checkout(&self) -> Output<PooledSession> {
+        let device_index = (0..self.num_devices())
+            .min_by_key(|i| self.in_flight(*i).iter().sum::<usize>())
+            .unwrap();
+        self.checkout_on(device_index)
+    }
+
+    /// Checks out a queue of the device at `device_index`.
+    pub fn checkout_on(&self, device_index: usize) -> Output<PooledSession> {
+        if device_index >= self.num_devices() {
+            return Err(SessionPoolError::NoSuchDevice(device_index))?;
+        }
+        let entry = match self.policy {
+            PoolPolicy::LeastLoaded => self.least_loaded(device_index)?,
+            PoolPolicy::ThreadAffinity => {
+                let key = (self.id, device_index);
+                let assigned = AFFINITY.with(|a| a.borrow().get(&key).and_then(Weak::upgrade));
+                match assigned {
+                    Some(entry) => entry,
+                    None => {
+                        let entry = self.least_loaded(device_index)?;
+                        AFFINITY.with(|a| {
+                            let mut affinity = a.borrow_mut();
+                            affinity.retain(|_, e| e.strong_count() > 0);
+                            affinity.insert(key, Arc::downgrade(&entry));
+                        });
+                        entry
+                    }
+                }
+            }
+        };
+        Ok(PooledSession::new(entry))
+    }
+
+    /// Finds the least loaded queue of the device. A new queue is created if every queue
+    /// of the device is busy and the device has room for another queue.
+    fn least_loaded(&self, device_index: usize) -> Output<Arc<PoolEntry>> {
+        if let Some(entry) = self.least_loaded_entry(device_index, false) {
+            return Ok(entry);
+        }
+        // Creating a queue can be slow, so it is done without holding the lock.
+        let session = self.bases[device_index]
+            .create_copy()?
+            .with_in_flight_tracking();
+        let mut entries = self.entries.write().unwrap();
+        let count = entries
+            .iter()
+            .filter(|e| e.device_index == device_index)
+            .count();
+        if count >= self.max_queues_per_device {
+            // Another thread added the device's last queue in the meantime.
+            std::mem::drop(entries);
+            return Ok(self.least_loaded_entry(device_index, true).unwrap());
+        }
+        let entry = Arc::new(PoolEntry {
+            device_index,
+            session,
+        });
+        entries.push(entry.clone());
+        Ok(entry)
+    }
+
+    /// The least loaded queue of the device, or None if every queue is busy and another
+    /// queue may be created (unless `at_capacity` is set).
+    fn least_loaded_entry(&self, device_index: usize, at_capacity: bool) -> Option<Arc<PoolEntry>> {
+        let entries = self.entries.read().unwrap();
+        let queues: Vec<&Arc<PoolEntry>> = entries
+            .iter()
+            .filter(|e| e.device_index == device_index)
+            .collect();
+        let loads: Vec<usize> = queues.iter().map(|e| e.in_flight()).collect();
+        let at_capacity = at_capacity || queues.len() >= self.max_queues_per_device;
+        choose_queue(&loads[..], at_capacity).map(|i| queues[i].clone())
+    }
+}
+
+/// The index of the least loaded queue, or None if every queue is busy and the device is
+/// not at capacity (so a new queue should be created instead).
+fn choose_queue(loads: &[usize], at_capacity: bool) -> Option<usize> {
+    let (index, load) = loads.iter().enumerate().min_by_key(|(_, load)| **load)?;
+    if *load == 0 || at_capacity {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+/// A Session checked out of a SessionPool. The commands it executes count as in-flight
+/// on its queue until they complete.
+#[derive(Debug)]
+pub struct PooledSession {
+    entry: Arc<PoolEntry>,
+}
+
+impl PooledSession {
+    fn new(entry: Arc<PoolEntry>) -> PooledSession {
+        PooledSession { entry }
+    }
+
+    pub fn device_index(&self) -> usize {
+        self.entry.device_index
+    }
+}
+
+impl Deref for PooledSession {
+    type Target = Session;
+
+    fn deref(&self) -> &Session {
+        &self.entry.session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::choose_queue;
+    use crate::{testing, KernelOperation, PoolPolicy, SessionPool};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    const SRC: &'static str = "__kernel void test(__global int *data) {
+        data[get_global_id(0)] += 1;
+    }";
+
+    fn new_pool(policy: PoolPolicy) -> SessionPool {
+        SessionPool::new(testing::get_sessions(SRC))
+            .unwrap()
+            .with_policy(policy)
+    }
+
+    #[test]
+    fn choose_queue_prefers_idle_queues_and_creates_new_ones_when_busy() {
+        assert_eq!(choose_queue(&[2, 0, 1], false), Some(1));
+        assert_eq!(choose_queue(&[2, 1], false), None);
+        assert_eq!(choose_queue(&[2, 1], true), Some(1));
+        assert_eq!(choose_queue(&[], false), None);
+    }
+
+    #[test]
+    fn session_pool_counts_in_flight_commands_until_they_complete() {
+        let pool = new_pool(PoolPolicy::LeastLoaded);
+        let session = pool.checkout_on(0).unwrap();
+        assert_eq!(pool.in_flight(0), vec![0]);
+        let buffer = session.create_buffer::<i32, usize>(64).unwrap();
+        let op = KernelOperation::new("test").with_dims(64).add_arg(&buffer);
+        session.execute_sync_kernel_operation(op).unwrap();
+        // The completion callback can run shortly after the wait returns.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while pool.in_flight(0) != vec![0] && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(pool.in_flight(0), vec![0]);
+    }
+
+    #[test]
+    fn sessions_outside_a_pool_do_not_count_in_flight_commands() {
+        let session = testing::get_session(SRC);
+        let buffer = session.create_buffer::<i32, usize>(64).unwrap();
+        let op = KernelOperation::new("test").with_dims(64).add_arg(&buffer);
+        session.execute_sync_kernel_operation(op).unwrap();
+        assert_eq!(session.in_flight_commands(), 0);
+    }
+
+    #[test]
+    fn session_pool_shares_idle_queues_between_checkouts() {
+        let pool = new_pool(PoolPolicy::LeastLoaded);
+        let first = pool.checkout_on(0).unwrap();
+        let second = pool.checkout_on(0).unwrap();
+        assert!(*first == *second);
+        assert_eq!(pool.queue_count(0), 1);
+    }
+
+    #[test]
+    fn session_pool_hands_a_thread_the_same_queue() {
+        let pool = new_pool(PoolPolicy::ThreadAffinity);
+        let first = pool.checkout_on(0).unwrap();
+        let second = pool.checkout_on(0).unwrap();
+        assert!(*first == *second);
+        assert_eq!(pool.queue_count(0), 1);
+    }
+
+    #[test]
+    fn session_pool_affinity_does_not_keep_queues_alive() {
+        let pool = new_pool(PoolPolicy::ThreadAffinity);
+        let entry = Arc::downgrade(&pool.checkout_on(0).unwrap().entry);
+        std::mem::drop(pool);
+        assert!(entry.upgrade().is_none());
+    }
+
+    #[test]
+    fn session_pool_affinity_is_per_pool() {
+        let first_pool = new_pool(PoolPolicy::ThreadAffinity);
+        let second_pool = new_pool(PoolPolicy::ThreadAffinity)
+            .with_max_queues_per_device(1)
+            .unwrap();
+        let first = first_pool.checkout_on(0).unwrap();
+        let second = second_pool.checkout_on(0).unwrap();
+        assert!(!Arc::ptr_eq(&first.entry, &second.entry));
+    }
+}
@@ -16,6 +16,20 @@ pub fn get_sessions(src: &str) -> Vec<Session> {
     })
 }
 
+/// A Session whose program only has a noop kernel, for tests that build their kernels
+/// from templates.
+pub fn get_noop_session() -> Session {
+    get_session("__kernel void noop() {}")
+}
+
+/// Reads the whole Buffer into a Vec.
+pub fn read_buffer<T: Number>(session: &Session, buffer: &Buffer<T>) -> Vec<T> {
+    session
+        .sync_read_buffer(buffer, vec![T::zero(); buffer.len()], None)
+        .unwrap()
+        .unwrap()
+}
+
 pub fn get_platforms() -> Vec<Platform> {
     Platform::list_all().unwrap()
 }
@@ -56,9 +70,9 @@ pub fn get_program(src: &str) -> Program {
     unbuilt_program.build(&devices[..]).unwrap()
 }
 
-pub fn get_buffer<T: Number>(size: usize) -> Buffer {
+pub fn get_buffer<T: Number>(size: usize) -> Buffer<T> {
     let context = testing::get_context();
-    Buffer::create::<T, usize>(
+    Buffer::<T>::create::<usize>(
         &context,
         size,
         HostAccess::ReadWrite,
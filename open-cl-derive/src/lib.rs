@@ -0,0 +1,344 @@
+//! Derive macros for open-cl-low-level.
+//!
+//! These macros are re-exported by open-cl-low-level. Depend on open-cl-low-level
+//! (or open-cl-core) instead of depending on this crate directly.
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Index, Lit, Meta, NestedMeta,
+    Path, Type,
+};
+
+/// Derives `Number` (and with it `NumberTypedT` and `KernelArgPtr`), `ClAlignment`,
+/// `ClTypeName` and `ClStruct` for a `#[repr(C)]` struct so the struct can be used in
+/// Buffers and as a kernel arg.
+///
+/// The derive also generates the arithmetic a Number needs: field-wise `Add` and `Mul`,
+/// `Zero` and `One` with every field zero or one, and a `Display` that prints the
+/// struct's `Debug`. The struct must also derive (or implement) `Clone`, `Copy`, `Debug`,
+/// `Default` and `PartialEq`. Every field must implement `Number`, `ClTypeName` and
+/// `ClAlignment`.
+///
+/// `usize` and `isize` fields are rejected: their OpenCL C counterparts (`size_t` and
+/// `ptrdiff_t`) are as wide as the device's address space, which need not match the host.
+/// Use `u64` (`ulong`) or `i64` (`long`) instead.
+///
+/// The layout of the struct is checked against the OpenCL C alignment rules at compile
+/// time. The vector types already have their OpenCL C alignment, but a field that is
+/// not at an offset aligned to its OpenCL C alignment (e.g. a `u64` on a target that
+/// aligns it to 4 bytes) fails to compile; add padding fields or `#[repr(C, align(N))]`
+/// to fix it.
+///
+/// `ClStruct::cl_typedef()` returns the OpenCL C `typedef struct` of the struct so the
+/// host and device layouts come from one definition. Tuple struct fields are named
+/// `_0`, `_1`, and so on in OpenCL C.
+///
+/// The generated code refers to `::open_cl_low_level`. Use
+/// `#[cl_struct(crate = "open_cl_core::ll")]` when deriving through open-cl-core.
+#[proc_macro_derive(ClStruct, attributes(cl_struct))]
+pub fn derive_cl_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_cl_struct(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand_cl_struct(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "ClStruct cannot be derived for generic structs",
+        ));
+    }
+    check_repr(name, &input.attrs)?;
+    let krate = crate_path(&input.attrs)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "ClStruct can only be derived for structs",
+            ))
+        }
+    };
+    let field_types: Vec<&Type> = fields.iter().map(|f| &f.ty).collect();
+    if field_types.is_empty() {
+        return Err(Error::new_spanned(
+            name,
+            "ClStruct cannot be derived for a struct without fields",
+        ));
+    }
+    for ty in field_types.iter() {
+        check_field_type(ty)?;
+    }
+    let field_names: Vec<String> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => ident.to_string(),
+            None => i.to_string(),
+        })
+        .collect();
+
+    let add = construct(fields, |member| quote!(self.#member + other.#member));
+    let mul = construct(fields, |member| quote!(self.#member * other.#member));
+    let zero = construct(fields, |_| quote!(#krate::Zero::zero()));
+    let one = construct(fields, |_| quote!(#krate::One::one()));
+    let is_zero = fields.iter().enumerate().map(|(i, f)| {
+        let member = member(i, f);
+        quote!(#krate::Zero::is_zero(&self.#member))
+    });
+
+    let layout_checks = field_types
+        .iter()
+        .zip(field_names.iter())
+        .map(|(ty, field)| {
+            let message = format!(
+                "ClStruct {}: field `{}` is not aligned to its OpenCL C alignment",
+                name, field
+            );
+            quote! {
+                let align = ::std::mem::align_of::<#ty>();
+                offset = (offset + align - 1) / align * align;
+                assert!(offset % <#ty as #krate::ClAlignment>::CL_ALIGNMENT == 0, #message);
+                offset += ::std::mem::size_of::<#ty>();
+            }
+        });
+    let typedef_fields = field_types
+        .iter()
+        .zip(field_names.iter())
+        .map(|(ty, field)| {
+            let field = if field.chars().all(|c| c.is_ascii_digit()) {
+                format!("_{}", field)
+            } else {
+                field.clone()
+            };
+            quote! {
+                typedef.push_str(&::std::format!(
+                    " {} {};",
+                    <#ty as #krate::ClTypeName>::CL_TYPE_NAME,
+                    #field
+                ));
+            }
+        });
+    let cl_type_name = name.to_string();
+    let size_message = format!(
+        "ClStruct {}: size is not a multiple of its OpenCL C alignment",
+        name
+    );
+
+    Ok(quote! {
+        impl ::std::ops::Add for #name {
+            type Output = #name;
+
+            #[inline]
+            fn add(self, other: #name) -> #name {
+                #add
+            }
+        }
+
+        impl ::std::ops::Mul for #name {
+            type Output = #name;
+
+            #[inline]
+            fn mul(self, other: #name) -> #name {
+                #mul
+            }
+        }
+
+        impl #krate::Zero for #name {
+            fn zero() -> #name {
+                #zero
+            }
+
+            fn is_zero(&self) -> bool {
+                #( #is_zero )&&*
+            }
+        }
+
+        impl #krate::One for #name {
+            fn one() -> #name {
+                #one
+            }
+        }
+
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(self, f)
+            }
+        }
+
+        impl #krate::NumberOps for #name {}
+
+        impl #krate::Scalar for #name {}
+
+        impl #krate::NumCastFrom<#name> for #name {
+            #[inline(always)]
+            fn num_cast_from(val: #name) -> ::std::option::Option<#name> {
+                ::std::option::Option::Some(val)
+            }
+        }
+
+        impl #krate::Number for #name {
+            type Scalar = #name;
+            type Outer = #name;
+
+            #[inline(always)]
+            fn new(val: #name) -> #name {
+                val
+            }
+
+            #[inline(always)]
+            fn into_outer(self) -> #name {
+                self
+            }
+        }
+
+        impl #krate::ClAlignment for #name {
+            const CL_ALIGNMENT: usize = {
+                let mut alignment = 1;
+                #(
+                    if <#field_types as #krate::ClAlignment>::CL_ALIGNMENT > alignment {
+                        alignment = <#field_types as #krate::ClAlignment>::CL_ALIGNMENT;
+                    }
+                )*
+                alignment
+            };
+        }
+
+        impl #krate::ClTypeName for #name {
+            const CL_TYPE_NAME: &'static str = #cl_type_name;
+        }
+
+        impl #krate::ClStruct for #name {
+            fn cl_typedef() -> ::std::string::String {
+                let mut typedef = ::std::string::String::from("typedef struct {");
+                #( #typedef_fields )*
+                typedef.push_str(&::std::format!(" }} {};", #cl_type_name));
+                typedef
+            }
+        }
+
+        // Evaluated at compile time so a layout mismatch is a compile error.
+        const _: () = {
+            let mut offset: usize = 0;
+            #( #layout_checks )*
+            let _ = offset;
+            assert!(
+                ::std::mem::size_of::<#name>() % <#name as #krate::ClAlignment>::CL_ALIGNMENT == 0,
+                #size_message
+            );
+        };
+    })
+}
+
+fn member(index: usize, field: &syn::Field) -> TokenStream2 {
+    match &field.ident {
+        Some(ident) => quote!(#ident),
+        None => {
+            let index = Index::from(index);
+            quote!(#index)
+        }
+    }
+}
+
+/// Builds a struct literal of Self with each field's value made by `value`.
+fn construct<F>(fields: &Fields, value: F) -> TokenStream2
+where
+    F: Fn(&TokenStream2) -> TokenStream2,
+{
+    let values = fields.iter().enumerate().map(|(i, f)| {
+        let member = member(i, f);
+        let value = value(&member);
+        quote!(#member: #value)
+    });
+    quote!(Self { #( #values ),* })
+}
+
+/// Rejects the field types whose OpenCL C size depends on the device.
+fn check_field_type(ty: &Type) -> Result<(), Error> {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            let replacement = match segment.ident.to_string().as_str() {
+                "usize" | "SizeT" => Some("u64"),
+                "isize" => Some("i64"),
+                _ => None,
+            };
+            if let Some(replacement) = replacement {
+                return Err(Error::new_spanned(
+                    ty,
+                    format!(
+                        "ClStruct fields cannot be `{}`: its OpenCL C size depends on the \
+                         device; use `{}` instead",
+                        segment.ident, replacement
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_repr(name: &syn::Ident, attrs: &[Attribute]) -> Result<(), Error> {
+    let mut is_repr_c = false;
+    for attr in attrs.iter().filter(|a| a.path.is_ident("repr")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested.iter() {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("C") => is_repr_c = true,
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("packed") => {
+                        return Err(Error::new_spanned(
+                            path,
+                            "ClStruct cannot be derived for packed structs",
+                        ))
+                    }
+                    NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("packed") => {
+                        return Err(Error::new_spanned(
+                            list,
+                            "ClStruct cannot be derived for packed structs",
+                        ))
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    if !is_repr_c {
+        return Err(Error::new_spanned(
+            name,
+            "ClStruct requires the struct to be #[repr(C)]",
+        ));
+    }
+    Ok(())
+}
+
+fn crate_path(attrs: &[Attribute]) -> Result<Path, Error> {
+    let mut krate = syn::parse_quote!(::open_cl_low_level);
+    for attr in attrs.iter().filter(|a| a.path.is_ident("cl_struct")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested.iter() {
+                match nested {
+                    NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate") => {
+                        krate = match &nv.lit {
+                            Lit::Str(lit) => lit.parse()?,
+                            lit => return Err(Error::new_spanned(lit, "expected a string")),
+                        };
+                    }
+                    other => {
+                        return Err(Error::new_spanned(
+                            other,
+                            "unknown cl_struct attribute, expected `crate = \"...\"`",
+                        ))
+                    }
+                }
+            }
+        }
+    }
+    Ok(krate)
+}
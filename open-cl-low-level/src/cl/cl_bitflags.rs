@@ -1,4 +1,6 @@
-use crate::cl::{cl_command_queue_properties, cl_mem_flags};
+use crate::cl::{cl_command_queue_properties, cl_mem_flags, cl_mem_migration_flags};
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::cl::{cl_device_svm_capabilities, cl_svm_mem_flags};
 pub use ocl_core::{DeviceAffinityDomain, DeviceExecCapabilities, DeviceFpConfig, DeviceType};
 
 bitflags! {
@@ -118,6 +120,57 @@ impl From<MemFlags> for cl_mem_flags {
     }
 }
 
+bitflags! {
+    /// Flags for clEnqueueMigrateMemObjects. An empty set of flags migrates
+    /// the mem objects to the device of the command queue.
+    pub struct MemMigrationFlags: cl_mem_migration_flags {
+        const HOST = 1;
+        const CONTENT_UNDEFINED = 1 << 1;
+    }
+}
+
+impl Default for MemMigrationFlags {
+    fn default() -> MemMigrationFlags {
+        MemMigrationFlags::empty()
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+bitflags! {
+    pub struct SvmMemFlags: cl_svm_mem_flags {
+        const READ_WRITE = 1;
+        const WRITE_ONLY = 1 << 1;
+        const READ_ONLY = 1 << 2;
+        const FINE_GRAIN_BUFFER = 1 << 10;
+        const ATOMICS = 1 << 11;
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+impl Default for SvmMemFlags {
+    fn default() -> SvmMemFlags {
+        SvmMemFlags::READ_WRITE
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+impl SvmMemFlags {
+    /// Fine-grained SVM can be accessed by the host without mapping.
+    pub fn is_fine_grained(&self) -> bool {
+        self.contains(SvmMemFlags::FINE_GRAIN_BUFFER)
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+bitflags! {
+    pub struct DeviceSvmCapabilities: cl_device_svm_capabilities {
+        const COARSE_GRAIN_BUFFER = 1;
+        const FINE_GRAIN_BUFFER = 1 << 1;
+        const FINE_GRAIN_SYSTEM = 1 << 2;
+        const ATOMICS = 1 << 3;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,4 +187,19 @@ mod tests {
         let q: CommandQueueProperties = CommandQueueProperties::from_bits(p).unwrap();
         assert_eq!(q, CommandQueueProperties::PROFILING_ENABLE);
     }
+
+    #[test]
+    fn test_mem_migration_flags_can_be_combined() {
+        let flags = MemMigrationFlags::HOST | MemMigrationFlags::CONTENT_UNDEFINED;
+        assert_eq!(flags.bits(), 3);
+        assert_eq!(MemMigrationFlags::default().bits(), 0);
+    }
+
+    #[cfg(feature = "opencl_version_2_0_0")]
+    #[test]
+    fn test_svm_mem_flags_fine_grained_check() {
+        assert!(!SvmMemFlags::default().is_fine_grained());
+        let flags = SvmMemFlags::READ_WRITE | SvmMemFlags::FINE_GRAIN_BUFFER;
+        assert!(flags.is_fine_grained());
+    }
 }
@@ -4,8 +4,8 @@ use crate::cl::{
     cl_device_local_mem_type, cl_device_mem_cache_type, cl_device_partition_property,
     cl_event_info, cl_filter_mode, cl_image_info, cl_int, cl_kernel_arg_access_qualifier,
     cl_kernel_arg_address_qualifier, cl_kernel_arg_info, cl_kernel_arg_type_qualifier,
-    cl_kernel_info, cl_kernel_work_group_info, cl_map_flags, cl_mem_info, cl_mem_migration_flags,
-    cl_mem_object_type, cl_platform_info, cl_profiling_info, cl_program_binary_type,
+    cl_kernel_info, cl_kernel_work_group_info, cl_map_flags, cl_mem_info, cl_mem_object_type,
+    cl_pipe_info, cl_platform_info, cl_profiling_info, cl_program_binary_type,
     cl_program_build_info, cl_program_info, cl_sampler_info,
 };
 
@@ -162,11 +162,11 @@ __cl_enum!(ImageInfo, cl_image_info, {
     NumSamples => 0x111A
 });
 
-// NOTE: Version for cl_pipe_info?
-// crate::__codes_enum!(PipeInfo, cl_pipe_info, {
-//     PacketSize => 0x1120,
-//     MaxPackets => 0x1121
-// });
+// v2.0
+__cl_enum!(PipeInfo, cl_pipe_info, {
+    PacketSize => 0x1120,
+    MaxPackets => 0x1121
+});
 
 // /* cl_sampler_info */
 // /* These enumerants are for the cl_khr_mipmap_image extension.
@@ -357,9 +357,10 @@ __cl_enum!(ProgramInfo, cl_program_info, {
     BinarySizes => 0x1165,
     Binaries => 0x1166,
     NumKernels => 0x1167,
-    KernelNames => 0x1168
-    // v2.0+
-    // Il => 0x1169,
+    KernelNames => 0x1168,
+    // v2.1+
+    Il => 0x1169
+    // v2.2+
     // ScopeGlobalCtorsPresent => 0x116A,
     // ScopeGlobalDtorsPresent => 0x116B
 });
@@ -380,11 +381,6 @@ __cl_enum!(ProgramBinaryType, cl_program_binary_type, {
     Executable => 0x4
 });
 
-__cl_enum!(MemMigrationFlags, cl_mem_migration_flags, {
-    Host => 1,
-    ContentUndefined => (1 << 1)
-});
-
 __cl_enum!(MemObjectType, cl_mem_object_type, {
     Buffer => 0x10F0,
     Image2D => 0x10F1,
@@ -471,10 +467,10 @@ __cl_enum!(CommandQueueInfo, cl_command_queue_info, {
     Context => 0x1090,
     Device => 0x1091,
     ReferenceCount => 0x1092,
-    Properties => 0x1093
+    Properties => 0x1093,
 
     // v2.0
-    // Size => 0x1094,
+    Size => 0x1094
 
     // v2.1
     // DeviceDefault => 0x1095
@@ -550,10 +546,4 @@ __cl_enum!(ProfilingInfo, cl_profiling_info, {
 // CL_CONTEXT_ADAPTER_DXVA_KHR      IDXVAHD_Device *                    (if the cl_khr_dx9_media_sharing extension is supported).
 // CL_CONTEXT_D3D11_DEVICE_KHR      ID3D11Device *                      default: NULL
 
-// NOTE: Version for cl_device_svm? 2.0?
-// __cl_enum!(DeviceSvm, cl_device_svm, {
-//     CoarseGrainBuffer => 1,
-//     FineGrainBuffer => 2,
-//     FineGrainSystem => 4,
-//     Atomics => 8
-// });
+// NOTE: cl_device_svm_capabilities is a bitfield. See DeviceSvmCapabilities.
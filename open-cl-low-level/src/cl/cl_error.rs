@@ -0,0 +1,321 @@
+use super::StatusCodeError;
+use crate::Output;
+use std::fmt;
+use thiserror::Error;
+
+/// Where an OpenCL error happened: the API call that returned the status code and,
+/// when known, the object it was called for (e.g. a kernel name or a buffer size).
+/// The call is None for errors converted from a StatusCodeError, which does not record
+/// where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClErrorSite {
+    pub call: Option<&'static str>,
+    pub object: Option<String>,
+}
+
+impl ClErrorSite {
+    pub fn new(call: &'static str) -> ClErrorSite {
+        ClErrorSite {
+            call: Some(call),
+            object: None,
+        }
+    }
+
+    pub fn with_object<S: Into<String>>(call: &'static str, object: S) -> ClErrorSite {
+        ClErrorSite {
+            call: Some(call),
+            object: Some(object.into()),
+        }
+    }
+
+    /// A site whose API call is not known.
+    pub fn unknown() -> ClErrorSite {
+        ClErrorSite {
+            call: None,
+            object: None,
+        }
+    }
+}
+
+impl fmt::Display for ClErrorSite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.call.unwrap_or("an unknown call"))?;
+        if let Some(object) = &self.object {
+            write!(f, " for {}", object)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! cl_errors {
+    ($($code:literal => $variant:ident, $name:literal;)*) => {
+        /// An OpenCL error status code with one variant per status code.
+        #[derive(Error, Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum ClError {
+            $(
+                $variant(ClErrorSite),
+            )*
+            Unknown(i32, ClErrorSite),
+        }
+
+        impl ClError {
+            pub fn new(status_code: i32, site: ClErrorSite) -> ClError {
+                match status_code {
+                    $(
+                        $code => ClError::$variant(site),
+                    )*
+                    code => ClError::Unknown(code, site),
+                }
+            }
+
+            pub fn status_code(&self) -> i32 {
+                match self {
+                    $(
+                        ClError::$variant(_) => $code,
+                    )*
+                    ClError::Unknown(code, _) => *code,
+                }
+            }
+
+            pub fn site(&self) -> &ClErrorSite {
+                match self {
+                    $(
+                        ClError::$variant(site) => site,
+                    )*
+                    ClError::Unknown(_, site) => site,
+                }
+            }
+        }
+
+        /// The name of an OpenCL status code (e.g. `CL_OUT_OF_RESOURCES`).
+        pub fn status_code_name(status_code: i32) -> Option<&'static str> {
+            match status_code {
+                $(
+                    $code => Some($name),
+                )*
+                _ => None,
+            }
+        }
+    };
+}
+
+cl_errors! {
+    -1 => DeviceNotFound, "CL_DEVICE_NOT_FOUND";
+    -2 => DeviceNotAvailable, "CL_DEVICE_NOT_AVAILABLE";
+    -3 => CompilerNotAvailable, "CL_COMPILER_NOT_AVAILABLE";
+    -4 => MemObjectAllocationFailure, "CL_MEM_OBJECT_ALLOCATION_FAILURE";
+    -5 => OutOfResources, "CL_OUT_OF_RESOURCES";
+    -6 => OutOfHostMemory, "CL_OUT_OF_HOST_MEMORY";
+    -7 => ProfilingInfoNotAvailable, "CL_PROFILING_INFO_NOT_AVAILABLE";
+    -8 => MemCopyOverlap, "CL_MEM_COPY_OVERLAP";
+    -9 => ImageFormatMismatch, "CL_IMAGE_FORMAT_MISMATCH";
+    -10 => ImageFormatNotSupported, "CL_IMAGE_FORMAT_NOT_SUPPORTED";
+    -11 => BuildProgramFailure, "CL_BUILD_PROGRAM_FAILURE";
+    -12 => MapFailure, "CL_MAP_FAILURE";
+    -13 => MisalignedSubBufferOffset, "CL_MISALIGNED_SUB_BUFFER_OFFSET";
+    -14 => ExecStatusErrorForEventsInWaitList, "CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST";
+    -15 => CompileProgramFailure, "CL_COMPILE_PROGRAM_FAILURE";
+    -16 => LinkerNotAvailable, "CL_LINKER_NOT_AVAILABLE";
+    -17 => LinkProgramFailure, "CL_LINK_PROGRAM_FAILURE";
+    -18 => DevicePartitionFailed, "CL_DEVICE_PARTITION_FAILED";
+    -19 => KernelArgInfoNotAvailable, "CL_KERNEL_ARG_INFO_NOT_AVAILABLE";
+    -30 => InvalidValue, "CL_INVALID_VALUE";
+    -31 => InvalidDeviceType, "CL_INVALID_DEVICE_TYPE";
+    -32 => InvalidPlatform, "CL_INVALID_PLATFORM";
+    -33 => InvalidDevice, "CL_INVALID_DEVICE";
+    -34 => InvalidContext, "CL_INVALID_CONTEXT";
+    -35 => InvalidQueueProperties, "CL_INVALID_QUEUE_PROPERTIES";
+    -36 => InvalidCommandQueue, "CL_INVALID_COMMAND_QUEUE";
+    -37 => InvalidHostPtr, "CL_INVALID_HOST_PTR";
+    -38 => InvalidMemObject, "CL_INVALID_MEM_OBJECT";
+    -39 => InvalidImageFormatDescriptor, "CL_INVALID_IMAGE_FORMAT_DESCRIPTOR";
+    -40 => InvalidImageSize, "CL_INVALID_IMAGE_SIZE";
+    -41 => InvalidSampler, "CL_INVALID_SAMPLER";
+    -42 => InvalidBinary, "CL_INVALID_BINARY";
+    -43 => InvalidBuildOptions, "CL_INVALID_BUILD_OPTIONS";
+    -44 => InvalidProgram, "CL_INVALID_PROGRAM";
+    -45 => InvalidProgramExecutable, "CL_INVALID_PROGRAM_EXECUTABLE";
+    -46 => InvalidKernelName, "CL_INVALID_KERNEL_NAME";
+    -47 => InvalidKernelDefinition, "CL_INVALID_KERNEL_DEFINITION";
+    -48 => InvalidKernel, "CL_INVALID_KERNEL";
+    -49 => InvalidArgIndex, "CL_INVALID_ARG_INDEX";
+    -50 => InvalidArgValue, "CL_INVALID_ARG_VALUE";
+    -51 => InvalidArgSize, "CL_INVALID_ARG_SIZE";
+    -52 => InvalidKernelArgs, "CL_INVALID_KERNEL_ARGS";
+    -53 => InvalidWorkDimension, "CL_INVALID_WORK_DIMENSION";
+    -54 => InvalidWorkGroupSize, "CL_INVALID_WORK_GROUP_SIZE";
+    -55 => InvalidWorkItemSize, "CL_INVALID_WORK_ITEM_SIZE";
+    -56 => InvalidGlobalOffset, "CL_INVALID_GLOBAL_OFFSET";
+    -57 => InvalidEventWaitList, "CL_INVALID_EVENT_WAIT_LIST";
+    -58 => InvalidEvent, "CL_INVALID_EVENT";
+    -59 => InvalidOperation, "CL_INVALID_OPERATION";
+    -60 => InvalidGlObject, "CL_INVALID_GL_OBJECT";
+    -61 => InvalidBufferSize, "CL_INVALID_BUFFER_SIZE";
+    -62 => InvalidMipLevel, "CL_INVALID_MIP_LEVEL";
+    -63 => InvalidGlobalWorkSize, "CL_INVALID_GLOBAL_WORK_SIZE";
+    -64 => InvalidProperty, "CL_INVALID_PROPERTY";
+    -65 => InvalidImageDescriptor, "CL_INVALID_IMAGE_DESCRIPTOR";
+    -66 => InvalidCompilerOptions, "CL_INVALID_COMPILER_OPTIONS";
+    -67 => InvalidLinkerOptions, "CL_INVALID_LINKER_OPTIONS";
+    -68 => InvalidDevicePartitionCount, "CL_INVALID_DEVICE_PARTITION_COUNT";
+    -69 => InvalidPipeSize, "CL_INVALID_PIPE_SIZE";
+    -70 => InvalidDeviceQueue, "CL_INVALID_DEVICE_QUEUE";
+    -71 => InvalidSpecId, "CL_INVALID_SPEC_ID";
+    -72 => MaxSizeRestrictionExceeded, "CL_MAX_SIZE_RESTRICTION_EXCEEDED";
+    -1001 => PlatformNotFoundKhr, "CL_PLATFORM_NOT_FOUND_KHR";
+}
+
+impl ClError {
+    /// Returns an error for any status code other than CL_SUCCESS.
+    #[inline(always)]
+    pub fn check(status_code: i32, call: &'static str) -> Output<()> {
+        match status_code {
+            0 => Ok(()),
+            code => Err(ClError::new(code, ClErrorSite::new(call)))?,
+        }
+    }
+
+    /// Like `check`, but also records the object of the call. The object is only
+    /// formatted when the status code is an error.
+    #[inline(always)]
+    pub fn check_with<F, S>(status_code: i32, call: &'static str, object: F) -> Output<()>
+    where
+        F: FnOnce() -> S,
+        S: Into<String>,
+    {
+        match status_code {
+            0 => Ok(()),
+            code => Err(ClError::new(code, ClErrorSite::with_object(call, object())))?,
+        }
+    }
+
+    /// The name of the status code (e.g. `CL_OUT_OF_RESOURCES`).
+    pub fn name(&self) -> &'static str {
+        status_code_name(self.status_code()).unwrap_or("CL_UNKNOWN_ERROR")
+    }
+
+    /// The API call that returned the status code, if it is known.
+    pub fn call(&self) -> Option<&'static str> {
+        self.site().call
+    }
+
+    /// The object of the API call, if it was recorded.
+    pub fn object(&self) -> Option<&str> {
+        self.site().object.as_ref().map(|o| &o[..])
+    }
+
+    /// Determines if the device or host ran out of memory or other resources.
+    pub fn is_out_of_resources(&self) -> bool {
+        match self {
+            ClError::OutOfResources(_)
+            | ClError::OutOfHostMemory(_)
+            | ClError::MemObjectAllocationFailure(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Determines if the same call may succeed when it is tried again later (e.g. after
+    /// other work on the device has finished and released its resources).
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClError::DeviceNotAvailable(_) => true,
+            _ => self.is_out_of_resources(),
+        }
+    }
+
+    /// Determines if a program failed to compile, link or build.
+    pub fn is_build_failure(&self) -> bool {
+        match self {
+            ClError::BuildProgramFailure(_)
+            | ClError::CompileProgramFailure(_)
+            | ClError::LinkProgramFailure(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ClError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "OpenCL error {} {} from {}",
+            self.status_code(),
+            self.name(),
+            self.site()
+        )
+    }
+}
+
+impl From<StatusCodeError> for ClError {
+    fn from(err: StatusCodeError) -> ClError {
+        ClError::new(err.status_code, ClErrorSite::unknown())
+    }
+}
+
+impl From<&ClError> for StatusCodeError {
+    fn from(err: &ClError) -> StatusCodeError {
+        StatusCodeError {
+            status_code: err.status_code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClError, ClErrorSite};
+    use crate::cl::StatusCodeError;
+
+    #[test]
+    fn cl_errors_are_created_from_status_codes() {
+        let err = ClError::new(
+            -5,
+            ClErrorSite::with_object("clEnqueueNDRangeKernel", "kernel add"),
+        );
+        assert_eq!(
+            err,
+            ClError::OutOfResources(ClErrorSite::with_object(
+                "clEnqueueNDRangeKernel",
+                "kernel add"
+            ))
+        );
+        assert_eq!(err.status_code(), -5);
+        assert_eq!(err.name(), "CL_OUT_OF_RESOURCES");
+        assert_eq!(err.call(), Some("clEnqueueNDRangeKernel"));
+        assert_eq!(err.object(), Some("kernel add"));
+        assert!(err.is_out_of_resources());
+        assert!(err.is_retryable());
+        assert_eq!(
+            format!("{}", err),
+            "OpenCL error -5 CL_OUT_OF_RESOURCES from clEnqueueNDRangeKernel for kernel add"
+        );
+
+        let unknown = ClError::new(-9999, ClErrorSite::new("clFinish"));
+        assert_eq!(unknown.status_code(), -9999);
+        assert_eq!(unknown.name(), "CL_UNKNOWN_ERROR");
+        assert!(!unknown.is_retryable());
+    }
+
+    #[test]
+    fn cl_error_check_records_the_call_and_object() {
+        assert!(ClError::check(0, "clFinish").is_ok());
+        let err = ClError::check_with(-46, "clCreateKernel", || "kernel nope").unwrap_err();
+        let cl_error = err.downcast_ref::<ClError>().unwrap();
+        assert_eq!(
+            *cl_error,
+            ClError::InvalidKernelName(ClErrorSite::with_object("clCreateKernel", "kernel nope"))
+        );
+        assert!(!cl_error.is_retryable());
+    }
+
+    #[test]
+    fn cl_errors_convert_to_and_from_status_code_errors() {
+        let err: ClError = StatusCodeError { status_code: -11 }.into();
+        assert!(err.is_build_failure());
+        assert_eq!(err.call(), None);
+        assert_eq!(
+            format!("{}", err),
+            "OpenCL error -11 CL_BUILD_PROGRAM_FAILURE from an unknown call"
+        );
+        assert_eq!(StatusCodeError::from(&err).status_code, -11);
+    }
+}
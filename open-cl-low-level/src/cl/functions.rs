@@ -8,7 +8,7 @@ macro_rules! _object_count {
             std::ptr::null_mut(),
             &mut output_size as *mut u32,
         );
-        StatusCodeError::check(err_code)?;
+        $crate::cl::ClError::check(err_code, stringify!($func))?;
         Ok(output_size)
     }};
 }
@@ -26,7 +26,7 @@ macro_rules! _get_objects {
                 output.as_mut_ptr() as *mut *mut libc::c_void,
                 std::ptr::null_mut(),
             );
-            StatusCodeError::check(status)?;
+            $crate::cl::ClError::check(status, stringify!($func))?;
             Ok(output)
         }
     }};
@@ -124,6 +124,11 @@ macro_rules! _empty_data {
         0 as cl_mem_flags
     }};
 
+    (One, cl_build_status, $n_bytes:expr) => {{
+        assert_eq!($n_bytes, std::mem::size_of::<cl_build_status>());
+        0 as cl_build_status
+    }};
+
     (One, cl_command_execution_status, $n_bytes:expr) => {{
         assert_eq!($n_bytes, std::mem::size_of::<cl_command_execution_status>());
         0 as cl_command_execution_status
@@ -210,7 +215,7 @@ macro_rules! cl_get_info {
             std::ptr::null_mut(),
             &mut n_bytes,
         );
-        $crate::cl::StatusCodeError::check(status_code)?;
+        $crate::cl::ClError::check(status_code, stringify!($func))?;
         let mut data: _data_type!($one_or_many, $output_t) =
             _empty_data!($one_or_many, $output_t, n_bytes);
         let status = $func(
@@ -220,7 +225,7 @@ macro_rules! cl_get_info {
             _as_ptr!($one_or_many, $output_t, data),
             std::ptr::null_mut(),
         );
-        $crate::cl::StatusCodeError::check(status)?;
+        $crate::cl::ClError::check(status, stringify!($func))?;
         let out: _output_type!($one_or_many, $output_t) =
             _finalize_data!($one_or_many, $output_t, data);
         Ok(out)
@@ -235,7 +240,7 @@ macro_rules! cl_get_info {
             std::ptr::null_mut(),
             &mut n_bytes,
         );
-        StatusCodeError::check(status_code)?;
+        $crate::cl::ClError::check(status_code, stringify!($func))?;
         let mut data: _data_type!($one_or_many, $output_t) =
             _empty_data!($one_or_many, $output_t, n_bytes);
         let status = $func(
@@ -246,7 +251,7 @@ macro_rules! cl_get_info {
             _as_ptr!($one_or_many, $output_t, data),
             std::ptr::null_mut(),
         );
-        StatusCodeError::check(status)?;
+        $crate::cl::ClError::check(status, stringify!($func))?;
         let out: _output_type!($one_or_many, $output_t) =
             _finalize_data!($one_or_many, $output_t, data);
         Ok(out)
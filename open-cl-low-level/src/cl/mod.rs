@@ -3,6 +3,9 @@ pub mod strings;
 pub mod status_code;
 pub use status_code::StatusCodeError;
 
+pub mod cl_error;
+pub use cl_error::{ClError, ClErrorSite};
+
 mod cl_bitflags;
 pub use cl_bitflags::*;
 
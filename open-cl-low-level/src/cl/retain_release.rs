@@ -43,7 +43,10 @@ macro_rules! impl_retain_release {
                 /// This function atomically decrements the OpenCL reference count. Mismanagement
                 /// of an object's OpenCL ARC can lead to undefined behavior.
                 unsafe fn retain(&self) {
-                    $crate::cl::StatusCodeError::check([<clRetain $pascal>](self.as_ptr() as *mut c_void))
+                    $crate::cl::ClError::check(
+                        [<clRetain $pascal>](self.as_ptr() as *mut c_void),
+                        concat!("clRetain", stringify!($pascal)),
+                    )
                         .unwrap_or_else(|e| {
                             panic!("Failed to retain cl_{} {:?} due to {:?}", stringify!($snake), self, e);
                         })
@@ -56,7 +59,10 @@ macro_rules! impl_retain_release {
                 /// This function atomically decrements the OpenCL reference count. Mismanagement
                 /// of an object's OpenCL ARC can lead to undefined behavior.
                 unsafe fn release(&mut self) {
-                    $crate::cl::StatusCodeError::check([<clRelease $pascal>](self.as_mut_ptr()))
+                    $crate::cl::ClError::check(
+                        [<clRelease $pascal>](self.as_mut_ptr()),
+                        concat!("clRelease", stringify!($pascal)),
+                    )
                         .unwrap_or_else(|e| {
                             panic!("Failed to release cl_{} {:?} due to {:?}", stringify!($snake), self, e);
                         })
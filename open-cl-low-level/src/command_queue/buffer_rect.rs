@@ -0,0 +1,71 @@
+/// The region of a rect read or write between a buffer and host memory.
+///
+/// All values are in bytes. The byte offset of (x, y, z) is
+/// `z * slice_pitch + y * row_pitch + x` on both the buffer side and the host side, and
+/// `region` is the (width in bytes, rows, slices) that is copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BufferRect {
+    pub buffer_origin: [usize; 3],
+    pub host_origin: [usize; 3],
+    pub region: [usize; 3],
+    pub buffer_row_pitch: usize,
+    pub buffer_slice_pitch: usize,
+    pub host_row_pitch: usize,
+    pub host_slice_pitch: usize,
+}
+
+impl BufferRect {
+    /// The byte offset one past the last byte touched on the buffer side.
+    pub fn buffer_end(&self) -> usize {
+        rect_end(
+            self.buffer_origin,
+            self.region,
+            self.buffer_row_pitch,
+            self.buffer_slice_pitch,
+        )
+    }
+
+    /// The byte offset one past the last byte touched on the host side.
+    pub fn host_end(&self) -> usize {
+        rect_end(
+            self.host_origin,
+            self.region,
+            self.host_row_pitch,
+            self.host_slice_pitch,
+        )
+    }
+
+    /// The number of bytes copied.
+    pub fn byte_count(&self) -> usize {
+        self.region[0] * self.region[1] * self.region[2]
+    }
+}
+
+fn rect_end(origin: [usize; 3], region: [usize; 3], row_pitch: usize, slice_pitch: usize) -> usize {
+    if region.iter().any(|r| *r == 0) {
+        return 0;
+    }
+    let start = origin[2] * slice_pitch + origin[1] * row_pitch + origin[0];
+    start + (region[2] - 1) * slice_pitch + (region[1] - 1) * row_pitch + region[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BufferRect;
+
+    #[test]
+    fn buffer_rect_ends_are_computed_from_the_pitches() {
+        let rect = BufferRect {
+            buffer_origin: [4, 1, 0],
+            host_origin: [0, 0, 0],
+            region: [8, 2, 3],
+            buffer_row_pitch: 16,
+            buffer_slice_pitch: 64,
+            host_row_pitch: 8,
+            host_slice_pitch: 16,
+        };
+        assert_eq!(rect.buffer_end(), 4 + 16 + 2 * 64 + 16 + 8);
+        assert_eq!(rect.host_end(), 48);
+        assert_eq!(rect.byte_count(), 48);
+    }
+}
@@ -1,14 +1,86 @@
-use super::{functions, CommandQueueOptions};
+use super::{functions, BufferRect, CommandQueueOptions};
 use crate::cl::{
-    cl_command_queue, cl_command_queue_properties, cl_context, cl_device_id, CommandQueueInfo,
-    CommandQueueProperties, ObjectWrapper,
+    cl_command_queue, cl_command_queue_properties, cl_context, cl_device_id, cl_mem,
+    CommandQueueInfo, CommandQueueProperties, MemMigrationFlags, ObjectWrapper,
 };
 use crate::vec_or_slice::{MutVecOrSlice, VecOrSlice};
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::{cl::MapFlags, cl_device_opencl_version, SvmBuffer, Waitlist};
 use crate::{
-    BufferReadEvent, Context, ContextPtr, Device, DevicePtr, Event, Kernel, KernelPtr, Mem, MemPtr,
-    Number, Output, Work,
+    BufferReadEvent, Context, ContextPtr, Device, DevicePtr, ErrorT, Event, Kernel, KernelPtr, Mem,
+    MemPtr, Number, Output, Work,
 };
 
+/// An error related to CommandQueue creation.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum CommandQueueError {
+    #[error("Invalid cl_command_queue_properties: {0}")]
+    InvalidProperties(cl_command_queue_properties),
+
+    #[error("The ON_DEVICE command queue property requires OUT_OF_ORDER_EXEC_MODE_ENABLE")]
+    OnDeviceRequiresOutOfOrder,
+
+    #[error("The ON_DEVICE_DEFAULT command queue property requires ON_DEVICE")]
+    OnDeviceDefaultRequiresOnDevice,
+
+    #[error("A command queue size can only be specified for ON_DEVICE command queues")]
+    QueueSizeRequiresOnDevice,
+
+    #[error("The command queue options require OpenCL 2.0: Got OpenCL {0}.{1}")]
+    RequiresOpenCL2(u32, u32),
+
+    #[error("The command queue options require the {0} crate feature")]
+    FeatureNotEnabled(&'static str),
+
+    #[error("The rect ends at byte {0} but the {1} is only {2} bytes")]
+    RectOutOfBounds(usize, &'static str, usize),
+}
+
+/// Checks that the rect lies within both the mem and the host buffer of host_len items.
+unsafe fn check_rect<T: Number>(rect: &BufferRect, mem: &Mem, host_len: usize) -> Output<()> {
+    let mem_size = mem.size()?;
+    if rect.buffer_end() > mem_size {
+        return Err(CommandQueueError::RectOutOfBounds(
+            rect.buffer_end(),
+            "buffer",
+            mem_size,
+        ))?;
+    }
+    let host_size = host_len * std::mem::size_of::<T>();
+    if rect.host_end() > host_size {
+        return Err(CommandQueueError::RectOutOfBounds(
+            rect.host_end(),
+            "host buffer",
+            host_size,
+        ))?;
+    }
+    Ok(())
+}
+
+impl CommandQueueProperties {
+    /// Checks the property combinations that OpenCL 2.x drivers reject.
+    pub fn validate(&self, queue_size: Option<u32>) -> Output<()> {
+        let on_device = self.contains(CommandQueueProperties::ON_DEVICE);
+        if on_device && !self.contains(CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE) {
+            return Err(CommandQueueError::OnDeviceRequiresOutOfOrder)?;
+        }
+        if self.contains(CommandQueueProperties::ON_DEVICE_DEFAULT) && !on_device {
+            return Err(CommandQueueError::OnDeviceDefaultRequiresOnDevice)?;
+        }
+        if queue_size.is_some() && !on_device {
+            return Err(CommandQueueError::QueueSizeRequiresOnDevice)?;
+        }
+        Ok(())
+    }
+
+    /// Determines if the properties can only be used with clCreateCommandQueueWithProperties.
+    pub fn requires_opencl_2(&self) -> bool {
+        self.intersects(
+            CommandQueueProperties::ON_DEVICE | CommandQueueProperties::ON_DEVICE_DEFAULT,
+        )
+    }
+}
+
 pub type CommandQueue = ObjectWrapper<cl_command_queue>;
 
 impl ObjectWrapper<cl_command_queue> {
@@ -22,15 +94,33 @@ impl ObjectWrapper<cl_command_queue> {
         context: &Context,
         device: &Device,
         opt_props: Option<CommandQueueProperties>,
+    ) -> Output<CommandQueue> {
+        CommandQueue::create_with_size(context, device, opt_props, None)
+    }
+
+    /// Create a new CommandQueue in the given Context on the given Device with the
+    /// given CommandQueueProperties (optional) and queue size (optional).
+    ///
+    /// A queue size is only valid for ON_DEVICE queues on OpenCL 2.0 devices.
+    ///
+    /// # Safety
+    /// Calling this function with an invalid Context or Device
+    /// is undefined behavior.
+    pub unsafe fn create_with_size(
+        context: &Context,
+        device: &Device,
+        opt_props: Option<CommandQueueProperties>,
+        queue_size: Option<u32>,
     ) -> Output<CommandQueue> {
         let properties = match opt_props {
             None => CommandQueueProperties::PROFILING_ENABLE,
             Some(prop) => prop,
         };
-        CommandQueue::create_from_raw_pointers(
+        CommandQueue::create_from_raw_pointers_with_size(
             context.context_ptr(),
             device.device_ptr(),
             properties.bits() as cl_command_queue_properties,
+            queue_size,
         )
     }
 
@@ -43,22 +133,79 @@ impl ObjectWrapper<cl_command_queue> {
         device: cl_device_id,
         props: cl_command_queue_properties,
     ) -> Output<CommandQueue> {
+        CommandQueue::create_from_raw_pointers_with_size(context, device, props, None)
+    }
+
+    /// Creates a CommandQueue from raw ClObject pointers with an optional queue size.
+    ///
+    /// With the opencl_version_2_0_0 crate feature, queues on devices that report OpenCL
+    /// 2.0 or later are created with clCreateCommandQueueWithProperties. Otherwise the
+    /// deprecated clCreateCommandQueue is used, which cannot create queues with properties
+    /// or sizes that require OpenCL 2.0.
+    ///
+    /// # Safety
+    /// Passing an invalid ClObject is undefined behavior.
+    pub unsafe fn create_from_raw_pointers_with_size(
+        context: cl_context,
+        device: cl_device_id,
+        props: cl_command_queue_properties,
+        queue_size: Option<u32>,
+    ) -> Output<CommandQueue> {
+        let properties = CommandQueueProperties::from_bits(props)
+            .ok_or(CommandQueueError::InvalidProperties(props))?;
+        properties.validate(queue_size)?;
+        let requires_opencl_2 = properties.requires_opencl_2() || queue_size.is_some();
+        #[cfg(feature = "opencl_version_2_0_0")]
+        {
+            let version = cl_device_opencl_version(device)?;
+            if version.0 >= 2 {
+                return functions::create_command_queue_with_properties(
+                    context, device, props, queue_size,
+                )
+                .map(|cq| CommandQueue::new(cq));
+            }
+            if requires_opencl_2 {
+                return Err(CommandQueueError::RequiresOpenCL2(version.0, version.1))?;
+            }
+        }
+        #[cfg(not(feature = "opencl_version_2_0_0"))]
+        {
+            if requires_opencl_2 {
+                return Err(CommandQueueError::FeatureNotEnabled("opencl_version_2_0_0"))?;
+            }
+        }
         functions::create_command_queue(context, device, props).map(|cq| CommandQueue::new(cq))
     }
 
     /// Creates a copy of a CommandQueue. The copy is, in fact, a completely differnt
-    /// CommandQueue that has the same cl_context and cl_device_id as the original.
+    /// CommandQueue that has the same cl_context, cl_device_id, properties and (for
+    /// ON_DEVICE queues) size as the original.
     ///
     /// # Safety
     /// Calling this function on an invalid CommandQueue is undefined behavior.
     pub unsafe fn create_copy(&self) -> Output<CommandQueue> {
-        CommandQueue::create_from_raw_pointers(
+        let props = self.cl_command_queue_properties()?;
+        CommandQueue::create_from_raw_pointers_with_size(
             self.cl_context()?,
             self.cl_device_id()?,
-            self.cl_command_queue_properties()?,
+            props,
+            self.copied_queue_size(props)?,
         )
     }
 
+    #[cfg(feature = "opencl_version_2_0_0")]
+    unsafe fn copied_queue_size(&self, props: cl_command_queue_properties) -> Output<Option<u32>> {
+        if props & CommandQueueProperties::ON_DEVICE.bits() == 0 {
+            return Ok(None);
+        }
+        self.size().map(Some)
+    }
+
+    #[cfg(not(feature = "opencl_version_2_0_0"))]
+    unsafe fn copied_queue_size(&self, _props: cl_command_queue_properties) -> Output<Option<u32>> {
+        Ok(None)
+    }
+
     /// write_buffer is used to move data from the host buffer (buffer: &[T]) to
     /// the mutable OpenCL cl_mem pointer.
     pub unsafe fn write_buffer<'a, T, H>(
@@ -139,6 +286,56 @@ impl ObjectWrapper<cl_command_queue> {
         .map(|e| Event::new(e))
     }
 
+    /// Copies the rect region of a ClMem<T> buffer into a &mut [T].
+    ///
+    /// # Safety
+    /// Usage of invalid ClObjects is undefined behavior.
+    pub unsafe fn read_buffer_rect<T>(
+        &mut self,
+        mem: &Mem,
+        host_buffer: &mut [T],
+        rect: &BufferRect,
+        opts: Option<CommandQueueOptions>,
+    ) -> Output<Event>
+    where
+        T: Number,
+    {
+        check_rect::<T>(rect, mem, host_buffer.len())?;
+        functions::enqueue_read_buffer_rect(
+            self.command_queue_ptr(),
+            mem.mem_ptr(),
+            host_buffer,
+            rect,
+            opts.into(),
+        )
+        .map(|e| Event::new(e))
+    }
+
+    /// Copies the rect region of a &[T] into a ClMem<T> buffer.
+    ///
+    /// # Safety
+    /// Usage of invalid ClObjects is undefined behavior.
+    pub unsafe fn write_buffer_rect<T>(
+        &mut self,
+        mem: &mut Mem,
+        host_buffer: &[T],
+        rect: &BufferRect,
+        opts: Option<CommandQueueOptions>,
+    ) -> Output<Event>
+    where
+        T: Number,
+    {
+        check_rect::<T>(rect, mem, host_buffer.len())?;
+        functions::enqueue_write_buffer_rect(
+            self.command_queue_ptr(),
+            mem.mem_ptr(),
+            host_buffer,
+            rect,
+            opts.into(),
+        )
+        .map(|e| Event::new(e))
+    }
+
     /// Enqueues a ClKernel onto a the CommandQueue.
     ///
     /// # Safety
@@ -159,9 +356,78 @@ impl ObjectWrapper<cl_command_queue> {
         Ok(Event::new(event))
     }
 
+    /// Migrates the mems to the device of the CommandQueue or, when `flags` contains
+    /// HOST, to the host. Passing CONTENT_UNDEFINED skips copying the mems' contents.
+    ///
+    /// # Safety
+    /// Usage of invalid ClObjects is undefined behavior.
+    pub unsafe fn migrate_mems(
+        &mut self,
+        mems: &[&Mem],
+        flags: MemMigrationFlags,
+        opts: Option<CommandQueueOptions>,
+    ) -> Output<Event> {
+        let cq_opts: CommandQueueOptions = opts.into();
+        let mem_ptrs: Vec<cl_mem> = mems.iter().map(|m| m.mem_ptr()).collect();
+        let event = functions::enqueue_migrate_mem_objects(
+            self.command_queue_ptr(),
+            &mem_ptrs[..],
+            flags.bits(),
+            &cq_opts.waitlist[..],
+        )?;
+        Ok(Event::new(event))
+    }
+
     pub unsafe fn finish(&mut self) -> Output<()> {
         functions::finish(self.cl_object())
     }
+
+    /// Maps a coarse-grained SvmBuffer for host access. The map is always blocking.
+    ///
+    /// The returned slice is only valid until the buffer is unmapped.
+    ///
+    /// # Safety
+    /// Usage of invalid ClObjects is undefined behavior. Using the buffer in
+    /// a kernel while it is mapped is undefined behavior.
+    #[cfg(feature = "opencl_version_2_0_0")]
+    pub unsafe fn map_svm_buffer<'a, T: Number>(
+        &mut self,
+        buffer: &'a mut SvmBuffer<T>,
+        map_flags: MapFlags,
+        opts: Option<CommandQueueOptions>,
+    ) -> Output<&'a mut [T]> {
+        let cq_opts: CommandQueueOptions = opts.into();
+        let event = functions::enqueue_svm_map(
+            self.command_queue_ptr(),
+            true,
+            map_flags.into(),
+            buffer.svm_ptr(),
+            buffer.size(),
+            &cq_opts.waitlist[..],
+        )?;
+        Event::new(event).wait()?;
+        Ok(buffer.mut_slice_unchecked())
+    }
+
+    /// Unmaps a previously mapped coarse-grained SvmBuffer.
+    ///
+    /// # Safety
+    /// Usage of invalid ClObjects is undefined behavior. Unmapping a buffer that
+    /// was not mapped is undefined behavior.
+    #[cfg(feature = "opencl_version_2_0_0")]
+    pub unsafe fn unmap_svm_buffer<T: Number>(
+        &mut self,
+        buffer: &mut SvmBuffer<T>,
+        opts: Option<CommandQueueOptions>,
+    ) -> Output<Event> {
+        let cq_opts: CommandQueueOptions = opts.into();
+        let event = functions::enqueue_svm_unmap(
+            self.command_queue_ptr(),
+            buffer.svm_ptr(),
+            &cq_opts.waitlist[..],
+        )?;
+        Ok(Event::new(event))
+    }
 }
 
 pub unsafe trait CommandQueuePtr: Sized {
@@ -201,6 +467,12 @@ pub unsafe trait CommandQueuePtr: Sized {
         )
     }
 
+    /// Returns the size of an ON_DEVICE CommandQueue.
+    #[cfg(feature = "opencl_version_2_0_0")]
+    unsafe fn size(&self) -> Output<u32> {
+        functions::get_info_u32(self.command_queue_ptr(), CommandQueueInfo::Size.into())
+    }
+
     unsafe fn properties(&self) -> Output<CommandQueueProperties> {
         self.cl_command_queue_properties().map(|props| {
             CommandQueueProperties::from_bits(props).unwrap_or_else(|| {
@@ -221,6 +493,36 @@ mod tests {
     use crate::cl::*;
     use crate::*;
 
+    #[test]
+    fn command_queue_properties_validate_rejects_invalid_combinations() {
+        let on_device = CommandQueueProperties::ON_DEVICE;
+        let out_of_order = CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE;
+        let default_on_device = CommandQueueProperties::ON_DEVICE_DEFAULT;
+        assert!(CommandQueueProperties::default().validate(None).is_ok());
+        assert!((on_device | out_of_order).validate(Some(1024)).is_ok());
+        assert!((on_device | out_of_order | default_on_device)
+            .validate(None)
+            .is_ok());
+
+        let err = on_device.validate(None).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CommandQueueError>(),
+            Some(&CommandQueueError::OnDeviceRequiresOutOfOrder)
+        );
+        let err = (out_of_order | default_on_device)
+            .validate(None)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CommandQueueError>(),
+            Some(&CommandQueueError::OnDeviceDefaultRequiresOnDevice)
+        );
+        let err = out_of_order.validate(Some(1024)).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CommandQueueError>(),
+            Some(&CommandQueueError::QueueSizeRequiresOnDevice)
+        );
+    }
+
     #[test]
     fn command_queue_can_be_created() {
         let (context, devices) = ll_testing::get_context();
@@ -334,4 +636,25 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn mems_can_be_migrated_to_each_device_and_back_to_the_host() {
+        let (mut cqs, context, _devices) = ll_testing::get_command_queues();
+        let mut data = vec![0u8, 1, 2, 3, 4, 5, 6, 7];
+        let buffer = ll_testing::mem_from_data_and_context(&mut data, &context);
+        for cq in cqs.iter_mut() {
+            unsafe {
+                let event = cq
+                    .migrate_mems(&[&buffer], MemMigrationFlags::default(), None)
+                    .unwrap();
+                event.wait().unwrap();
+                let event = cq
+                    .migrate_mems(&[&buffer], MemMigrationFlags::HOST, None)
+                    .unwrap();
+                event.wait().unwrap();
+                let mut event = cq.read_buffer(&buffer, vec![0u8; 8], None).unwrap();
+                assert_eq!(event.wait().unwrap(), Some(data.clone()));
+            }
+        }
+    }
 }
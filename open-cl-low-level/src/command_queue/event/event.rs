@@ -1,7 +1,12 @@
 use super::{functions, Waitlist};
-use crate::cl::{cl_event, CommandExecutionStatus, EventInfo, ObjectWrapper, ProfilingInfo};
+use crate::cl::{
+    cl_event, cl_int, ClError, ClErrorSite, CommandExecutionStatus, EventInfo, ObjectWrapper,
+    ProfilingInfo,
+};
 use crate::{CommandQueue, Context, Number, Output};
+use libc::c_void;
 use std::mem::ManuallyDrop;
+use std::panic::{self, AssertUnwindSafe};
 use std::time::Duration;
 use thiserror::Error;
 
@@ -34,6 +39,29 @@ unsafe impl EventPtr for &Event {
 pub enum EventError {
     #[error("Event was already consumed. {0}")]
     EventAlreadyConsumed(String),
+
+    #[error("Event callbacks cannot be registered for the {0:?} status")]
+    InvalidCallbackStatus(CommandExecutionStatus),
+}
+
+/// The status an event callback is called with. An event that terminated abnormally
+/// calls its callbacks with the error status code.
+pub type EventCallbackStatus = Result<CommandExecutionStatus, ClError>;
+
+type EventCallback = Box<dyn FnOnce(EventCallbackStatus) + Send>;
+
+/// Called by OpenCL (on a thread of its choosing) when an event reaches the registered
+/// status. The boxed closure is reclaimed here, so each callback is called exactly once.
+/// Panics are caught because unwinding into OpenCL is undefined behavior.
+extern "C" fn event_callback(_event: *mut c_void, status: cl_int, user_data: *mut c_void) {
+    let callback: Box<EventCallback> = unsafe { Box::from_raw(user_data as *mut EventCallback) };
+    let status = if status < 0 {
+        Err(ClError::new(status, ClErrorSite::new("clSetEventCallback")))
+    } else {
+        Ok(CommandExecutionStatus::from(status))
+    };
+    // The panic hook has already reported the panic; there is nobody to return it to.
+    let _ = panic::catch_unwind(AssertUnwindSafe(move || callback(status)));
 }
 
 impl Event {
@@ -82,8 +110,48 @@ impl Event {
     pub unsafe fn command_execution_status(&self) -> Output<CommandExecutionStatus> {
         functions::get_command_execution_status(self.event_ptr()).map(From::from)
     }
+
+    /// Calls the callback once the event's command has completed, or has terminated
+    /// with an error status.
+    pub fn on_complete<F>(&self, callback: F) -> Output<()>
+    where
+        F: FnOnce(EventCallbackStatus) + Send + 'static,
+    {
+        self.on_status(CommandExecutionStatus::Complete, callback)
+    }
+
+    /// Calls the callback once the event reaches the given execution status. The
+    /// callback may be called from an OpenCL thread and may be called immediately
+    /// if the event has already reached the status.
+    ///
+    /// Complete is supported from OpenCL 1.1 on. Submitted and Running require
+    /// OpenCL 1.2.
+    pub fn on_status<F>(&self, status: CommandExecutionStatus, callback: F) -> Output<()>
+    where
+        F: FnOnce(EventCallbackStatus) + Send + 'static,
+    {
+        if status == CommandExecutionStatus::Queued {
+            return Err(EventError::InvalidCallbackStatus(status))?;
+        }
+        let boxed: Box<EventCallback> = Box::new(Box::new(callback));
+        let user_data = Box::into_raw(boxed) as *mut c_void;
+        unsafe {
+            let registered = functions::set_event_callback(
+                self.event_ptr(),
+                status.into(),
+                event_callback,
+                user_data,
+            );
+            if registered.is_err() {
+                // OpenCL will never call the callback; reclaim it.
+                std::mem::drop(Box::from_raw(user_data as *mut EventCallback));
+            }
+            registered
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Profiling {
     pub queue_time: Option<u64>,
     pub submit_time: Option<u64>,
@@ -101,10 +169,11 @@ impl Profiling {
     }
 
     pub fn duration_between_submit_and_start(&self) -> Option<Duration> {
-        Some(Duration::from_nanos(self.submit_time? - self.queue_time?))
+        Some(Duration::from_nanos(self.start_time? - self.submit_time?))
     }
+
     pub fn duration_of_execution(&self) -> Option<Duration> {
-        Some(Duration::from_nanos(self.submit_time? - self.queue_time?))
+        Some(Duration::from_nanos(self.end_time? - self.start_time?))
     }
 }
 
@@ -129,6 +198,17 @@ where
         }
     }
 
+    /// The profiling info of the read command. The times are only available once
+    /// the read has completed.
+    pub fn profiling(&self) -> Profiling {
+        self.event.profiling()
+    }
+
+    /// The event of the read command.
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+
     pub fn wait(&mut self) -> Output<Option<Vec<T>>> {
         if self.is_consumed {
             return Err(EventError::EventAlreadyConsumed(self.event.address()))?;
@@ -190,7 +270,8 @@ impl CompleteEvent {
 mod tests {
     use crate::cl::CommandExecutionStatus;
     use crate::{
-        BufferBuilder, CommandQueue, Context, Event, Kernel, Mem, Session, SessionBuilder, Work,
+        BufferBuilder, CommandQueue, Context, Event, EventError, Kernel, Mem, Session,
+        SessionBuilder, Work,
     };
 
     use std::time::Duration;
@@ -336,4 +417,60 @@ mod tests {
             max_duration_of_execution
         );
     }
+
+    #[test]
+    fn event_on_complete_calls_the_callback() {
+        let (_sess, event) = get_event();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        event
+            .on_complete(move |status| sender.send(status).unwrap())
+            .unwrap();
+        let status = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(status, Ok(CommandExecutionStatus::Complete));
+    }
+
+    #[test]
+    fn event_on_complete_survives_a_panicking_callback() {
+        let (_sess, event) = get_event();
+        event.on_complete(|_| panic!("callback panic")).unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        event
+            .on_complete(move |status| sender.send(status).unwrap())
+            .unwrap();
+        let status = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(status, Ok(CommandExecutionStatus::Complete));
+    }
+
+    #[test]
+    fn event_on_status_rejects_the_queued_status() {
+        let (_sess, event) = get_event();
+        let err = event
+            .on_status(CommandExecutionStatus::Queued, |_| ())
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<EventError>(),
+            Some(&EventError::InvalidCallbackStatus(
+                CommandExecutionStatus::Queued
+            ))
+        );
+    }
+
+    #[test]
+    fn profiling_durations_are_measured_between_the_right_times() {
+        let profiling = super::Profiling {
+            queue_time: Some(100),
+            submit_time: Some(150),
+            start_time: Some(400),
+            end_time: Some(1_000),
+        };
+        assert_eq!(
+            profiling.duration_between_submit_and_start(),
+            Some(Duration::from_nanos(250))
+        );
+        assert_eq!(
+            profiling.duration_of_execution(),
+            Some(Duration::from_nanos(600))
+        );
+        assert_eq!(super::Profiling::default().duration_of_execution(), None);
+    }
 }
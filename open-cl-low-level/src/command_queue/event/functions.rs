@@ -1,8 +1,8 @@
 use super::Waitlist;
 use crate::cl::{
-    clGetEventInfo, clGetEventProfilingInfo, clWaitForEvents, cl_command_execution_status,
-    cl_command_queue, cl_context, cl_event, cl_event_info, cl_profiling_info, ClObject, EventInfo,
-    StatusCodeError,
+    clGetEventInfo, clGetEventProfilingInfo, clSetEventCallback, clWaitForEvents,
+    cl_command_execution_status, cl_command_queue, cl_context, cl_event, cl_event_info, cl_int,
+    cl_profiling_info, ClError, ClObject, EventInfo,
 };
 use crate::Output;
 use libc::{c_void, size_t};
@@ -20,7 +20,7 @@ pub unsafe fn cl_get_event_profiling_info(
         (&mut time as *mut u64) as *mut c_void,
         std::ptr::null_mut(),
     );
-    StatusCodeError::check(err_code)?;
+    ClError::check(err_code, "clGetEventProfilingInfo")?;
     Ok(time)
 }
 
@@ -103,5 +103,30 @@ pub unsafe fn get_command_execution_status(event: cl_event) -> Output<cl_command
 /// has been freed, if any of the events is a null pointer, if the queue the event was created with
 /// is freed, and a plethora of other conditions.
 pub unsafe fn wait_for_events<'a>(wl: &'a [cl_event]) -> Output<()> {
-    StatusCodeError::check(clWaitForEvents(wl.waitlist_len(), wl.waitlist_ptr()))
+    ClError::check(
+        clWaitForEvents(wl.waitlist_len(), wl.waitlist_ptr()),
+        "clWaitForEvents",
+    )
+}
+
+/// Registers an OpenCL event callback for the given execution status.
+///
+/// # Safety
+/// Calling this function with an invalid cl_event is undefined behavior. The user_data
+/// must stay valid until the callback is called.
+pub unsafe fn set_event_callback(
+    mut event: cl_event,
+    command_exec_callback_type: cl_int,
+    callback: extern "C" fn(*mut c_void, cl_int, *mut c_void),
+    user_data: *mut c_void,
+) -> Output<()> {
+    ClError::check(
+        clSetEventCallback(
+            event.as_mut_ptr(),
+            command_exec_callback_type,
+            Some(callback),
+            user_data,
+        ),
+        "clSetEventCallback",
+    )
 }
@@ -1,13 +1,20 @@
-use super::{functions, CommandQueueOptions};
+use super::{functions, BufferRect, CommandQueueOptions};
 use crate::cl::{
-    clCreateCommandQueue, clEnqueueNDRangeKernel, clEnqueueReadBuffer, clEnqueueWriteBuffer,
-    clFinish, clGetCommandQueueInfo, cl_command_queue, cl_command_queue_info,
-    cl_command_queue_properties, cl_context, cl_device_id, cl_event, cl_kernel, cl_mem, ClObject,
-    CommandQueueInfo, StatusCodeError,
+    clCreateCommandQueue, clEnqueueMigrateMemObjects, clEnqueueNDRangeKernel, clEnqueueReadBuffer,
+    clEnqueueReadBufferRect, clEnqueueWriteBuffer, clEnqueueWriteBufferRect, clFinish,
+    clGetCommandQueueInfo, cl_command_queue, cl_command_queue_info, cl_command_queue_properties,
+    cl_context, cl_device_id, cl_event, cl_kernel, cl_mem, cl_mem_migration_flags, ClError,
+    ClObject, CommandQueueInfo, KernelInfo,
 };
 use crate::{BufferBuilder, GlobalWorkSize, LocalWorkSize, Number, Output, Waitlist, Work};
 use libc::c_void;
 
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::cl::{
+    clCreateCommandQueueWithProperties, clEnqueueSVMMap, clEnqueueSVMUnmap, cl_map_flags,
+    cl_queue_properties,
+};
+
 /// Creates a new cl_command_queue.
 ///
 /// # Safety
@@ -24,7 +31,41 @@ pub unsafe fn create_command_queue(
         flags,
         &mut status_code,
     );
-    StatusCodeError::check(status_code)?;
+    ClError::check(status_code, "clCreateCommandQueue")?;
+    cl_command_queue::new(command_queue)
+}
+
+/// Creates a new cl_command_queue with clCreateCommandQueueWithProperties.
+///
+/// The queue properties list is assembled from the given flags and the optional
+/// queue size (only valid for ON_DEVICE queues) and is zero-terminated.
+///
+/// # Safety
+/// Usage of an invalid ClObject is undefined behavior.
+#[cfg(feature = "opencl_version_2_0_0")]
+pub unsafe fn create_command_queue_with_properties(
+    mut context: cl_context,
+    mut device: cl_device_id,
+    flags: cl_command_queue_properties,
+    queue_size: Option<u32>,
+) -> Output<cl_command_queue> {
+    let mut properties: Vec<cl_queue_properties> = vec![
+        CommandQueueInfo::Properties as cl_queue_properties,
+        flags as cl_queue_properties,
+    ];
+    if let Some(size) = queue_size {
+        properties.push(CommandQueueInfo::Size as cl_queue_properties);
+        properties.push(size as cl_queue_properties);
+    }
+    properties.push(0);
+    let mut status_code = 0;
+    let command_queue = clCreateCommandQueueWithProperties(
+        context.as_mut_ptr(),
+        device.as_mut_ptr(),
+        properties.as_ptr(),
+        &mut status_code,
+    );
+    ClError::check(status_code, "clCreateCommandQueueWithProperties")?;
     cl_command_queue::new(command_queue)
 }
 
@@ -33,7 +74,7 @@ pub unsafe fn create_command_queue(
 /// # Safety
 /// Usage of an invalid ClObject is undefined behavior.
 pub unsafe fn finish(mut command_queue: cl_command_queue) -> Output<()> {
-    StatusCodeError::check(clFinish(command_queue.as_mut_ptr()))
+    ClError::check(clFinish(command_queue.as_mut_ptr()), "clFinish")
 }
 
 /// Queues an n-dimensionally ranged kernel to be executed.
@@ -66,7 +107,14 @@ pub unsafe fn enqueue_kernel<W: Waitlist>(
         &mut tracking_event,
     );
 
-    StatusCodeError::check(status_code)?;
+    ClError::check_with(status_code, "clEnqueueNDRangeKernel", || {
+        let name =
+            crate::kernel::functions::get_info_string(kernel, KernelInfo::FunctionName.into());
+        format!(
+            "kernel {}",
+            name.unwrap_or_else(|_| "<unknown>".to_string())
+        )
+    })?;
     functions::finish(queue)?;
 
     // TODO: Remove this check when Event checks for null pointer
@@ -102,7 +150,9 @@ where
         &mut tracking_event,
     );
 
-    StatusCodeError::check(status_code)?;
+    ClError::check_with(status_code, "clEnqueueReadBuffer", || {
+        format!("{} bytes", buffer.buffer_len() * std::mem::size_of::<T>())
+    })?;
     cl_event::new(tracking_event)
 }
 
@@ -130,7 +180,174 @@ where
         wl.waitlist_ptr(),
         &mut tracking_event,
     );
-    StatusCodeError::check(status_code)?;
+    ClError::check_with(status_code, "clEnqueueWriteBuffer", || {
+        format!("{} bytes", buffer.buffer_len() * std::mem::size_of::<T>())
+    })?;
+    cl_event::new(tracking_event)
+}
+
+/// Reads the rect region of the mem into the host buffer.
+///
+/// # Safety
+/// Usage of an invalid ClObject is undefined behavior. The rect must lie within both the
+/// mem and the host buffer.
+pub unsafe fn enqueue_read_buffer_rect<T>(
+    mut queue: cl_command_queue,
+    mut mem: cl_mem,
+    buffer: &mut [T],
+    rect: &BufferRect,
+    command_queue_opts: CommandQueueOptions,
+) -> Output<cl_event>
+where
+    T: Number,
+{
+    let mut tracking_event = std::ptr::null_mut();
+    let waitlist = command_queue_opts.new_waitlist();
+    let wl = waitlist.as_slice();
+    let status_code = clEnqueueReadBufferRect(
+        queue.as_mut_ptr(),
+        mem.as_mut_ptr(),
+        command_queue_opts.is_blocking as u32,
+        rect.buffer_origin.as_ptr(),
+        rect.host_origin.as_ptr(),
+        rect.region.as_ptr(),
+        rect.buffer_row_pitch,
+        rect.buffer_slice_pitch,
+        rect.host_row_pitch,
+        rect.host_slice_pitch,
+        buffer.buffer_ptr(),
+        wl.waitlist_len(),
+        wl.waitlist_ptr(),
+        &mut tracking_event,
+    );
+    ClError::check_with(status_code, "clEnqueueReadBufferRect", || {
+        format!("{} bytes", rect.byte_count())
+    })?;
+    cl_event::new(tracking_event)
+}
+
+/// Writes the rect region of the host buffer into the mem.
+///
+/// # Safety
+/// Usage of an invalid ClObject is undefined behavior. The rect must lie within both the
+/// mem and the host buffer.
+pub unsafe fn enqueue_write_buffer_rect<T>(
+    mut queue: cl_command_queue,
+    mut mem: cl_mem,
+    buffer: &[T],
+    rect: &BufferRect,
+    command_queue_opts: CommandQueueOptions,
+) -> Output<cl_event>
+where
+    T: Number,
+{
+    let mut tracking_event = std::ptr::null_mut();
+    let waitlist = command_queue_opts.new_waitlist();
+    let wl = waitlist.as_slice();
+    let status_code = clEnqueueWriteBufferRect(
+        queue.as_mut_ptr(),
+        mem.as_mut_ptr(),
+        command_queue_opts.is_blocking as u32,
+        rect.buffer_origin.as_ptr(),
+        rect.host_origin.as_ptr(),
+        rect.region.as_ptr(),
+        rect.buffer_row_pitch,
+        rect.buffer_slice_pitch,
+        rect.host_row_pitch,
+        rect.host_slice_pitch,
+        buffer.buffer_ptr(),
+        wl.waitlist_len(),
+        wl.waitlist_ptr(),
+        &mut tracking_event,
+    );
+    ClError::check_with(status_code, "clEnqueueWriteBufferRect", || {
+        format!("{} bytes", rect.byte_count())
+    })?;
+    cl_event::new(tracking_event)
+}
+
+/// Enqueues the migration of mem objects to the device of the command queue or,
+/// with the HOST flag, to the host.
+///
+/// # Safety
+/// Usage of an invalid ClObject is undefined behavior.
+pub unsafe fn enqueue_migrate_mem_objects<W: Waitlist>(
+    mut queue: cl_command_queue,
+    mems: &[cl_mem],
+    flags: cl_mem_migration_flags,
+    waitlist: W,
+) -> Output<cl_event> {
+    let mut tracking_event = std::ptr::null_mut();
+    let mem_ptrs: Vec<*mut c_void> = mems.iter().map(|m| m.as_ptr() as *mut c_void).collect();
+    let event_waitlist = waitlist.new_waitlist();
+    let wl = event_waitlist.as_slice();
+    let status_code = clEnqueueMigrateMemObjects(
+        queue.as_mut_ptr(),
+        mem_ptrs.len() as u32,
+        mem_ptrs.as_ptr(),
+        flags,
+        wl.waitlist_len(),
+        wl.waitlist_ptr(),
+        &mut tracking_event,
+    );
+    ClError::check_with(status_code, "clEnqueueMigrateMemObjects", || {
+        format!("{} mems", mems.len())
+    })?;
+    cl_event::new(tracking_event)
+}
+
+/// Maps a coarse-grained SVM buffer for host access.
+///
+/// # Safety
+/// Usage of an invalid ClObject or an invalid SVM pointer is undefined behavior.
+/// The size must not exceed the size of the SVM allocation.
+#[cfg(feature = "opencl_version_2_0_0")]
+pub unsafe fn enqueue_svm_map<W: Waitlist>(
+    mut queue: cl_command_queue,
+    is_blocking: bool,
+    map_flags: cl_map_flags,
+    svm_ptr: *mut c_void,
+    size_in_bytes: usize,
+    waitlist: W,
+) -> Output<cl_event> {
+    let mut tracking_event = std::ptr::null_mut();
+    let event_waitlist = waitlist.new_waitlist();
+    let wl = event_waitlist.as_slice();
+    let status_code = clEnqueueSVMMap(
+        queue.as_mut_ptr(),
+        is_blocking as u32,
+        map_flags,
+        svm_ptr,
+        size_in_bytes,
+        wl.waitlist_len(),
+        wl.waitlist_ptr(),
+        &mut tracking_event,
+    );
+    ClError::check(status_code, "clEnqueueSVMMap")?;
+    cl_event::new(tracking_event)
+}
+
+/// Unmaps a previously mapped coarse-grained SVM buffer.
+///
+/// # Safety
+/// Usage of an invalid ClObject or an SVM pointer that was not mapped is undefined behavior.
+#[cfg(feature = "opencl_version_2_0_0")]
+pub unsafe fn enqueue_svm_unmap<W: Waitlist>(
+    mut queue: cl_command_queue,
+    svm_ptr: *mut c_void,
+    waitlist: W,
+) -> Output<cl_event> {
+    let mut tracking_event = std::ptr::null_mut();
+    let event_waitlist = waitlist.new_waitlist();
+    let wl = event_waitlist.as_slice();
+    let status_code = clEnqueueSVMUnmap(
+        queue.as_mut_ptr(),
+        svm_ptr,
+        wl.waitlist_len(),
+        wl.waitlist_ptr(),
+        &mut tracking_event,
+    );
+    ClError::check(status_code, "clEnqueueSVMUnmap")?;
     cl_event::new(tracking_event)
 }
 
@@ -6,5 +6,8 @@ pub use event::*;
 mod command_queue_options;
 pub use command_queue_options::*;
 
+mod buffer_rect;
+pub use buffer_rect::*;
+
 mod command_queue;
 pub use command_queue::*;
@@ -1,6 +1,7 @@
-use super::functions;
+use super::context_properties::{build_properties_list, parse_properties_list};
+use super::{functions, ContextErrorCallback, ContextProperty};
 use crate::cl::{cl_context, cl_device_id, ClObject, ObjectWrapper};
-use crate::cl::{cl_context_properties, ContextInfo, ContextProperties};
+use crate::cl::{cl_context_properties, ContextInfo, DeviceType};
 use crate::{Device, DevicePtr, Output};
 
 pub unsafe trait ContextPtr: Sized {
@@ -21,15 +22,17 @@ pub unsafe trait ContextPtr: Sized {
             .collect();
         Ok(devices)
     }
-    unsafe fn properties(&self) -> Output<Vec<ContextProperties>> {
-        let ctx_props = functions::get_context_info_vec_u64(
+
+    /// The properties the context was created with. Empty if it was created without any.
+    unsafe fn properties(&self) -> Output<Vec<ContextProperty>> {
+        let list: Vec<cl_context_properties> = functions::get_context_info_vec_u64(
             self.context_ptr(),
             ContextInfo::Properties.into(),
         )?
         .into_iter()
-        .map(|p: u64| ContextProperties::from(p as cl_context_properties))
+        .map(|p: u64| p as cl_context_properties)
         .collect();
-        Ok(ctx_props)
+        parse_properties_list(&list[..])
     }
 
     unsafe fn num_devices(&self) -> Output<u32> {
@@ -40,12 +43,53 @@ pub unsafe trait ContextPtr: Sized {
 pub type Context = ObjectWrapper<cl_context>;
 
 impl Context {
+    /// Creates a context whose driver errors are forwarded to the `log` crate.
     pub unsafe fn create<D>(devices: &[D]) -> Output<Context>
+    where
+        D: DevicePtr,
+    {
+        Context::create_with_error_callback(devices, ContextErrorCallback::default())
+    }
+
+    /// Creates a context that calls the error callback when the driver reports an error
+    /// in the context.
+    pub unsafe fn create_with_error_callback<D>(
+        devices: &[D],
+        error_callback: ContextErrorCallback,
+    ) -> Output<Context>
+    where
+        D: DevicePtr,
+    {
+        Context::create_with_properties(devices, &[], error_callback)
+    }
+
+    /// Creates a context for the devices with the given properties. Each property may be
+    /// given at most once.
+    pub unsafe fn create_with_properties<D>(
+        devices: &[D],
+        properties: &[ContextProperty],
+        error_callback: ContextErrorCallback,
+    ) -> Output<Context>
     where
         D: DevicePtr,
     {
         let device_ptrs: Vec<cl_device_id> = devices.iter().map(|d| d.device_ptr()).collect();
-        let obj = functions::create_context(&device_ptrs[..])?;
+        let props = build_properties_list(properties)?;
+        let obj = functions::create_context(&props[..], &device_ptrs[..], error_callback)?;
+        Ok(Context::new(obj))
+    }
+
+    /// Creates a context for all the devices of the device type via clCreateContextFromType.
+    /// Without a `ContextProperty::Platform` the platform is picked by the OpenCL
+    /// implementation, which is unpredictable when several ICDs are installed.
+    pub unsafe fn create_from_type(
+        device_type: DeviceType,
+        properties: &[ContextProperty],
+        error_callback: ContextErrorCallback,
+    ) -> Output<Context> {
+        let props = build_properties_list(properties)?;
+        let obj =
+            functions::create_context_from_type(&props[..], device_type.bits(), error_callback)?;
         Ok(Context::new(obj))
     }
 }
@@ -58,6 +102,7 @@ unsafe impl ContextPtr for Context {
 
 #[cfg(test)]
 mod test_context_ptr {
+    use crate::cl::DeviceType;
     use crate::*;
 
     #[test]
@@ -81,6 +126,66 @@ mod test_context_ptr {
         let _props = unsafe { ctx.properties() }.unwrap();
     }
 
+    #[test]
+    fn properties_round_trip_through_context_creation() {
+        let platform = Platform::list_all().unwrap().remove(0);
+        let devices = platform.list_devices().unwrap();
+        let properties = vec![ContextProperty::Platform(platform)];
+        let ctx = unsafe {
+            Context::create_with_properties(
+                &devices[..],
+                &properties[..],
+                ContextErrorCallback::default(),
+            )
+        }
+        .unwrap();
+        assert_eq!(unsafe { ctx.properties() }.unwrap(), properties);
+    }
+
+    #[test]
+    fn create_from_type_works_with_a_platform_property() {
+        let platform = Platform::list_all().unwrap().remove(0);
+        let properties = vec![ContextProperty::Platform(platform.clone())];
+        let ctx = unsafe {
+            Context::create_from_type(
+                DeviceType::ALL,
+                &properties[..],
+                ContextErrorCallback::default(),
+            )
+        }
+        .unwrap();
+        let n_devices = unsafe { ctx.num_devices() }.unwrap();
+        assert_eq!(n_devices as usize, platform.list_devices().unwrap().len());
+    }
+
+    #[test]
+    fn custom_error_callback_outlives_the_last_context_reference() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let (_ctx, devices) = ll_testing::get_context();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls2 = calls.clone();
+        let callback: Arc<ContextErrorFn> = Arc::new(move |_: &str, _: &[u8]| {
+            calls2.fetch_add(1, Ordering::SeqCst);
+        });
+        let ctx = unsafe {
+            Context::create_with_error_callback(
+                &devices[..],
+                ContextErrorCallback::Custom(callback.clone()),
+            )
+        }
+        .unwrap();
+        let ctx2 = ctx.clone();
+        std::mem::drop(ctx);
+        std::mem::drop(ctx2);
+        // The context's reference to the closure is leaked, so the driver can still call
+        // it after the last release.
+        assert_eq!(Arc::strong_count(&callback), 2);
+        callback("late error", &[]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn num_devices_works() {
         let (ctx, _devices) = ll_testing::get_context();
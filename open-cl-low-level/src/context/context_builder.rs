@@ -1,5 +1,6 @@
+use super::{ContextErrorCallback, ContextProperty};
 use crate::cl::DeviceType;
-use crate::{Context, Device, Output, Platform};
+use crate::{Context, ContextPtr, Device, Output, Platform};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq, Eq, Clone)]
@@ -9,6 +10,9 @@ pub enum ContextBuilderError {
 
     #[error("For context building devices and platforms cannot both be specified")]
     CannotSpecifyDevicesAndPlatforms,
+
+    #[error("For context building platforms and a platform property cannot both be specified")]
+    CannotSpecifyPlatformsAndPlatformProperty,
 }
 
 use ContextBuilderError::*;
@@ -17,6 +21,8 @@ pub struct ContextBuilder<'a> {
     pub platforms: Option<&'a [Platform]>,
     pub device_type: Option<DeviceType>,
     pub devices: Option<&'a [Device]>,
+    pub error_callback: Option<ContextErrorCallback>,
+    pub properties: Vec<ContextProperty>,
 }
 
 impl<'a> ContextBuilder<'a> {
@@ -25,6 +31,8 @@ impl<'a> ContextBuilder<'a> {
             platforms: None,
             device_type: None,
             devices: None,
+            error_callback: None,
+            properties: Vec::new(),
         }
     }
 
@@ -43,44 +51,63 @@ impl<'a> ContextBuilder<'a> {
         self
     }
 
+    /// Sets the callback for errors the driver reports in the context. Without one the
+    /// errors are forwarded to the `log` crate.
+    pub fn with_error_callback(
+        mut self,
+        error_callback: ContextErrorCallback,
+    ) -> ContextBuilder<'a> {
+        self.error_callback = Some(error_callback);
+        self
+    }
+
+    /// Adds a property to the context's zero-terminated properties list. Each property may
+    /// be added at most once.
+    ///
+    /// With a `ContextProperty::Platform` and no devices or platforms the context is created
+    /// with clCreateContextFromType for the devices of the device type on that platform.
+    pub fn with_property(mut self, property: ContextProperty) -> ContextBuilder<'a> {
+        self.properties.push(property);
+        self
+    }
+
     pub unsafe fn build(self) -> Output<BuiltContext> {
-        use ContextBuilder as B;
-        match self {
-            B {
-                device_type: Some(device_type),
-                devices: None,
-                platforms: None,
-            } => ContextBuilder::build_from_device_type(device_type),
-            B {
-                devices: Some(devices),
-                device_type: None,
-                platforms: None,
-            } => ContextBuilder::build_from_devices(devices),
-            B {
-                platforms: Some(platforms),
-                device_type: None,
-                devices: None,
-            } => ContextBuilder::build_from_platforms(platforms),
-            B {
-                platforms: Some(platforms),
-                device_type: Some(device_type),
-                devices: None,
-            } => ContextBuilder::build_from_platforms_with_device_type(platforms, device_type),
-            B {
-                platforms: None,
-                device_type: None,
-                devices: None,
-            } => ContextBuilder::build_with_defaults(),
-            B {
-                device_type: Some(_),
-                devices: Some(_),
-                ..
-            } => Err(CannotSpecifyDevicesAndDeviceType)?,
-            B {
-                devices: Some(_),
-                platforms: Some(_),
-                ..
-            } => Err(CannotSpecifyDevicesAndPlatforms)?,
+        let error_callback = self.error_callback.unwrap_or_default();
+        let properties = &self.properties[..];
+        let has_platform_property = properties
+            .iter()
+            .any(|p| matches!(p, ContextProperty::Platform(_)));
+        match (self.platforms, self.device_type, self.devices) {
+            (None, None, Some(devices)) => {
+                let context = Context::create_with_properties(devices, properties, error_callback)?;
+                Ok(BuiltContext::Context(context))
+            }
+            (_, Some(_), Some(_)) => Err(CannotSpecifyDevicesAndDeviceType)?,
+            (Some(_), _, Some(_)) => Err(CannotSpecifyDevicesAndPlatforms)?,
+            (Some(_), _, None) if has_platform_property => {
+                Err(CannotSpecifyPlatformsAndPlatformProperty)?
+            }
+            (None, device_type, None) if has_platform_property => {
+                let device_type = device_type.unwrap_or(DeviceType::ALL);
+                let context = Context::create_from_type(device_type, properties, error_callback)?;
+                let devices = context.devices()?;
+                Ok(BuiltContext::ContextWithDevices(context, devices))
+            }
+            (platforms, device_type, None) => {
+                let all_platforms;
+                let platforms = match platforms {
+                    Some(platforms) => platforms,
+                    None => {
+                        all_platforms = Platform::list_all()?;
+                        &all_platforms[..]
+                    }
+                };
+                let device_type = device_type.unwrap_or(DeviceType::ALL);
+                let devices = ContextBuilder::list_devices(platforms, device_type)?;
+                let context =
+                    Context::create_with_properties(&devices[..], properties, error_callback)?;
+                Ok(BuiltContext::ContextWithDevices(context, devices))
+            }
         }
     }
 
@@ -97,13 +124,18 @@ impl<'a> ContextBuilder<'a> {
         platforms: &[Platform],
         device_type: DeviceType,
     ) -> Output<BuiltContext> {
+        let devices = ContextBuilder::list_devices(platforms, device_type)?;
+        let context = Context::create(&devices[..])?;
+        Ok(BuiltContext::ContextWithDevices(context, devices))
+    }
+
+    unsafe fn list_devices(platforms: &[Platform], device_type: DeviceType) -> Output<Vec<Device>> {
         let mut devices = Vec::new();
         for p in platforms.iter() {
             let p_devices = p.list_devices_by_type(device_type)?;
             devices.extend(p_devices);
         }
-        let context = Context::create(&devices[..])?;
-        Ok(BuiltContext::ContextWithDevices(context, devices))
+        Ok(devices)
     }
 
     pub unsafe fn build_from_devices(devices: &[Device]) -> Output<BuiltContext> {
@@ -0,0 +1,142 @@
+use crate::cl::{cl_context_properties, cl_platform_id, ClObject, ContextProperties};
+use crate::{ErrorT, Output, Platform};
+use libc::c_void;
+
+/// An error related to the properties of a Context.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum ContextPropertiesError {
+    #[error("Context property {0:?} was specified more than once")]
+    DuplicateProperty(ContextProperties),
+
+    #[error("Context properties list is not zero-terminated")]
+    UnterminatedList,
+}
+
+/// A context property with its value. A list of these is turned into the zero-terminated
+/// `cl_context_properties` list of clCreateContext and clCreateContextFromType.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextProperty {
+    /// CL_CONTEXT_PLATFORM - the platform the context is created for.
+    Platform(Platform),
+
+    /// CL_CONTEXT_INTEROP_USER_SYNC - whether the user is responsible for synchronization
+    /// between OpenCL and other APIs (e.g. OpenGL) sharing the context's objects.
+    InteropUserSync(bool),
+}
+
+impl ContextProperty {
+    pub fn key(&self) -> ContextProperties {
+        match self {
+            ContextProperty::Platform(_) => ContextProperties::Platform,
+            ContextProperty::InteropUserSync(_) => ContextProperties::InteropUserSync,
+        }
+    }
+
+    unsafe fn value(&self) -> cl_context_properties {
+        match self {
+            ContextProperty::Platform(platform) => {
+                platform.cl_object().as_ptr() as cl_context_properties
+            }
+            ContextProperty::InteropUserSync(sync) => *sync as cl_context_properties,
+        }
+    }
+}
+
+/// Builds the zero-terminated key/value list of the properties. Each key may appear at
+/// most once.
+pub(crate) unsafe fn build_properties_list(
+    properties: &[ContextProperty],
+) -> Output<Vec<cl_context_properties>> {
+    let mut list = Vec::with_capacity(properties.len() * 2 + 1);
+    for (i, property) in properties.iter().enumerate() {
+        let key = property.key();
+        if properties[..i].iter().any(|p| p.key() == key) {
+            return Err(ContextPropertiesError::DuplicateProperty(key))?;
+        }
+        list.push(key.into());
+        list.push(property.value());
+    }
+    list.push(0);
+    Ok(list)
+}
+
+/// Parses a zero-terminated key/value list as returned by CL_CONTEXT_PROPERTIES. An empty
+/// list means the context was created without properties. Keys this crate does not know
+/// about (e.g. those of interop extensions) are skipped along with their values.
+pub(crate) unsafe fn parse_properties_list(
+    list: &[cl_context_properties],
+) -> Output<Vec<ContextProperty>> {
+    let mut properties = Vec::new();
+    let mut pairs = list.chunks(2);
+    loop {
+        match pairs.next() {
+            None if list.is_empty() => return Ok(properties),
+            None => return Err(ContextPropertiesError::UnterminatedList)?,
+            Some([0]) | Some([0, _]) => return Ok(properties),
+            Some([key, value]) => {
+                let key = *key;
+                if key == cl_context_properties::from(ContextProperties::Platform) {
+                    let platform_id = cl_platform_id::new(*value as *mut c_void)?;
+                    properties.push(ContextProperty::Platform(Platform::new(platform_id)));
+                } else if key == cl_context_properties::from(ContextProperties::InteropUserSync) {
+                    properties.push(ContextProperty::InteropUserSync(*value != 0));
+                }
+            }
+            Some(_) => return Err(ContextPropertiesError::UnterminatedList)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties_list_is_zero_terminated_and_round_trips() {
+        let platform = Platform::list_all().unwrap().remove(0);
+        let properties = vec![
+            ContextProperty::Platform(platform.clone()),
+            ContextProperty::InteropUserSync(true),
+        ];
+        let list = unsafe { build_properties_list(&properties[..]) }.unwrap();
+        assert_eq!(list.len(), 5);
+        assert_eq!(
+            list[0],
+            cl_context_properties::from(ContextProperties::Platform)
+        );
+        assert_eq!(
+            list[2],
+            cl_context_properties::from(ContextProperties::InteropUserSync)
+        );
+        assert_eq!(list[3], 1);
+        assert_eq!(list[4], 0);
+        let parsed = unsafe { parse_properties_list(&list[..]) }.unwrap();
+        assert_eq!(parsed, properties);
+    }
+
+    #[test]
+    fn duplicate_properties_are_rejected() {
+        let properties = vec![
+            ContextProperty::InteropUserSync(true),
+            ContextProperty::InteropUserSync(false),
+        ];
+        let err = unsafe { build_properties_list(&properties[..]) }.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ContextPropertiesError>(),
+            Some(&ContextPropertiesError::DuplicateProperty(
+                ContextProperties::InteropUserSync
+            ))
+        );
+    }
+
+    #[test]
+    fn parsing_handles_empty_and_unterminated_lists() {
+        assert_eq!(unsafe { parse_properties_list(&[]) }.unwrap(), vec![]);
+        assert_eq!(unsafe { parse_properties_list(&[0]) }.unwrap(), vec![]);
+        let err = unsafe { parse_properties_list(&[0x1085, 1]) }.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ContextPropertiesError>(),
+            Some(&ContextPropertiesError::UnterminatedList)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use libc::{c_char, c_void, size_t};
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+
+/// A closure called with the error string and the implementation specific private info
+/// of an error the OpenCL driver reports for a context.
+pub type ContextErrorFn = dyn Fn(&str, &[u8]) + Send + Sync + 'static;
+
+/// The `pfn_notify` of a context, which the driver calls (possibly from its own thread)
+/// when an error happens in the context.
+///
+/// A Custom closure is intentionally leaked by each context created with it: before
+/// OpenCL 3.0 (clSetContextDestructorCallback) there is no notification of when the
+/// driver is done with a context, and the context can outlive its last clReleaseContext
+/// while the queues, mems, programs or kernels created on it are alive.
+#[derive(Clone)]
+pub enum ContextErrorCallback {
+    /// Forwards the error string and private info to `log::error!`.
+    Log,
+    Custom(Arc<ContextErrorFn>),
+}
+
+impl ContextErrorCallback {
+    pub fn custom<F>(callback: F) -> ContextErrorCallback
+    where
+        F: Fn(&str, &[u8]) + Send + Sync + 'static,
+    {
+        ContextErrorCallback::Custom(Arc::new(callback))
+    }
+
+    /// The user_data to pass with `context_error_notify` to clCreateContext. Log needs no
+    /// user_data. A Custom closure is boxed and must be reclaimed with `drop_user_data`
+    /// if the context is not created; otherwise it is leaked (see ContextErrorCallback).
+    pub(crate) fn into_user_data(self) -> *mut c_void {
+        match self {
+            ContextErrorCallback::Log => std::ptr::null_mut(),
+            ContextErrorCallback::Custom(callback) => {
+                Box::into_raw(Box::new(callback)) as *mut c_void
+            }
+        }
+    }
+
+    pub(crate) unsafe fn drop_user_data(user_data: *mut c_void) {
+        if !user_data.is_null() {
+            std::mem::drop(Box::from_raw(user_data as *mut Arc<ContextErrorFn>));
+        }
+    }
+}
+
+impl Default for ContextErrorCallback {
+    fn default() -> ContextErrorCallback {
+        ContextErrorCallback::Log
+    }
+}
+
+impl fmt::Debug for ContextErrorCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContextErrorCallback::Log => write!(f, "ContextErrorCallback::Log"),
+            ContextErrorCallback::Custom(_) => write!(f, "ContextErrorCallback::Custom"),
+        }
+    }
+}
+
+fn log_context_error(message: &str, private_info: &[u8]) {
+    if private_info.is_empty() {
+        log::error!("OpenCL context error: {}", message);
+    } else {
+        log::error!(
+            "OpenCL context error: {} (private info: {:?})",
+            message,
+            private_info
+        );
+    }
+}
+
+/// Called by OpenCL when an error happens in a context. The user_data is null for the
+/// Log callback or a leaked boxed `Arc<ContextErrorFn>` for a Custom callback.
+/// Panics are caught because unwinding into OpenCL is undefined behavior.
+pub(crate) extern "C" fn context_error_notify(
+    errinfo: *const c_char,
+    private_info: *const c_void,
+    cb: size_t,
+    user_data: *mut c_void,
+) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let message = if errinfo.is_null() {
+            Cow::Borrowed("")
+        } else {
+            unsafe { CStr::from_ptr(errinfo) }.to_string_lossy()
+        };
+        let private_info: &[u8] = if private_info.is_null() || cb == 0 {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(private_info as *const u8, cb) }
+        };
+        if user_data.is_null() {
+            log_context_error(&message, private_info);
+        } else {
+            let callback = unsafe { &*(user_data as *const Arc<ContextErrorFn>) };
+            callback(&message, private_info);
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{context_error_notify, ContextErrorCallback};
+    use libc::c_void;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn context_error_notify_calls_custom_callbacks_and_catches_panics() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let seen2 = seen.clone();
+        let callback = ContextErrorCallback::custom(move |message, info| {
+            seen2
+                .lock()
+                .unwrap()
+                .push((message.to_string(), info.to_vec()));
+            panic!("callback panic");
+        });
+        let user_data = callback.into_user_data();
+        let info = [1u8, 2, 3];
+        context_error_notify(
+            b"out of memory\0".as_ptr() as *const _,
+            info.as_ptr() as *const c_void,
+            info.len(),
+            user_data,
+        );
+        unsafe { ContextErrorCallback::drop_user_data(user_data) };
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![("out of memory".to_string(), vec![1, 2, 3])]
+        );
+    }
+
+    #[test]
+    fn context_error_notify_logs_without_user_data() {
+        assert!(ContextErrorCallback::default().into_user_data().is_null());
+        context_error_notify(std::ptr::null(), std::ptr::null(), 0, std::ptr::null_mut());
+    }
+}
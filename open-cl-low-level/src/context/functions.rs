@@ -1,24 +1,81 @@
+use super::error_callback::{context_error_notify, ContextErrorCallback};
 use crate::cl::{
-    clCreateContext, clGetContextInfo, cl_context, cl_context_info, cl_device_id, ClObject,
-    ContextInfo, StatusCodeError,
+    clCreateContext, clCreateContextFromType, clGetContextInfo, cl_context, cl_context_info,
+    cl_context_properties, cl_device_id, cl_device_type, ClError, ClObject, ContextInfo,
+    DeviceType,
 };
 use crate::Output;
 
-#[allow(clippy::transmuting_null)]
-pub unsafe fn create_context(device_ids: &[cl_device_id]) -> Output<cl_context> {
+/// Creates a cl_context for the devices with the error callback as its pfn_notify.
+///
+/// The properties must be empty or a zero-terminated key/value list
+/// (see `context_properties::build_properties_list`).
+///
+/// # Safety
+/// Usage of an invalid cl_device_id is undefined behavior.
+pub unsafe fn create_context(
+    properties: &[cl_context_properties],
+    device_ids: &[cl_device_id],
+    error_callback: ContextErrorCallback,
+) -> Output<cl_context> {
     let mut err_code = 0;
+    let user_data = error_callback.into_user_data();
     let context_ptr = clCreateContext(
-        std::ptr::null(),
+        properties_ptr(properties),
         device_ids.len() as u32,
         device_ids.as_ptr() as *const *mut libc::c_void,
-        std::mem::transmute(std::ptr::null::<fn()>()),
-        std::ptr::null_mut(),
+        Some(context_error_notify),
+        user_data,
         &mut err_code,
     );
-    StatusCodeError::check(err_code)?;
+    if err_code != 0 {
+        ContextErrorCallback::drop_user_data(user_data);
+    }
+    ClError::check_with(err_code, "clCreateContext", || {
+        format!("{} devices", device_ids.len())
+    })?;
     cl_context::new(context_ptr)
 }
 
+/// Creates a cl_context for all the devices of the device type. Which platform is used
+/// without a CL_CONTEXT_PLATFORM property is implementation-defined.
+///
+/// # Safety
+/// Usage of an invalid cl_platform_id in the properties is undefined behavior.
+pub unsafe fn create_context_from_type(
+    properties: &[cl_context_properties],
+    device_type: cl_device_type,
+    error_callback: ContextErrorCallback,
+) -> Output<cl_context> {
+    let mut err_code = 0;
+    let user_data = error_callback.into_user_data();
+    let context_ptr = clCreateContextFromType(
+        properties_ptr(properties),
+        device_type,
+        Some(context_error_notify),
+        user_data,
+        &mut err_code,
+    );
+    if err_code != 0 {
+        ContextErrorCallback::drop_user_data(user_data);
+    }
+    ClError::check_with(err_code, "clCreateContextFromType", || {
+        format!(
+            "device type {:?}",
+            DeviceType::from_bits_truncate(device_type)
+        )
+    })?;
+    cl_context::new(context_ptr)
+}
+
+fn properties_ptr(properties: &[cl_context_properties]) -> *const cl_context_properties {
+    if properties.is_empty() {
+        std::ptr::null()
+    } else {
+        properties.as_ptr()
+    }
+}
+
 #[inline(always)]
 pub unsafe fn get_context_info_devices(context: cl_context) -> Output<Vec<cl_device_id>> {
     let flag: cl_context_info = ContextInfo::Devices.into();
@@ -1,6 +1,12 @@
 pub mod context;
 pub use context::*;
 
+pub mod error_callback;
+pub use error_callback::{ContextErrorCallback, ContextErrorFn};
+
+pub mod context_properties;
+pub use context_properties::{ContextPropertiesError, ContextProperty};
+
 pub mod context_builder;
 pub use context_builder::*;
 
@@ -4,11 +4,13 @@ use crate::cl::{
     cl_device_mem_cache_type, cl_device_type,
 };
 use crate::cl::{cl_device_id, ClObject, ObjectWrapper};
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::cl::{cl_device_svm_capabilities, DeviceSvmCapabilities};
 use crate::cl::{
     DeviceAffinityDomain, DeviceExecCapabilities, DeviceInfo, DeviceLocalMemType,
     DeviceMemCacheType, DeviceType,
 };
-use crate::{ErrorT, Output};
+use crate::{ErrorT, NumberType, Output};
 use std::fmt;
 use std::fmt::Debug;
 
@@ -23,6 +25,9 @@ pub enum DeviceError {
 
     #[error("Invalid device info value")]
     InvalidInfoValue,
+
+    #[error("The device does not support cl_khr_fp16 which is required for {0}")]
+    HalfNotSupported(&'static str),
 }
 
 pub type Device = ObjectWrapper<cl_device_id>;
@@ -141,6 +146,12 @@ where
     info_fn!(preferred_vector_width_half, PreferredVectorWidthHalf, u32);
     info_fn!(vendor_id, VendorId, u32);
 
+    // OpenCL v2.0 pipes
+    #[cfg(feature = "opencl_version_2_0_0")]
+    info_fn!(max_pipe_args, MaxPipeArgs, u32);
+    #[cfg(feature = "opencl_version_2_0_0")]
+    info_fn!(pipe_max_packet_size, PipeMaxPacketSize, u32);
+
     // cl_bool
     info_fn!(available, Available, bool);
     info_fn!(compiler_available, CompilerAvailable, bool);
@@ -216,15 +227,91 @@ where
 
     // DeviceType
     flag_info_fn_u64!(device_type, Type, cl_device_type, DeviceType);
+
+    /// The (major, minor) OpenCL version that the device supports.
+    fn opencl_version_number(&self) -> Output<(u32, u32)> {
+        let version = self.version()?;
+        match parse_opencl_version(&version[..]) {
+            Some(v) => Ok(v),
+            None => Err(DeviceError::InvalidInfoValue)?,
+        }
+    }
+
+    /// The extensions the device supports (e.g. `cl_khr_fp16`).
+    fn extensions(&self) -> Output<Vec<String>> {
+        unsafe {
+            functions::get_device_info_string(self.device_ptr(), DeviceInfo::Extensions.into())
+        }
+        .map(|exts| exts.split_whitespace().map(|ext| ext.to_string()).collect())
+    }
+
+    fn has_extension(&self, extension: &str) -> Output<bool> {
+        Ok(self.extensions()?.iter().any(|ext| ext == extension))
+    }
+
+    /// Whether the device supports half precision numbers (the cl_khr_fp16 extension).
+    fn supports_half(&self) -> Output<bool> {
+        self.has_extension("cl_khr_fp16")
+    }
+
+    /// Checks that the device can use the number type in Buffers and kernel args.
+    /// Half number types require cl_khr_fp16.
+    fn check_number_type(&self, number_type: &NumberType) -> Output<()> {
+        if number_type.is_half() && !self.supports_half()? {
+            return Err(DeviceError::HalfNotSupported(
+                number_type.number_type_name(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    // char[] of space separated "<IL prefix>_<major>.<minor>", e.g. "SPIR-V_1.0" (OpenCL v2.1)
+    #[cfg(feature = "opencl_version_2_1_0")]
+    info_fn!(il_version, IlVersion, String);
+
+    // cl_device_svm_capabilities (OpenCL v2.0)
+    #[cfg(feature = "opencl_version_2_0_0")]
+    flag_info_fn_u64!(
+        svm_capabilities,
+        SvmCapabilities,
+        cl_device_svm_capabilities,
+        DeviceSvmCapabilities
+    );
 }
 
 impl<T> HasDeviceInfo for T where T: DevicePtr + fmt::Debug {}
 
+/// Parses an OpenCL version string of the form "OpenCL <major>.<minor> <vendor-specific info>"
+/// into a (major, minor) tuple.
+pub fn parse_opencl_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split_whitespace();
+    if parts.next()? != "OpenCL" {
+        return None;
+    }
+    let mut numbers = parts.next()?.split('.');
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Looks up the (major, minor) OpenCL version of a raw cl_device_id.
+///
+/// # Safety
+/// Calling this function with an invalid cl_device_id is undefined behavior.
+pub unsafe fn cl_device_opencl_version(device: cl_device_id) -> Output<(u32, u32)> {
+    let version = functions::get_device_info_string(device, DeviceInfo::Version.into())?;
+    match parse_opencl_version(&version[..]) {
+        Some(v) => Ok(v),
+        None => Err(DeviceError::InvalidInfoValue)?,
+    }
+}
+
 unsafe impl Send for Device {}
 unsafe impl Sync for Device {}
 
 #[cfg(test)]
 mod tests {
+    use super::parse_opencl_version;
     use crate::cl::DeviceType;
     use crate::{ll_testing, Platform};
 
@@ -252,6 +339,14 @@ mod tests {
         // .expect("Failed to list ALL devices");
     }
 
+    #[test]
+    fn parse_opencl_version_works() {
+        assert_eq!(parse_opencl_version("OpenCL 1.2 pocl"), Some((1, 2)));
+        assert_eq!(parse_opencl_version("OpenCL 2.0 "), Some((2, 0)));
+        assert_eq!(parse_opencl_version("OpenCL C 1.2"), None);
+        assert_eq!(parse_opencl_version(""), None);
+    }
+
     #[test]
     fn device_fmt_debug_works() {
         ll_testing::with_each_device(|device| {
@@ -332,6 +427,8 @@ mod device_ptr_tests {
     test_method!(vendor);
     test_method!(version);
     test_method!(driver_version);
+    test_method!(extensions);
+    test_method!(supports_half);
 
     // u64
     test_method!(global_mem_cache_size);
@@ -369,4 +466,21 @@ mod device_ptr_tests {
 
     // DeviceType
     test_method!(device_type);
+
+    // parsed from version
+    test_method!(opencl_version_number);
+
+    // cl_device_svm_capabilities
+    #[cfg(feature = "opencl_version_2_0_0")]
+    test_method!(svm_capabilities);
+
+    // pipes
+    #[cfg(feature = "opencl_version_2_0_0")]
+    test_method!(max_pipe_args);
+    #[cfg(feature = "opencl_version_2_0_0")]
+    test_method!(pipe_max_packet_size);
+
+    // SPIR-V
+    #[cfg(feature = "opencl_version_2_1_0")]
+    test_method!(il_version);
 }
@@ -1,12 +1,15 @@
 use super::KernelArgPtr;
 use crate::cl::{
-    clCreateKernel, clGetKernelInfo, clSetKernelArg, cl_context, cl_kernel, cl_kernel_info,
-    cl_program, cl_uint, ClObject, StatusCodeError,
+    clCreateKernel, clGetKernelArgInfo, clGetKernelInfo, clSetKernelArg, cl_context, cl_kernel,
+    cl_kernel_arg_info, cl_kernel_info, cl_program, cl_uint, strings, ClError, ClObject,
 };
 use crate::Output;
 use libc::c_void;
 use std::ffi::CString;
 
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::cl::clSetKernelArgSVMPointer;
+
 pub unsafe fn set_kernel_arg<T: KernelArgPtr>(
     kernel: cl_kernel,
     arg_index: usize,
@@ -32,10 +35,33 @@ unsafe fn cl_set_kernel_arg_raw(
         arg_size,
         arg_ptr,
     );
-    StatusCodeError::check(status_code)?;
+    ClError::check_with(status_code, "clSetKernelArg", || {
+        format!("arg {}", arg_index)
+    })?;
     Ok(())
 }
 
+/// Sets an SVM pointer as the kernel arg at the given index.
+///
+/// # Safety
+/// Calling this function with an invalid kernel or a pointer that was not
+/// allocated by clSVMAlloc (in the kernel's context) is undefined behavior.
+#[cfg(feature = "opencl_version_2_0_0")]
+pub unsafe fn set_kernel_arg_svm_pointer(
+    kernel: cl_kernel,
+    arg_index: usize,
+    svm_ptr: *const c_void,
+) -> Output<()> {
+    let status_code = clSetKernelArgSVMPointer(
+        kernel.as_ptr() as *mut c_void,
+        arg_index as cl_uint,
+        svm_ptr,
+    );
+    ClError::check_with(status_code, "clSetKernelArgSVMPointer", || {
+        format!("arg {}", arg_index)
+    })
+}
+
 pub unsafe fn create_kernel(program: cl_program, c_name: CString) -> Output<cl_kernel> {
     let mut status_code = 0;
     let raw_kernel: *mut c_void = clCreateKernel(
@@ -43,7 +69,9 @@ pub unsafe fn create_kernel(program: cl_program, c_name: CString) -> Output<cl_k
         c_name.as_ptr(),
         &mut status_code,
     );
-    StatusCodeError::check(status_code)?;
+    ClError::check_with(status_code, "clCreateKernel", || {
+        format!("kernel {}", c_name.to_string_lossy())
+    })?;
     cl_kernel::new(raw_kernel)
 }
 
@@ -63,6 +91,46 @@ pub unsafe fn get_info_string(kernel: cl_kernel, flag: cl_kernel_info) -> Output
     )
 }
 
+/// Low level helper function for the FFI call to clGetKernelArgInfo with String expected.
+/// The info is only available if the program was built from source (some platforms also
+/// require the `-cl-kernel-arg-info` build option); otherwise this returns
+/// CL_KERNEL_ARG_INFO_NOT_AVAILABLE.
+///
+/// # Safety
+/// Calling this function with a cl_kernel that is not in a valid state is
+/// undefined behavior.
+pub unsafe fn get_arg_info_string(
+    kernel: cl_kernel,
+    arg_index: usize,
+    flag: cl_kernel_arg_info,
+) -> Output<String> {
+    let mut n_bytes = 0usize;
+    let status_code = clGetKernelArgInfo(
+        kernel.as_ptr() as *mut c_void,
+        arg_index as cl_uint,
+        flag,
+        0,
+        std::ptr::null_mut(),
+        &mut n_bytes,
+    );
+    ClError::check_with(status_code, "clGetKernelArgInfo", || {
+        format!("arg {}", arg_index)
+    })?;
+    let mut data: Vec<u8> = vec![0u8; n_bytes];
+    let status_code = clGetKernelArgInfo(
+        kernel.as_ptr() as *mut c_void,
+        arg_index as cl_uint,
+        flag,
+        n_bytes,
+        data.as_mut_ptr() as *mut c_void,
+        std::ptr::null_mut(),
+    );
+    ClError::check_with(status_code, "clGetKernelArgInfo", || {
+        format!("arg {}", arg_index)
+    })?;
+    Ok(strings::to_utf8_string(data))
+}
+
 /// Low level helper function for the FFI call to clGetKernelInfo with u32 expected
 ///
 /// # Safety
@@ -1,9 +1,12 @@
 use super::{functions, KernelArgPtr};
-use crate::cl::{cl_kernel, strings, KernelInfo, ObjectWrapper};
+use crate::cl::{cl_kernel, strings, ClError, KernelArgInfo, KernelInfo, ObjectWrapper};
 use crate::{Context, Program, ProgramPtr};
 use crate::{ErrorT, Output};
 use std::fmt::Debug;
 
+#[cfg(feature = "opencl_version_2_0_0")]
+use super::SvmKernelArg;
+
 /// An error related to a `Kernel`.
 #[derive(Debug, ErrorT, PartialEq, Eq, Clone)]
 pub enum KernelError {
@@ -21,6 +24,9 @@ pub enum KernelError {
 
     #[error("The KernelOpArg was not a num type.")]
     KernelOpArgWasNotNum,
+
+    #[error("Kernel arg {0} is declared as {1}, but was given a buffer of {2}")]
+    ArgTypeMismatch(usize, String, String),
 }
 
 pub unsafe trait KernelPtr: Sized {
@@ -54,6 +60,12 @@ pub unsafe trait KernelPtr: Sized {
         functions::get_info_string(self.kernel_ptr(), KernelInfo::Attributes.into())
     }
 
+    /// The declared type of the arg at the index (e.g. `float4*` for a
+    /// `__global float4 *` arg).
+    unsafe fn arg_type_name(&self, arg_index: usize) -> Output<String> {
+        functions::get_arg_info_string(self.kernel_ptr(), arg_index, KernelArgInfo::TypeName.into())
+    }
+
     // // OpenCL v2.0
     // fn max_num_sub_groups(&self) -> Output<String> {
     //     self.info(KernelInfo::MaxNumSubGroups).map(|ret| ret.to_string())
@@ -83,6 +95,55 @@ impl Kernel {
     pub unsafe fn set_arg<T: KernelArgPtr>(&mut self, arg_index: usize, arg: &mut T) -> Output<()> {
         functions::set_kernel_arg(self.kernel_ptr(), arg_index, arg)
     }
+
+    /// Checks that the pointer arg at the given index points to items of the OpenCL C type
+    /// (e.g. `int` for a `__global int *` arg). The check is skipped when the kernel's arg
+    /// info is not available (the program was not built with `-cl-kernel-arg-info`).
+    ///
+    /// The declared type is compared by name, so a typedef of the type is rejected.
+    ///
+    /// # Safety
+    /// Calling this function on an invalid kernel is undefined behavior.
+    pub unsafe fn check_arg_type(&self, arg_index: usize, cl_type_name: &str) -> Output<()> {
+        let declared = match self.arg_type_name(arg_index) {
+            Ok(declared) => declared,
+            Err(e) => match e.downcast_ref::<ClError>() {
+                Some(ClError::KernelArgInfoNotAvailable(_)) => return Ok(()),
+                _ => return Err(e),
+            },
+        };
+        if pointee_type_name(&declared) != cl_type_name {
+            return Err(KernelError::ArgTypeMismatch(
+                arg_index,
+                declared.clone(),
+                cl_type_name.to_string(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Sets an SVM pointer as the kernel arg at the given index via clSetKernelArgSVMPointer.
+    ///
+    /// # Safety
+    /// Calling this function on invalid kernel or with an SVM pointer from another
+    /// context is undefined behavior.
+    #[cfg(feature = "opencl_version_2_0_0")]
+    pub unsafe fn set_svm_arg(&mut self, arg_index: usize, arg: &SvmKernelArg) -> Output<()> {
+        functions::set_kernel_arg_svm_pointer(self.kernel_ptr(), arg_index, arg.svm_ptr())
+    }
+}
+
+/// The OpenCL C name of the items a pointer arg's declared type points to. Drivers spell
+/// unsigned types as `uint`, `unsigned int` or `unsignedint` and can keep the `struct`
+/// keyword of a struct type.
+fn pointee_type_name(declared: &str) -> String {
+    let pointee = declared.trim_end_matches('*').trim();
+    let pointee = pointee.strip_prefix("struct ").unwrap_or(pointee).trim();
+    match pointee.strip_prefix("unsigned") {
+        Some("") => "uint".to_string(),
+        Some(unsigned) => format!("u{}", unsigned.trim()),
+        None => pointee.to_string(),
+    }
 }
 
 unsafe impl KernelPtr for Kernel {
@@ -97,7 +158,10 @@ mod tests {
 
     use crate::cl::{clSetKernelArg, cl_mem, ClObject};
     use crate::numbers::{Number, Uchar, Uchar2};
-    use crate::{ll_testing, Context, Kernel, KernelPtr, MemPtr, Program, Session, SessionBuilder};
+    use crate::{
+        ll_testing, Context, Kernel, KernelError, KernelPtr, MemPtr, Program, Session,
+        SessionBuilder,
+    };
 
     const SRC: &'static str = "
     __kernel void test123(__global int *i) {
@@ -126,6 +190,38 @@ mod tests {
         assert_eq!(num_args, 1);
     }
 
+    #[test]
+    fn pointee_type_name_normalizes_driver_spellings() {
+        use super::pointee_type_name;
+        assert_eq!(pointee_type_name("float4*"), "float4");
+        assert_eq!(pointee_type_name("unsigned int*"), "uint");
+        assert_eq!(pointee_type_name("unsignedchar*"), "uchar");
+        assert_eq!(pointee_type_name("unsigned*"), "uint");
+        assert_eq!(pointee_type_name("struct Particle*"), "Particle");
+    }
+
+    #[test]
+    fn kernel_check_arg_type_works() {
+        let (_context, _devices, _program, kernel) = ll_testing::get_kernel(SRC, KERNEL_NAME);
+        match unsafe { kernel.arg_type_name(0) } {
+            Ok(type_name) => {
+                assert_eq!(type_name, "int*");
+                unsafe { kernel.check_arg_type(0, "int") }.unwrap();
+                let err = unsafe { kernel.check_arg_type(0, "float") }.unwrap_err();
+                assert_eq!(
+                    err.downcast_ref::<KernelError>(),
+                    Some(&KernelError::ArgTypeMismatch(
+                        0,
+                        "int*".to_string(),
+                        "float".to_string()
+                    ))
+                );
+            }
+            // Without arg info the check is skipped.
+            Err(_) => unsafe { kernel.check_arg_type(0, "float") }.unwrap(),
+        }
+    }
+
     #[test]
     fn kernel_reference_count_works() {
         let (_context, _devices, _program, kernel) = ll_testing::get_kernel(SRC, KERNEL_NAME);
@@ -4,6 +4,9 @@ use crate::{Mem, MemPtr, NumberType, NumberTyped, NumberTypedT};
 use libc::c_void;
 use std::marker::PhantomData;
 
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::{Pipe, SvmBuffer};
+
 pub unsafe trait KernelArgPtr: Sized {
     /// size_of<T> or size_of<T> * len
     fn kernel_arg_size(&self) -> usize;
@@ -51,6 +54,25 @@ unsafe impl KernelArgPtr for Mem {
     }
 }
 
+#[cfg(feature = "opencl_version_2_0_0")]
+unsafe impl<T: Number + NumberTypedT> KernelArgPtr for Pipe<T> {
+    fn kernel_arg_size(&self) -> usize {
+        std::mem::size_of::<cl_mem>()
+    }
+
+    fn kernel_arg_number_type(&self) -> NumberType {
+        T::number_type()
+    }
+
+    unsafe fn kernel_arg_ptr(&self) -> *const c_void {
+        self.mem_ptr_ref() as *const _ as *const c_void
+    }
+
+    unsafe fn kernel_arg_mut_ptr(&mut self) -> *mut c_void {
+        self.mem_ptr_ref() as *const _ as *mut c_void
+    }
+}
+
 #[derive(Debug)]
 pub struct KernelArg<'a> {
     _t: NumberType,
@@ -87,6 +109,11 @@ impl<'a> KernelArg<'a> {
         }
     }
 
+    #[cfg(feature = "opencl_version_2_0_0")]
+    pub fn from_pipe<T: Number + NumberTypedT>(pipe: &'a Pipe<T>) -> KernelArg<'a> {
+        KernelArg::new(pipe)
+    }
+
     pub unsafe fn from_raw_parts(t: NumberType, ptr: *const c_void, size: usize) -> KernelArg<'a> {
         KernelArg {
             _t: t,
@@ -114,3 +141,43 @@ unsafe impl<'a> KernelArgPtr for KernelArg<'a> {
         self._ptr as *mut c_void
     }
 }
+
+/// A kernel arg for an SvmBuffer. SVM pointers are passed to kernels with
+/// clSetKernelArgSVMPointer instead of clSetKernelArg.
+#[cfg(feature = "opencl_version_2_0_0")]
+#[derive(Debug)]
+pub struct SvmKernelArg<'a> {
+    _t: NumberType,
+    _ptr: *const c_void,
+    _phantom: PhantomData<&'a c_void>,
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+impl<'a> SvmKernelArg<'a> {
+    pub fn new<T: Number + NumberTypedT>(buffer: &'a SvmBuffer<T>) -> SvmKernelArg<'a> {
+        SvmKernelArg {
+            _t: T::number_type(),
+            _ptr: unsafe { buffer.svm_ptr() },
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn number_type(&self) -> NumberType {
+        self._t
+    }
+
+    /// Returns the raw SVM pointer of the kernel arg.
+    ///
+    /// # Safety
+    /// The pointer is only valid for as long as the SvmBuffer lives.
+    pub unsafe fn svm_ptr(&self) -> *const c_void {
+        self._ptr
+    }
+}
+
+#[cfg(feature = "opencl_version_2_0_0")]
+impl<'a, T: Number + NumberTypedT> From<&'a SvmBuffer<T>> for SvmKernelArg<'a> {
+    fn from(buffer: &'a SvmBuffer<T>) -> SvmKernelArg<'a> {
+        SvmKernelArg::new(buffer)
+    }
+}
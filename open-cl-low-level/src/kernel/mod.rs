@@ -1,4 +1,4 @@
-mod functions;
+pub(crate) mod functions;
 
 mod work;
 pub use work::*;
@@ -34,6 +34,9 @@
 #[macro_use]
 extern crate derive_more;
 
+// Lets derive(ClStruct) refer to `::open_cl_low_level` inside this crate too.
+extern crate self as open_cl_low_level;
+
 pub mod numbers;
 pub use numbers::*;
 
@@ -1,11 +1,16 @@
 use super::BufferBuilder;
 use crate::cl::{
-    clCreateBuffer, clGetMemObjectInfo, cl_context, cl_int, cl_mem, cl_mem_flags, cl_mem_info,
-    ClObject, MemInfo, StatusCodeError,
+    clCreateBuffer, clCreateSubBuffer, clGetMemObjectInfo, cl_buffer_region, cl_context, cl_int,
+    cl_mem, cl_mem_flags, cl_mem_info, BufferCreateType, ClError, ClObject, MemInfo,
 };
 use crate::{Number, Output};
 use libc::c_void;
 
+#[cfg(feature = "opencl_version_2_0_0")]
+use crate::cl::{
+    clCreatePipe, clGetPipeInfo, clSVMAlloc, clSVMFree, cl_pipe_info, cl_svm_mem_flags,
+};
+
 /// Low-level helper for creating a cl_mem buffer from a context, mem flags, and a buffer creator.
 ///
 /// # Safety
@@ -42,10 +47,111 @@ unsafe fn cl_create_buffer(
         ptr,
         &mut err_code,
     );
-    StatusCodeError::check(err_code)?;
+    ClError::check_with(err_code, "clCreateBuffer", || {
+        format!("{} bytes", size_in_bytes)
+    })?;
     cl_mem::new(device_mem_ptr)
 }
 
+/// Low-level helper for creating a sub-buffer of a cl_mem buffer. The region is
+/// described in bytes.
+///
+/// # Safety
+/// Use of an invalid cl_mem in this function call is undefined behavior.
+pub unsafe fn create_sub_buffer(
+    mut mem: cl_mem,
+    mem_flags: cl_mem_flags,
+    origin_in_bytes: usize,
+    size_in_bytes: usize,
+) -> Output<cl_mem> {
+    let mut err_code: cl_int = 0;
+    let region = cl_buffer_region {
+        origin: origin_in_bytes,
+        size: size_in_bytes,
+    };
+    let sub_mem_ptr: *mut c_void = clCreateSubBuffer(
+        mem.as_mut_ptr(),
+        mem_flags,
+        BufferCreateType::CreateTypeRegion.into(),
+        &region as *const cl_buffer_region as *const c_void,
+        &mut err_code,
+    );
+    ClError::check_with(err_code, "clCreateSubBuffer", || {
+        format!("{} bytes at offset {}", size_in_bytes, origin_in_bytes)
+    })?;
+    cl_mem::new(sub_mem_ptr)
+}
+
+/// Low-level helper for allocating a Shared Virtual Memory (SVM) buffer of `len` T.
+///
+/// Returns a null pointer if the allocation failed. OpenCL does not return an
+/// error code for clSVMAlloc.
+///
+/// # Safety
+/// Use of a invalid cl_context in this function call is undefined behavior.
+#[cfg(feature = "opencl_version_2_0_0")]
+pub unsafe fn svm_alloc<T: Number>(
+    mut context: cl_context,
+    flags: cl_svm_mem_flags,
+    len: usize,
+) -> *mut c_void {
+    clSVMAlloc(
+        context.as_mut_ptr(),
+        flags,
+        len * std::mem::size_of::<T>(),
+        0,
+    )
+}
+
+/// Low-level helper for freeing a Shared Virtual Memory (SVM) buffer.
+///
+/// # Safety
+/// Use of a invalid cl_context or an SVM pointer that was not allocated in the given
+/// cl_context is undefined behavior. Freeing an SVM pointer that is in use by
+/// an enqueued command is undefined behavior.
+#[cfg(feature = "opencl_version_2_0_0")]
+pub unsafe fn svm_free(mut context: cl_context, svm_ptr: *mut c_void) {
+    clSVMFree(context.as_mut_ptr(), svm_ptr)
+}
+
+/// Low-level helper for creating a pipe cl_mem with `max_packets` packets of
+/// `packet_size` bytes.
+///
+/// # Safety
+/// Use of a invalid cl_context in this function call is undefined behavior.
+#[cfg(feature = "opencl_version_2_0_0")]
+pub unsafe fn create_pipe(
+    mut context: cl_context,
+    mem_flags: cl_mem_flags,
+    packet_size: u32,
+    max_packets: u32,
+) -> Output<cl_mem> {
+    let mut err_code: cl_int = 0;
+    let pipe: *mut c_void = clCreatePipe(
+        context.as_mut_ptr(),
+        mem_flags,
+        packet_size,
+        max_packets,
+        std::ptr::null(),
+        &mut err_code,
+    );
+    ClError::check_with(err_code, "clCreatePipe", || {
+        format!("{} packets of {} bytes", max_packets, packet_size)
+    })?;
+    cl_mem::new(pipe)
+}
+
+/// Low level helper function for the FFI call to clGetPipeInfo with u32 expected
+///
+/// # Safety
+/// Calling this function with a cl_mem that is not a valid pipe is
+/// undefined behavior.
+#[cfg(feature = "opencl_version_2_0_0")]
+#[inline(always)]
+pub unsafe fn get_pipe_info_u32(pipe: cl_mem, flag: cl_pipe_info) -> Output<u32> {
+    cl_get_info!(One, u32, clGetPipeInfo, pipe, flag)
+}
+
 // pub fn cl_get_mem_object_info<T>(device_mem: cl_mem, flag: cl_mem_info) -> Output<ClPointer<T>>
 // where
 //     T: Copy,
@@ -4,10 +4,20 @@ use crate::cl::cl_mem;
 use crate::cl::{MemFlags, MemInfo, ObjectWrapper};
 use crate::numbers::Number;
 use crate::numbers::{NumberType, NumberTyped, NumberTypedT};
-use crate::{Context, ContextPtr, Output};
+use crate::{Context, ContextPtr, ErrorT, Output};
 
 use super::{functions, BufferBuilder, HostAccess, KernelAccess, MemAllocation, MemConfig};
 
+/// An error related to a Mem.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum MemError {
+    #[error("Sub-buffers cannot have a len of 0")]
+    SubBufferZeroLength,
+
+    #[error("Sub-buffer region (offset: {0}, len: {1}) is out of bounds for a Mem of len {2}")]
+    SubBufferOutOfBounds(usize, usize, usize),
+}
+
 #[derive(Eq, PartialEq)]
 pub struct Mem {
     inner: ObjectWrapper<cl_mem>,
@@ -77,6 +87,29 @@ impl Mem {
     pub fn mem_config(&self) -> &MemConfig {
         &self.mem_config
     }
+
+    /// Creates a sub-buffer of `len` elements that starts at element `offset` of the Mem.
+    /// The sub-buffer shares the device memory of the Mem; the Mem is retained by
+    /// OpenCL until the sub-buffer is released.
+    ///
+    /// The offset in bytes must be a multiple of the MEM_BASE_ADDR_ALIGN of the devices
+    /// of the Mem's context, otherwise OpenCL returns CL_MISALIGNED_SUB_BUFFER_OFFSET.
+    ///
+    /// # Safety
+    /// Calling this function with an invalid Mem is invalid behavior.
+    pub unsafe fn create_sub_buffer(&self, offset: usize, len: usize) -> Output<Mem> {
+        if len == 0 {
+            return Err(MemError::SubBufferZeroLength)?;
+        }
+        let mem_len = self.len()?;
+        if offset + len > mem_len {
+            return Err(MemError::SubBufferOutOfBounds(offset, len, mem_len))?;
+        }
+        let size_of_t = self.t.size_of();
+        let sub_mem =
+            functions::create_sub_buffer(self.mem_ptr(), 0, offset * size_of_t, len * size_of_t)?;
+        Ok(Mem::new(self.t, sub_mem, self.mem_config))
+    }
 }
 
 /// The MemPtr trait gives access to the cl_mem of a wrapping object and provides
@@ -242,6 +275,24 @@ mod tests {
         };
     }
 
+    #[test]
+    fn mem_can_create_a_sub_buffer() {
+        let (_devices, _context, ll_mem) = ll_testing::get_mem::<u32>(64);
+        let sub_mem = unsafe { ll_mem.create_sub_buffer(0, 16).unwrap() };
+        assert_eq!(unsafe { sub_mem.len().unwrap() }, 16);
+        assert_eq!(unsafe { sub_mem.offset().unwrap() }, 0);
+    }
+
+    #[test]
+    fn mem_sub_buffer_cannot_be_out_of_bounds() {
+        let (_devices, _context, ll_mem) = ll_testing::get_mem::<u32>(10);
+        let err = unsafe { ll_mem.create_sub_buffer(8, 4).unwrap_err() };
+        assert_eq!(
+            err.downcast_ref::<MemError>(),
+            Some(&MemError::SubBufferOutOfBounds(8, 4, 10))
+        );
+    }
+
     mod mem_ptr_trait {
         use crate::cl::MemFlags;
         use crate::*;
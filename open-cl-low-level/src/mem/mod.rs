@@ -8,3 +8,13 @@ pub use mem_config::*;
 
 mod mem;
 pub use mem::*;
+
+#[cfg(feature = "opencl_version_2_0_0")]
+mod svm_buffer;
+#[cfg(feature = "opencl_version_2_0_0")]
+pub use svm_buffer::*;
+
+#[cfg(feature = "opencl_version_2_0_0")]
+mod pipe;
+#[cfg(feature = "opencl_version_2_0_0")]
+pub use pipe::*;
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::cl::{cl_mem, MemFlags, ObjectWrapper, PipeInfo};
+use crate::numbers::{Number, NumberType, NumberTyped, NumberTypedT};
+use crate::{Context, ContextPtr, ErrorT, HasDeviceInfo, MemPtr, Output};
+
+use super::functions;
+
+/// An error related to a Pipe.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum PipeError {
+    #[error("Pipes cannot have 0 max packets")]
+    ZeroMaxPackets,
+
+    #[error("Pipe packet size {0} exceeds the max packet size {1} of device {2}")]
+    PacketSizeTooLarge(u32, u32, String),
+}
+
+/// A pipe memory object that streams packets of T between kernels.
+///
+/// The host cannot read or write a Pipe. A Pipe is only useful as a kernel arg
+/// for `read_pipe` and `write_pipe` kernel functions.
+pub struct Pipe<T: Number> {
+    inner: ObjectWrapper<cl_mem>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Number + NumberTypedT> Pipe<T> {
+    /// Instantiates a new Pipe of type T.
+    ///
+    /// # Safety
+    /// This function does not retain its cl_mem, but will release its cl_mem
+    /// when it is dropped. The cl_mem must be a pipe with a packet size of size_of::<T>().
+    pub unsafe fn new(object: cl_mem) -> Pipe<T> {
+        Pipe {
+            inner: ObjectWrapper::new(object),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a pipe of `max_packets` packets of T in the given context.
+    ///
+    /// The packet size of the pipe is size_of::<T>() and is checked against the
+    /// PIPE_MAX_PACKET_SIZE of every device of the context.
+    ///
+    /// # Safety
+    /// Calling this function with an invalid Context is undefined behavior.
+    pub unsafe fn create(context: &Context, max_packets: u32) -> Output<Pipe<T>> {
+        if max_packets == 0 {
+            return Err(PipeError::ZeroMaxPackets)?;
+        }
+        let packet_size = std::mem::size_of::<T>() as u32;
+        for device in context.devices()?.iter() {
+            let max_packet_size = device.pipe_max_packet_size()?;
+            if packet_size > max_packet_size {
+                return Err(PipeError::PacketSizeTooLarge(
+                    packet_size,
+                    max_packet_size,
+                    device.address(),
+                ))?;
+            }
+        }
+        let flags = MemFlags::KERNEL_READ_WRITE | MemFlags::HOST_NO_ACCESS;
+        let pipe = functions::create_pipe(
+            context.context_ptr(),
+            flags.into(),
+            packet_size,
+            max_packets,
+        )?;
+        Ok(Pipe::new(pipe))
+    }
+}
+
+impl<T: Number> Pipe<T> {
+    /// Returns the size in bytes of a packet of the Pipe.
+    ///
+    /// # Safety
+    /// Calling this function with an invalid Pipe is invalid behavior.
+    pub unsafe fn packet_size(&self) -> Output<u32> {
+        functions::get_pipe_info_u32(self.inner.cl_object(), PipeInfo::PacketSize.into())
+    }
+
+    /// Returns the max number of packets the Pipe can hold.
+    ///
+    /// # Safety
+    /// Calling this function with an invalid Pipe is invalid behavior.
+    pub unsafe fn max_packets(&self) -> Output<u32> {
+        functions::get_pipe_info_u32(self.inner.cl_object(), PipeInfo::MaxPackets.into())
+    }
+}
+
+impl<T: Number + NumberTypedT> NumberTyped for Pipe<T> {
+    fn number_type(&self) -> NumberType {
+        T::number_type()
+    }
+}
+
+unsafe impl<T: Number + NumberTypedT> MemPtr for Pipe<T> {
+    unsafe fn mem_ptr(&self) -> cl_mem {
+        self.inner.cl_object()
+    }
+
+    unsafe fn mem_ptr_ref(&self) -> &cl_mem {
+        self.inner.cl_object_ref()
+    }
+}
+
+unsafe impl<T: Number> Send for Pipe<T> {}
+
+impl<T: Number> fmt::Debug for Pipe<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pipe{{{:?}}}", unsafe { self.inner.cl_object() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn pipe_can_be_created_with_max_packets() {
+        let (context, _devices) = ll_testing::get_context();
+        let pipe = unsafe { Pipe::<u32>::create(&context, 16) }.unwrap();
+        let packet_size = unsafe { pipe.packet_size() }.unwrap();
+        assert_eq!(packet_size, 4);
+        let max_packets = unsafe { pipe.max_packets() }.unwrap();
+        assert_eq!(max_packets, 16);
+    }
+
+    #[test]
+    fn pipe_cannot_be_created_with_zero_max_packets() {
+        let (context, _devices) = ll_testing::get_context();
+        let err = unsafe { Pipe::<u32>::create(&context, 0) }.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PipeError>(),
+            Some(&PipeError::ZeroMaxPackets)
+        );
+    }
+}
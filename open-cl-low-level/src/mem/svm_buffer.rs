@@ -0,0 +1,235 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use libc::c_void;
+
+use crate::cl::{DeviceSvmCapabilities, SvmMemFlags};
+use crate::numbers::{Number, NumberType, NumberTyped, NumberTypedT};
+use crate::{Context, ContextPtr, ErrorT, HasDeviceInfo, Output};
+
+use super::functions;
+
+/// An error related to Shared Virtual Memory.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum SvmError {
+    #[error("SVM buffers cannot have a len of 0")]
+    ZeroLength,
+
+    #[error("clSVMAlloc failed to allocate {0} bytes")]
+    AllocationFailed(usize),
+
+    #[error("Device {0} does not support fine-grained SVM buffers")]
+    FineGrainedBufferNotSupported(String),
+
+    #[error("Device {0} does not support SVM atomics")]
+    AtomicsNotSupported(String),
+
+    #[error("Direct host access requires a fine-grained SVM buffer")]
+    NotFineGrained,
+}
+
+/// A Shared Virtual Memory buffer of `len` T allocated with clSVMAlloc.
+///
+/// Coarse-grained buffers must be mapped via the CommandQueue before the host
+/// touches them. Fine-grained buffers can be accessed by the host directly.
+pub struct SvmBuffer<T: Number> {
+    context: Context,
+    ptr: *mut c_void,
+    len: usize,
+    flags: SvmMemFlags,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Number + NumberTypedT> SvmBuffer<T> {
+    /// Allocates an SVM buffer of `len` T in the given context.
+    ///
+    /// If `flags` contains FINE_GRAIN_BUFFER (or ATOMICS) every device of the context
+    /// is checked for the matching DEVICE_SVM_CAPABILITIES before allocating.
+    ///
+    /// # Safety
+    /// Calling this function with an invalid Context is undefined behavior.
+    pub unsafe fn create(
+        context: &Context,
+        len: usize,
+        flags: SvmMemFlags,
+    ) -> Output<SvmBuffer<T>> {
+        if len == 0 {
+            return Err(SvmError::ZeroLength)?;
+        }
+        if flags.intersects(SvmMemFlags::FINE_GRAIN_BUFFER | SvmMemFlags::ATOMICS) {
+            check_device_capabilities(context, flags)?;
+        }
+        let ptr = functions::svm_alloc::<T>(context.context_ptr(), flags.bits(), len);
+        if ptr.is_null() {
+            return Err(SvmError::AllocationFailed(len * std::mem::size_of::<T>()))?;
+        }
+        Ok(SvmBuffer {
+            context: context.clone(),
+            ptr,
+            len,
+            flags,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Allocates a fine-grained SVM buffer that the host can read and write directly.
+    ///
+    /// # Safety
+    /// Calling this function with an invalid Context is undefined behavior.
+    pub unsafe fn create_fine_grained(context: &Context, len: usize) -> Output<SvmBuffer<T>> {
+        SvmBuffer::create(
+            context,
+            len,
+            SvmMemFlags::READ_WRITE | SvmMemFlags::FINE_GRAIN_BUFFER,
+        )
+    }
+}
+
+unsafe fn check_device_capabilities(context: &Context, flags: SvmMemFlags) -> Output<()> {
+    for device in context.devices()?.iter() {
+        let caps = device.svm_capabilities()?;
+        if flags.is_fine_grained() && !caps.contains(DeviceSvmCapabilities::FINE_GRAIN_BUFFER) {
+            return Err(SvmError::FineGrainedBufferNotSupported(device.address()))?;
+        }
+        if flags.contains(SvmMemFlags::ATOMICS) && !caps.contains(DeviceSvmCapabilities::ATOMICS) {
+            return Err(SvmError::AtomicsNotSupported(device.address()))?;
+        }
+    }
+    Ok(())
+}
+
+impl<T: Number> SvmBuffer<T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The size of the SVM allocation in bytes.
+    pub fn size(&self) -> usize {
+        self.len * std::mem::size_of::<T>()
+    }
+
+    pub fn flags(&self) -> SvmMemFlags {
+        self.flags
+    }
+
+    pub fn is_fine_grained(&self) -> bool {
+        self.flags.is_fine_grained()
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns the raw SVM pointer.
+    ///
+    /// # Safety
+    /// The pointer is owned by the SvmBuffer and must not outlive it.
+    pub unsafe fn svm_ptr(&self) -> *mut c_void {
+        self.ptr
+    }
+
+    /// Direct host access to a fine-grained SVM buffer.
+    ///
+    /// # Safety
+    /// Reading the buffer while a kernel is writing to it is a data race.
+    pub unsafe fn as_slice(&self) -> Output<&[T]> {
+        if !self.is_fine_grained() {
+            return Err(SvmError::NotFineGrained)?;
+        }
+        Ok(self.slice_unchecked())
+    }
+
+    /// Direct mutable host access to a fine-grained SVM buffer.
+    ///
+    /// # Safety
+    /// Writing to the buffer while a kernel is using it is a data race.
+    pub unsafe fn as_mut_slice(&mut self) -> Output<&mut [T]> {
+        if !self.is_fine_grained() {
+            return Err(SvmError::NotFineGrained)?;
+        }
+        Ok(self.mut_slice_unchecked())
+    }
+
+    /// Host access to the SVM buffer without checking the buffer's granularity.
+    ///
+    /// # Safety
+    /// Coarse-grained buffers must be mapped before this function is called.
+    pub unsafe fn slice_unchecked(&self) -> &[T] {
+        std::slice::from_raw_parts(self.ptr as *const T, self.len)
+    }
+
+    /// Mutable host access to the SVM buffer without checking the buffer's granularity.
+    ///
+    /// # Safety
+    /// Coarse-grained buffers must be mapped before this function is called.
+    pub unsafe fn mut_slice_unchecked(&mut self) -> &mut [T] {
+        std::slice::from_raw_parts_mut(self.ptr as *mut T, self.len)
+    }
+}
+
+impl<T: Number + NumberTypedT> NumberTyped for SvmBuffer<T> {
+    fn number_type(&self) -> NumberType {
+        T::number_type()
+    }
+}
+
+impl<T: Number> Drop for SvmBuffer<T> {
+    fn drop(&mut self) {
+        unsafe { functions::svm_free(self.context.context_ptr(), self.ptr) }
+    }
+}
+
+unsafe impl<T: Number> Send for SvmBuffer<T> {}
+
+impl<T: Number> fmt::Debug for SvmBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SvmBuffer{{ptr: {:?}, len: {}, flags: {:?}, context: {}}}",
+            self.ptr,
+            self.len,
+            self.flags,
+            self.context.address()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cl::SvmMemFlags;
+    use crate::*;
+
+    #[test]
+    fn svm_buffer_can_be_created_with_len() {
+        let (context, _devices) = ll_testing::get_context();
+        let svm =
+            unsafe { SvmBuffer::<u32>::create(&context, 10, SvmMemFlags::default()) }.unwrap();
+        assert_eq!(svm.len(), 10);
+        assert_eq!(svm.size(), 40);
+        assert!(!svm.is_fine_grained());
+    }
+
+    #[test]
+    fn svm_buffer_cannot_be_created_with_zero_len() {
+        let (context, _devices) = ll_testing::get_context();
+        let err =
+            unsafe { SvmBuffer::<u32>::create(&context, 0, SvmMemFlags::default()) }.unwrap_err();
+        assert_eq!(err.downcast_ref::<SvmError>(), Some(&SvmError::ZeroLength));
+    }
+
+    #[test]
+    fn coarse_grained_svm_buffer_cannot_be_accessed_directly() {
+        let (context, _devices) = ll_testing::get_context();
+        let svm =
+            unsafe { SvmBuffer::<u32>::create(&context, 10, SvmMemFlags::default()) }.unwrap();
+        let err = unsafe { svm.as_slice() }.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<SvmError>(),
+            Some(&SvmError::NotFineGrained)
+        );
+    }
+}
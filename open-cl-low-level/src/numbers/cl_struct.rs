@@ -0,0 +1,123 @@
+use crate::numbers::{
+    Char, ClTypeName, Double, Float, Half, Int, Long, Number, Short, SizeT, Uchar, Uint, Ulong,
+    Ushort,
+};
+
+/// Derives ClStruct (along with Number, ClAlignment and ClTypeName) for a `#[repr(C)]`
+/// struct. See the open-cl-derive crate.
+pub use open_cl_derive::ClStruct;
+
+/// A `#[repr(C)]` struct whose layout matches its OpenCL C counterpart.
+/// Implemented with `#[derive(ClStruct)]`.
+pub trait ClStruct: Number + ClAlignment + ClTypeName {
+    /// The OpenCL C `typedef struct` of the struct (e.g.
+    /// `typedef struct { float4 position; float mass; } Particle;`).
+    fn cl_typedef() -> String;
+}
+
+/// The alignment in bytes of a type in OpenCL C.
+///
+/// OpenCL C aligns scalars and vectors to their size (e.g. a `float4` is aligned to 16
+/// bytes), and the vector types are given the same alignment in Rust. 3 component
+/// vectors take the size and alignment of 4 component vectors. A struct's alignment is
+/// the largest alignment of its fields.
+pub trait ClAlignment {
+    const CL_ALIGNMENT: usize;
+}
+
+macro_rules! impl_cl_alignment_by_size {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl ClAlignment for $t {
+                const CL_ALIGNMENT: usize = std::mem::size_of::<$t>();
+            }
+        )*
+    };
+}
+
+macro_rules! impl_cl_alignment_for_vectors {
+    ( $( $scalar:ident ),* ) => {
+        $(
+            paste::item! {
+                impl_cl_alignment_by_size!(
+                    crate::numbers::[<$scalar 2>],
+                    crate::numbers::[<$scalar 3>],
+                    crate::numbers::[<$scalar 4>],
+                    crate::numbers::[<$scalar 8>],
+                    crate::numbers::[<$scalar 16>]
+                );
+            }
+        )*
+    };
+}
+
+impl_cl_alignment_by_size!(u8, i8, u16, i16, u32, i32, f32, u64, i64, f64, isize, usize);
+impl_cl_alignment_by_size!(
+    Char, Uchar, Short, Ushort, Int, Uint, Long, Ulong, Float, Double, SizeT, Half
+);
+impl_cl_alignment_for_vectors!(
+    Char, Double, Float, Half, Int, Long, Short, Uchar, Uint, Ulong, Ushort
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::numbers::{
+        ClAlignment, ClStruct, ClTypeName, Float3, Float4, Number, NumberType, NumberTypedT, One,
+        Zero,
+    };
+
+    #[derive(ClStruct, Clone, Copy, Debug, Default, PartialEq)]
+    #[repr(C)]
+    struct Particle {
+        position: Float4,
+        velocity: Float4,
+        mass: f32,
+        charge: f32,
+        id: u32,
+        _padding: u32,
+    }
+
+    #[derive(ClStruct, Clone, Copy, Debug, Default, PartialEq)]
+    #[repr(C)]
+    struct Pair(u32, f32);
+
+    #[test]
+    fn vectors_are_aligned_to_their_size() {
+        assert_eq!(Float4::CL_ALIGNMENT, 16);
+        assert_eq!(std::mem::align_of::<Float4>(), 16);
+        assert_eq!(Float3::CL_ALIGNMENT, 16);
+    }
+
+    #[test]
+    fn cl_struct_alignment_is_the_largest_field_alignment() {
+        assert_eq!(Particle::CL_ALIGNMENT, 16);
+        assert_eq!(Pair::CL_ALIGNMENT, 4);
+        assert_eq!(std::mem::size_of::<Particle>(), 48);
+        assert_eq!(std::mem::align_of::<Particle>(), 16);
+    }
+
+    #[test]
+    fn cl_struct_implements_number() {
+        let pair = Pair::new(Pair(1, 2.0));
+        assert_eq!(pair + Pair(1, 1.0), Pair(2, 3.0));
+        assert_eq!(pair * Pair(2, 2.0), Pair(2, 4.0));
+        assert!(Pair::zero().is_zero());
+        assert_eq!(Pair::one(), Pair(1, 1.0));
+        assert_eq!(format!("{}", pair), "Pair(1, 2.0)");
+        assert_eq!(Pair::number_type(), NumberType::new::<Pair>());
+    }
+
+    #[test]
+    fn cl_struct_has_a_cl_typedef() {
+        assert_eq!(
+            Particle::cl_typedef(),
+            "typedef struct { float4 position; float4 velocity; float mass; float charge; \
+             uint id; uint _padding; } Particle;"
+        );
+        assert_eq!(Pair::CL_TYPE_NAME, "Pair");
+        assert_eq!(
+            Pair::cl_typedef(),
+            "typedef struct { uint _0; float _1; } Pair;"
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::any;
+
+use crate::numbers::{
+    Char, Double, Float, Half, Int, Long, Short, SizeT, Uchar, Uint, Ulong, Ushort,
+};
+
+/// The spelling of a type in OpenCL C (e.g. `int`, `float4` or `ulong`).
+///
+/// Implemented for the built-in Numbers (and by `#[derive(ClStruct)]`) so that kernel
+/// source can be generated for a Rust type. See `KernelTemplate` in open-cl-core.
+pub trait ClTypeName {
+    const CL_TYPE_NAME: &'static str;
+}
+
+macro_rules! impl_cl_type_name {
+    ( $( $t:ty => $name:expr ),* ) => {
+        $(
+            impl ClTypeName for $t {
+                const CL_TYPE_NAME: &'static str = $name;
+            }
+        )*
+    };
+}
+
+macro_rules! impl_cl_type_name_for_vectors {
+    ( $( $scalar:ident => $name:expr ),* ) => {
+        $(
+            paste::item! {
+                impl_cl_type_name!(
+                    crate::numbers::[<$scalar 2>] => concat!($name, "2"),
+                    crate::numbers::[<$scalar 3>] => concat!($name, "3"),
+                    crate::numbers::[<$scalar 4>] => concat!($name, "4"),
+                    crate::numbers::[<$scalar 8>] => concat!($name, "8"),
+                    crate::numbers::[<$scalar 16>] => concat!($name, "16")
+                );
+            }
+        )*
+    };
+}
+
+impl_cl_type_name!(
+    u8 => "uchar",
+    i8 => "char",
+    u16 => "ushort",
+    i16 => "short",
+    u32 => "uint",
+    i32 => "int",
+    f32 => "float",
+    u64 => "ulong",
+    i64 => "long",
+    f64 => "double",
+    usize => "size_t",
+    isize => "ptrdiff_t"
+);
+
+impl_cl_type_name!(
+    Uchar => "uchar",
+    Char => "char",
+    Ushort => "ushort",
+    Short => "short",
+    Uint => "uint",
+    Int => "int",
+    Ulong => "ulong",
+    Long => "long",
+    Float => "float",
+    Double => "double",
+    SizeT => "size_t",
+    Half => "half"
+);
+
+impl_cl_type_name_for_vectors!(
+    Uchar => "uchar",
+    Char => "char",
+    Ushort => "ushort",
+    Short => "short",
+    Uint => "uint",
+    Int => "int",
+    Ulong => "ulong",
+    Long => "long",
+    Float => "float",
+    Double => "double",
+    Half => "half"
+);
+
+macro_rules! return_cl_type_name_if_type_id_of {
+    ( $type_id:expr, $( $t:ty ),* ) => {
+        $(
+            if $type_id == any::TypeId::of::<$t>() {
+                return Some(<$t as ClTypeName>::CL_TYPE_NAME);
+            }
+        )*
+    };
+}
+
+macro_rules! return_vector_cl_type_name_if_type_id_of {
+    ( $type_id:expr, $( $scalar:ident ),* ) => {
+        $(
+            paste::item! {
+                return_cl_type_name_if_type_id_of!(
+                    $type_id,
+                    crate::numbers::[<$scalar 2>],
+                    crate::numbers::[<$scalar 3>],
+                    crate::numbers::[<$scalar 4>],
+                    crate::numbers::[<$scalar 8>],
+                    crate::numbers::[<$scalar 16>]
+                );
+            }
+        )*
+    };
+}
+
+/// The OpenCL C type name of a built-in Number looked up by TypeId. This is the runtime
+/// counterpart of `ClTypeName` for code that only holds a `NumberType` (e.g. an
+/// UntypedBuffer). None for types that are not built-in Numbers (e.g. ClStructs).
+pub fn cl_type_name_of(type_id: any::TypeId) -> Option<&'static str> {
+    return_cl_type_name_if_type_id_of!(
+        type_id, u8, i8, u16, i16, u32, i32, f32, u64, i64, f64, usize, isize, Uchar, Char, Ushort,
+        Short, Uint, Int, Ulong, Long, Float, Double, SizeT, Half
+    );
+    return_vector_cl_type_name_if_type_id_of!(
+        type_id, Uchar, Char, Ushort, Short, Uint, Int, Ulong, Long, Float, Double, Half
+    );
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::numbers::{
+        ClTypeName, Float3, Float4, Half8, Int, NumberType, NumberTyped, NumberTypedT, Uchar16,
+    };
+
+    #[test]
+    fn number_types_know_their_cl_type_name() {
+        assert_eq!(i32::CL_TYPE_NAME, "int");
+        assert_eq!(u64::CL_TYPE_NAME, "ulong");
+        assert_eq!(Int::CL_TYPE_NAME, "int");
+        assert_eq!(Float3::CL_TYPE_NAME, "float3");
+        assert_eq!(Float4::CL_TYPE_NAME, "float4");
+        assert_eq!(Uchar16::CL_TYPE_NAME, "uchar16");
+        assert_eq!(Half8::CL_TYPE_NAME, "half8");
+        assert_eq!(f64::CL_TYPE_NAME, "double");
+    }
+
+    #[test]
+    fn number_types_know_their_cl_type_name_at_runtime() {
+        assert_eq!(i32::cl_type_name(), Some("int"));
+        assert_eq!(Float3::cl_type_name(), Some("float3"));
+        assert_eq!(Half8::cl_type_name(), Some("half8"));
+        let number_type = NumberType::new::<f64>();
+        assert_eq!(number_type.number_type_cl_name(), Some("double"));
+        assert_eq!(NumberTyped::cl_type_name(&number_type), Some("double"));
+    }
+}
@@ -61,10 +61,12 @@ macro_rules! get_index {
 #[repr(C)]
 pub struct ClVector2<T: Scalar>(T, T);
 
-// 3 has 4 slots because we do not align memory correctly.
-// #[derive(Clone, Copy, Hash)]
-// #[repr(C)]
-// pub struct ClVector3<T: Scalar>(T, T, T, T);
+/// A 3 component vector with the size of a 4 component vector, like OpenCL's 3
+/// component vectors. The 4th slot is padding; it is always zero on the host and is
+/// ignored by comparisons (hence no Hash).
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct ClVector3<T: Scalar>(T, T, T, T);
 
 #[derive(Clone, Copy, Hash)]
 #[repr(C)]
@@ -103,34 +105,36 @@ macro_rules! vector_n_trait {
 }
 
 vector_n_trait!(2);
+vector_n_trait!(3);
 vector_n_trait!(4);
 vector_n_trait!(8);
 vector_n_trait!(16);
 
 macro_rules! def_array {
-  // { name: $name:ident, size: 3 } => { def_array!($name,  4, [0, 1, 2], [0, 1, 2, 3]); };
-  { name: $name:ident, size: 1 } => { def_array!($name, 1, [0]); };
-  { name: $name:ident, size: 2 } => { def_array!($name, 2, [0, 1]); };
-  { name: $name:ident, size: 4 } => { def_array!($name, 4, [0, 1, 2, 3]); };
-  { name: $name:ident, size: 8 } => { def_array!($name, 8, [0, 1, 2, 3, 4, 5, 6, 7]); };
-  { name: $name:ident, size: 16 } => { def_array!($name, 16, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]); };
+  // The last list is the values of the padding slots of the vector.
+  { name: $name:ident, size: 1 } => { def_array!($name, 1, [0], []); };
+  { name: $name:ident, size: 2 } => { def_array!($name, 2, [0, 1], []); };
+  { name: $name:ident, size: 3 } => { def_array!($name, 3, [0, 1, 2], [Zero::zero()]); };
+  { name: $name:ident, size: 4 } => { def_array!($name, 4, [0, 1, 2, 3], []); };
+  { name: $name:ident, size: 8 } => { def_array!($name, 8, [0, 1, 2, 3, 4, 5, 6, 7], []); };
+  { name: $name:ident, size: 16 } => { def_array!($name, 16, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15], []); };
 
   // $keep_i copies the given index
   // ($name:ident, $outer_size:expr, $inner_size:expr, [$( $outer_i:expr ),+]) => {
   //   def_array!($name, $outer_size, $inner_size, [$( $outer_i ),+], [$( $outer_i ),+]);
   // };
-  ($name:ident, $n:expr, [$( $i:expr ),+]) => {
+  ($name:ident, $n:expr, [$( $i:expr ),+], [$( $pad:expr ),*]) => {
     impl_index!($name, $n, [$( $i ),+]);
     impl_index_mut!($name, $n, [$( $i ),+]);
-    impl_cl_vector!($name, $n, [$( $i ),+]);
+    impl_cl_vector!($name, $n, [$( $i ),+], [$( $pad ),*]);
     impl_zero!($name, [$( $i ),+]);
     impl_one!($name, [$( $i ),+]);
     impl_debug!($name, [$( $i ),+]);
     impl_cmp!($name, [$( $i ),+]);
-    impl_add!($name, [$( $i ),+]);
-    impl_mul!($name, [$( $i ),+]);
-    // impl_sub!($name, [$( $i ),+]);
-    // impl_div!($name, [$( $i ),+]);
+    impl_add!($name, [$( $i ),+], [$( $pad ),*]);
+    impl_mul!($name, [$( $i ),+], [$( $pad ),*]);
+    impl_sub!($name, [$( $i ),+], [$( $pad ),*]);
+    impl_div!($name, [$( $i ),+], [$( $pad ),*]);
     // impl_rem!($name, [$( $i ),+]);
     // impl_neg!($name, [$( $i ),+]);
     // impl_not!($name, [$( $i ),+]);
@@ -142,7 +146,7 @@ macro_rules! def_array {
     // impl_shr_unsigned!($name, [Uchar, Ushort, Uint, Ulong], [$( $i ),+]);
     // impl_shr_assign!($name);
     rust_array_conv!($name, $n);
-    impl_array_num_cast_from!($name, $n, [$( $i ),+]);
+    impl_array_num_cast_from!($name, $n, [$( $i ),+], [$( $pad ),*]);
     impl_default!($name, $n);
   };
 }
@@ -158,52 +162,52 @@ macro_rules! impl_default {
 }
 
 macro_rules! impl_add {
-  ($name:ident, [$( $i:expr ),+]) => {
+  ($name:ident, [$( $i:expr ),+], [$( $pad:expr ),*]) => {
     impl<T: Scalar> Add for $name<T> {
       type Output = $name<T>;
 
       fn add(self, other: $name<T>) -> $name<T> {
-        $name( $( self[$i] + other[$i], )+ )
+        $name( $( self[$i] + other[$i], )+ $( $pad, )* )
       }
     }
   }
 }
 
-// macro_rules! impl_sub {
-//   ($name:ident, [$( $i:expr ),+]) => {
-//     impl<T: Scalar> Sub for $name<T> {
-//       type Output = $name<T>;
+macro_rules! impl_sub {
+  ($name:ident, [$( $i:expr ),+], [$( $pad:expr ),*]) => {
+    impl<T: Scalar + Sub<Output = T>> Sub for $name<T> {
+      type Output = $name<T>;
 
-//       fn sub(self, other: $name<T>) -> $name<T> {
-//         $name( $( self[$i] - other[$i], )+ )
-//       }
-//     }
-//   }
-// }
+      fn sub(self, other: $name<T>) -> $name<T> {
+        $name( $( self[$i] - other[$i], )+ $( $pad, )* )
+      }
+    }
+  }
+}
 
 macro_rules! impl_mul {
-  ($name:ident, [$( $i:expr ),+]) => {
+  ($name:ident, [$( $i:expr ),+], [$( $pad:expr ),*]) => {
     impl<T: Scalar> Mul for $name<T> {
       type Output = $name<T>;
 
       fn mul(self, other: $name<T>) -> $name<T> {
-        $name( $( self[$i] * other[$i], )+ )
+        $name( $( self[$i] * other[$i], )+ $( $pad, )* )
       }
     }
   }
 }
 
-// macro_rules! impl_div {
-//   ($name:ident, [$( $i:expr ),+]) => {
-//     impl<T: Scalar> Div for $name<T> {
-//       type Output = $name<T>;
+macro_rules! impl_div {
+  ($name:ident, [$( $i:expr ),+], [$( $pad:expr ),*]) => {
+    impl<T: Scalar + Div<Output = T>> Div for $name<T> {
+      type Output = $name<T>;
 
-//       fn div(self, other: $name<T>) -> $name<T> {
-//         $name( $( self[$i] / other[$i], )+ )
-//       }
-//     }
-//   }
-// }
+      fn div(self, other: $name<T>) -> $name<T> {
+        $name( $( self[$i] / other[$i], )+ $( $pad, )* )
+      }
+    }
+  }
+}
 // macro_rules! impl_neg {
 //   ($name:ident, [$( $i:expr ),+]) => {
 //     impl<T: Scalar> Neg for $name<T> {
@@ -266,10 +270,10 @@ macro_rules! impl_index_mut {
 }
 
 macro_rules! impl_cl_vector {
-  ($name:ident, $n:expr, [ $( $i:expr ),+ ]) => {
+  ($name:ident, $n:expr, [ $( $i:expr ),+ ], [$( $pad:expr ),*]) => {
     impl<T: Scalar> ClVector<T, $n> for $name<T> {
       fn new(val: [T; $n]) -> Self {
-        $name($( val[$i] ),+)
+        $name($( val[$i], )+ $( $pad, )*)
       }
 
       fn into_array(self) -> [T; $n] {
@@ -450,7 +454,7 @@ macro_rules! rust_array_conv {
 
     impl<T: Scalar> From<$name<T>> for [T; $n] {
       fn from(val: $name<T>) -> [T; $n] {
-        val.into()
+        val.into_array()
       }
     }
   };
@@ -465,19 +469,26 @@ macro_rules! impl_assigns_ops {
       }
     }
 
-    //         impl<T: Scalar> SubAssign for $name<T> {
-    //             #[inline(always)]
-    //             fn sub_assign(&mut self, other: $name<T>) {
-    //                 *self = *self - other;
-    //             }
-    //         }
+    impl<T: Scalar + Sub<Output = T>> SubAssign for $name<T> {
+      #[inline(always)]
+      fn sub_assign(&mut self, other: $name<T>) {
+        *self = *self - other;
+      }
+    }
 
-    //         impl<T: Scalar> MulAssign for $name<T> {
-    //             #[inline(always)]
-    //             fn mul_assign(&mut self, other: $name<T>) {
-    //                 *self = *self * other;
-    //             }
-    //         }
+    impl<T: Scalar> MulAssign for $name<T> {
+      #[inline(always)]
+      fn mul_assign(&mut self, other: $name<T>) {
+        *self = *self * other;
+      }
+    }
+
+    impl<T: Scalar + Div<Output = T>> DivAssign for $name<T> {
+      #[inline(always)]
+      fn div_assign(&mut self, other: $name<T>) {
+        *self = *self / other;
+      }
+    }
 
     //         impl<T: Scalar> RemAssign for $name<T> {
     //             #[inline(always)]
@@ -486,12 +497,6 @@ macro_rules! impl_assigns_ops {
     //             }
     //         }
 
-    //         impl<T: Scalar> DivAssign for $name<T> {
-    //             #[inline(always)]
-    //             fn div_assign(&mut self, other: $name<T>) {
-    //                 *self = *self / other;
-    //             }
-    //         }
   };
 }
 
@@ -509,27 +514,197 @@ macro_rules! impl_assigns_ops {
 // }
 
 macro_rules! impl_array_num_cast_from {
-  ($name:ident, $n:expr, [ $( $i:expr ),+]) => {
+  ($name:ident, $n:expr, [ $( $i:expr ),+], [$( $pad:expr ),*]) => {
     impl<T: Scalar, U: Scalar + NumCastInto<T>> NumCastFrom<$name<U>> for $name<T> {
       fn num_cast_from(other: $name<U>) -> Option<$name<T>> {
-        Some($name( $( other[$i].num_cast_into()? ),+ ))
+        Some($name( $( other[$i].num_cast_into()?, )+ $( $pad, )* ))
       }
     }
 
     impl<T: Scalar, U: Copy + NumCastInto<T>> NumCastFrom<[U; $n]> for $name<T> {
         fn num_cast_from(arr: [U; $n]) -> Option<$name<T>> {
-          Some($name( $( arr[$i].num_cast_into()? ),+ ))
+          Some($name( $( arr[$i].num_cast_into()?, )+ $( $pad, )* ))
         }
       }
   }
 }
 
+// Swizzles like OpenCL's `.xy`, `.zyx` and `.wzyx` for vectors of up to 4 components.
+// Every 2, 3 and 4 component combination of the given components is generated, returning
+// the given 2, 3 and 4 component vector types. Used for the ClVectors and (in vectors.rs)
+// for the vector newtypes so a swizzle of a Float4 is a Float2, Float3 or Float4.
+macro_rules! impl_swizzles {
+  ([$( $header:tt )+], ($v2:ty, $v3:ty, $v4:ty), [$( $c:tt ),+]) => {
+    $( $header )+ {
+      impl_swizzles!(@two $v2, [$( $c ),+] [$( $c ),+]);
+      impl_swizzles!(@three $v3, [$( $c ),+] [$( $c ),+]);
+      impl_swizzles!(@four $v4, [$( $c ),+] [$( $c ),+]);
+    }
+  };
+
+  (@two $v2:ty, [$( $all:tt ),+] []) => {};
+  (@two $v2:ty, [$( ($b:ident, $j:expr) ),+] [($a:ident, $i:expr) $(, $rest:tt )*]) => {
+    paste::item! {
+      $(
+        #[inline]
+        pub fn [<$a $b>](&self) -> $v2 {
+          <$v2>::new([self[$i], self[$j]])
+        }
+      )+
+    }
+    impl_swizzles!(@two $v2, [$( ($b, $j) ),+] [$( $rest ),*]);
+  };
+
+  (@three $v3:ty, [$( $all:tt ),+] []) => {};
+  (@three $v3:ty, [$( $all:tt ),+] [$a:tt $(, $rest:tt )*]) => {
+    impl_swizzles!(@three_b $v3, $a [$( $all ),+] [$( $all ),+]);
+    impl_swizzles!(@three $v3, [$( $all ),+] [$( $rest ),*]);
+  };
+  (@three_b $v3:ty, $a:tt [$( $all:tt ),+] []) => {};
+  (@three_b $v3:ty, ($a:ident, $i:expr) [$( ($c:ident, $k:expr) ),+] [($b:ident, $j:expr) $(, $rest:tt )*]) => {
+    paste::item! {
+      $(
+        #[inline]
+        pub fn [<$a $b $c>](&self) -> $v3 {
+          <$v3>::new([self[$i], self[$j], self[$k]])
+        }
+      )+
+    }
+    impl_swizzles!(@three_b $v3, ($a, $i) [$( ($c, $k) ),+] [$( $rest ),*]);
+  };
+
+  (@four $v4:ty, [$( $all:tt ),+] []) => {};
+  (@four $v4:ty, [$( $all:tt ),+] [$a:tt $(, $rest:tt )*]) => {
+    impl_swizzles!(@four_b $v4, $a [$( $all ),+] [$( $all ),+]);
+    impl_swizzles!(@four $v4, [$( $all ),+] [$( $rest ),*]);
+  };
+  (@four_b $v4:ty, $a:tt [$( $all:tt ),+] []) => {};
+  (@four_b $v4:ty, $a:tt [$( $all:tt ),+] [$b:tt $(, $rest:tt )*]) => {
+    impl_swizzles!(@four_c $v4, $a $b [$( $all ),+] [$( $all ),+]);
+    impl_swizzles!(@four_b $v4, $a [$( $all ),+] [$( $rest ),*]);
+  };
+  (@four_c $v4:ty, $a:tt $b:tt [$( $all:tt ),+] []) => {};
+  (@four_c $v4:ty, ($a:ident, $i:expr) ($b:ident, $j:expr) [$( ($d:ident, $l:expr) ),+] [($c:ident, $k:expr) $(, $rest:tt )*]) => {
+    paste::item! {
+      $(
+        #[inline]
+        pub fn [<$a $b $c $d>](&self) -> $v4 {
+          <$v4>::new([self[$i], self[$j], self[$k], self[$l]])
+        }
+      )+
+    }
+    impl_swizzles!(@four_c $v4, ($a, $i) ($b, $j) [$( ($d, $l) ),+] [$( $rest ),*]);
+  };
+}
+
+// OpenCL's `.lo`, `.hi`, `.even` and `.odd`. The halves of a 2 component vector are
+// scalars. A 3 component vector is treated as a 4 component vector; the halves that
+// include the 4th component get the padding (zero) in its place.
+macro_rules! impl_halves {
+  ([$( $header:tt )+], $half:ty, [$( $lo:tt ),+], [$( $hi:tt ),+], [$( $even:tt ),+], [$( $odd:tt ),+]) => {
+    $( $header )+ {
+      /// The lower half of the components.
+      #[inline]
+      pub fn lo(&self) -> $half {
+        <$half>::new([$( impl_halves!(@component self, $lo) ),+])
+      }
+
+      /// The upper half of the components.
+      #[inline]
+      pub fn hi(&self) -> $half {
+        <$half>::new([$( impl_halves!(@component self, $hi) ),+])
+      }
+
+      /// The even components.
+      #[inline]
+      pub fn even(&self) -> $half {
+        <$half>::new([$( impl_halves!(@component self, $even) ),+])
+      }
+
+      /// The odd components.
+      #[inline]
+      pub fn odd(&self) -> $half {
+        <$half>::new([$( impl_halves!(@component self, $odd) ),+])
+      }
+    }
+  };
+  (@component $self:tt, pad) => {
+    Zero::zero()
+  };
+  (@component $self:tt, $i:tt) => {
+    $self[$i]
+  };
+  (@two [$( $header:tt )+], $scalar:ty) => {
+    $( $header )+ {
+      #[inline]
+      pub fn lo(&self) -> $scalar {
+        self[0]
+      }
+
+      #[inline]
+      pub fn hi(&self) -> $scalar {
+        self[1]
+      }
+
+      #[inline]
+      pub fn even(&self) -> $scalar {
+        self[0]
+      }
+
+      #[inline]
+      pub fn odd(&self) -> $scalar {
+        self[1]
+      }
+    }
+  };
+  (@three [$( $header:tt )+], $half:ty) => {
+    impl_halves!([$( $header )+], $half, [0, 1], [2, pad], [0, 2], [1, pad]);
+  };
+  (@four [$( $header:tt )+], $half:ty) => {
+    impl_halves!([$( $header )+], $half, [0, 1], [2, 3], [0, 2], [1, 3]);
+  };
+  (@eight [$( $header:tt )+], $half:ty) => {
+    impl_halves!([$( $header )+], $half, [0, 1, 2, 3], [4, 5, 6, 7], [0, 2, 4, 6], [1, 3, 5, 7]);
+  };
+  (@sixteen [$( $header:tt )+], $half:ty) => {
+    impl_halves!(
+      [$( $header )+],
+      $half,
+      [0, 1, 2, 3, 4, 5, 6, 7],
+      [8, 9, 10, 11, 12, 13, 14, 15],
+      [0, 2, 4, 6, 8, 10, 12, 14],
+      [1, 3, 5, 7, 9, 11, 13, 15]
+    );
+  };
+}
+
 def_array! { name: ClVector2, size: 2 }
+def_array! { name: ClVector3, size: 3 }
 def_array! { name: ClVector4, size: 4 }
 def_array! { name: ClVector8, size: 8 }
 def_array! { name: ClVector16, size: 16 }
 
-// def_array! { name: ClVector3, size: 3 }
+impl_swizzles!(
+  [impl<T: Scalar> ClVector2<T>],
+  (ClVector2<T>, ClVector3<T>, ClVector4<T>),
+  [(x, 0), (y, 1)]
+);
+impl_swizzles!(
+  [impl<T: Scalar> ClVector3<T>],
+  (ClVector2<T>, ClVector3<T>, ClVector4<T>),
+  [(x, 0), (y, 1), (z, 2)]
+);
+impl_swizzles!(
+  [impl<T: Scalar> ClVector4<T>],
+  (ClVector2<T>, ClVector3<T>, ClVector4<T>),
+  [(x, 0), (y, 1), (z, 2), (w, 3)]
+);
+
+impl_halves!(@two [impl<T: Scalar> ClVector2<T>], T);
+impl_halves!(@three [impl<T: Scalar> ClVector3<T>], ClVector2<T>);
+impl_halves!(@four [impl<T: Scalar> ClVector4<T>], ClVector2<T>);
+impl_halves!(@eight [impl<T: Scalar> ClVector8<T>], ClVector4<T>);
+impl_halves!(@sixteen [impl<T: Scalar> ClVector16<T>], ClVector8<T>);
 
 #[cfg(test)]
 mod arrays_tests {
@@ -607,3 +782,110 @@ mod arrays_tests {
   // }
   // __run_ops4!(Char, Int, Long, Short, Uchar, Uint, Ulong, Ushort);
 }
+
+#[cfg(test)]
+mod vector_tests {
+  use crate::numbers::cl_vectors::{ClVector, ClVector16, ClVector2, ClVector3, ClVector4, ClVector8};
+  use crate::numbers::{Double16, Double3, Float3, Float4, Half3, Int2, Uchar3, Ushort8};
+  use std::mem::{align_of, size_of};
+
+  #[test]
+  fn vector3_has_the_size_and_alignment_of_vector4() {
+    assert_eq!(size_of::<ClVector3<f32>>(), size_of::<ClVector4<f32>>());
+    assert_eq!(size_of::<ClVector3<u8>>(), 4);
+    // cl_float3 and cl_float4 are both 16 byte aligned.
+    assert_eq!(size_of::<Float3>(), 16);
+    assert_eq!(align_of::<Float3>(), 16);
+    assert_eq!(size_of::<Float4>(), 16);
+    assert_eq!(align_of::<Float4>(), 16);
+    assert_eq!(align_of::<Uchar3>(), 4);
+    assert_eq!(align_of::<Half3>(), 8);
+    assert_eq!(align_of::<Double3>(), 32);
+  }
+
+  #[test]
+  fn vectors_are_aligned_to_their_size() {
+    assert_eq!(align_of::<Int2>(), 8);
+    assert_eq!(align_of::<Ushort8>(), 16);
+    assert_eq!(size_of::<Double16>(), 128);
+    assert_eq!(align_of::<Double16>(), 128);
+  }
+
+  #[test]
+  fn vector3_padding_does_not_affect_equality_or_arrays() {
+    let v = ClVector3::new([1i32, 2, 3]);
+    assert_eq!(v, ClVector3(1, 2, 3, 99));
+    assert_eq!(v.into_array(), [1, 2, 3]);
+    let arr: [i32; 3] = v.into();
+    assert_eq!(arr, [1, 2, 3]);
+  }
+
+  #[test]
+  fn vectors_support_element_wise_arithmetic() {
+    let a = ClVector4::new([8i32, 6, 4, 2]);
+    let b = ClVector4::new([2i32, 3, 4, 1]);
+    assert_eq!(a + b, ClVector4::new([10, 9, 8, 3]));
+    assert_eq!(a - b, ClVector4::new([6, 3, 0, 1]));
+    assert_eq!(a * b, ClVector4::new([16, 18, 16, 2]));
+    assert_eq!(a / b, ClVector4::new([4, 2, 1, 2]));
+
+    let mut c = ClVector3::new([1.0f32, 2.0, 3.0]);
+    c += ClVector3::new([1.0, 1.0, 1.0]);
+    c *= ClVector3::new([2.0, 2.0, 2.0]);
+    c -= ClVector3::new([1.0, 1.0, 1.0]);
+    c /= ClVector3::new([3.0, 5.0, 7.0]);
+    assert_eq!(c.into_array(), [1.0, 1.0, 1.0]);
+  }
+
+  #[test]
+  fn vectors_support_swizzles() {
+    let v = ClVector4::new([1i32, 2, 3, 4]);
+    assert_eq!(v.xy(), ClVector2::new([1, 2]));
+    assert_eq!(v.zyx(), ClVector3::new([3, 2, 1]));
+    assert_eq!(v.wzyx(), ClVector4::new([4, 3, 2, 1]));
+    assert_eq!(v.xxww(), ClVector4::new([1, 1, 4, 4]));
+
+    let v3 = ClVector3::new([1i32, 2, 3]);
+    assert_eq!(v3.zyx(), ClVector3::new([3, 2, 1]));
+    assert_eq!(ClVector2::new([1i32, 2]).yx(), ClVector2::new([2, 1]));
+  }
+
+  #[test]
+  fn vectors_support_lo_hi_even_and_odd() {
+    let v = ClVector4::new([1i32, 2, 3, 4]);
+    assert_eq!(v.lo(), ClVector2::new([1, 2]));
+    assert_eq!(v.hi(), ClVector2::new([3, 4]));
+    assert_eq!(v.even(), ClVector2::new([1, 3]));
+    assert_eq!(v.odd(), ClVector2::new([2, 4]));
+
+    let v3 = ClVector3::new([1i32, 2, 3]);
+    assert_eq!(v3.hi(), ClVector2::new([3, 0]));
+    assert_eq!(v3.odd(), ClVector2::new([2, 0]));
+
+    let v8 = ClVector8::new([0i32, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(v8.hi(), ClVector4::new([4, 5, 6, 7]));
+    assert_eq!(v8.odd(), ClVector4::new([1, 3, 5, 7]));
+
+    let mut arr = [0i32; 16];
+    for (i, x) in arr.iter_mut().enumerate() {
+      *x = i as i32;
+    }
+    let v16 = ClVector16::new(arr);
+    assert_eq!(v16.even(), ClVector8::new([0, 2, 4, 6, 8, 10, 12, 14]));
+  }
+
+  #[test]
+  fn vector_newtypes_swizzle_into_newtypes() {
+    use crate::numbers::{Int, Int2, Int3, Int4, Int8, Number};
+
+    let v = Int4::from([1i32, 2, 3, 4]);
+    let xy: Int2 = v.xy();
+    assert_eq!(xy, Int2::from([1i32, 2]));
+    assert_eq!(v.zyx(), Int3::from([3i32, 2, 1]));
+    assert_eq!(v.hi(), Int2::from([3i32, 4]));
+    assert_eq!(Int3::from([1i32, 2, 3]).odd(), Int2::from([2i32, 0]));
+    assert_eq!(Int2::from([1i32, 2]).hi(), Int::new(2));
+    let v8 = Int8::from([0i32, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(v8.odd(), Int4::from([1i32, 3, 5, 7]));
+  }
+}
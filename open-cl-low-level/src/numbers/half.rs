@@ -1,8 +1,9 @@
 // "half" package aliased to "half_lib" in the Cargo.toml.
 use crate::cl::cl_half;
-use crate::numbers::{NumCastFrom, NumberOps, One, ToPrimitive, Zero};
+use crate::numbers::{NumCastFrom, Number, NumberOps, One, ToPrimitive, Zero};
 use crate::Output;
 use half_lib::f16;
+use half_lib::slice::{HalfBitsSliceExt, HalfFloatSliceExt};
 use std::cmp::Ordering;
 use std::fmt;
 use std::ops::*;
@@ -29,6 +30,8 @@ pub enum HalfError {
     ValueTooHigh(f32),
     #[error("Half value was too low (min_value {}, got {0})", MIN)]
     ValueTooLow(f32),
+    #[error("Half slice conversion length mismatch (src {0}, dst {1})")]
+    LengthMismatch(usize, usize),
 }
 
 use HalfError::*;
@@ -71,9 +74,71 @@ impl Half {
     pub const fn min_value() -> Half {
         MIN
     }
+
+    /// Converts a slice of f32 to a Vec of Half in bulk. Values outside the range of
+    /// Half become infinities. Much faster than casting element by element.
+    pub fn from_f32_slice(src: &[f32]) -> Vec<Half> {
+        let mut dst = vec![ZERO; src.len()];
+        as_f16_slice_mut(&mut dst[..]).convert_from_f32_slice(src);
+        dst
+    }
+
+    /// Converts a slice of Half to a Vec of f32 in bulk.
+    pub fn into_f32_vec(src: &[Half]) -> Vec<f32> {
+        let mut dst = vec![0.0f32; src.len()];
+        as_f16_slice(src).convert_to_f32_slice(&mut dst[..]);
+        dst
+    }
+
+    /// Converts a slice of f32 into an existing slice of Half of the same length.
+    pub fn convert_from_f32_slice(dst: &mut [Half], src: &[f32]) -> Output<()> {
+        if src.len() != dst.len() {
+            return Err(LengthMismatch(src.len(), dst.len()))?;
+        }
+        as_f16_slice_mut(dst).convert_from_f32_slice(src);
+        Ok(())
+    }
+
+    /// Converts a slice of Half into an existing slice of f32 of the same length.
+    pub fn convert_to_f32_slice(src: &[Half], dst: &mut [f32]) -> Output<()> {
+        if src.len() != dst.len() {
+            return Err(LengthMismatch(src.len(), dst.len()))?;
+        }
+        as_f16_slice(src).convert_to_f32_slice(dst);
+        Ok(())
+    }
+}
+
+// Half is repr(transparent) over cl_half (u16) which has the same layout as f16.
+fn as_f16_slice(halfs: &[Half]) -> &[f16] {
+    let bits: &[cl_half] =
+        unsafe { std::slice::from_raw_parts(halfs.as_ptr() as *const cl_half, halfs.len()) };
+    bits.reinterpret_cast()
+}
+
+fn as_f16_slice_mut(halfs: &mut [Half]) -> &mut [f16] {
+    let bits: &mut [cl_half] =
+        unsafe { std::slice::from_raw_parts_mut(halfs.as_mut_ptr() as *mut cl_half, halfs.len()) };
+    bits.reinterpret_cast_mut()
 }
+
 impl NumberOps for Half {}
 
+impl Number for Half {
+    type Scalar = Half;
+    type Outer = f32;
+
+    #[inline(always)]
+    fn new(val: f32) -> Half {
+        Half::from_f32(val)
+    }
+
+    #[inline(always)]
+    fn into_outer(self) -> f32 {
+        self.into_f32()
+    }
+}
+
 impl From<f32> for Half {
     fn from(val: f32) -> Half {
         Half::from_f32(val)
@@ -239,6 +304,28 @@ impl fmt::Display for Half {
     }
 }
 
+impl ToPrimitive for Half {
+    #[inline]
+    fn to_i64(&self) -> Option<i64> {
+        self.into_f32().to_i64()
+    }
+
+    #[inline]
+    fn to_u64(&self) -> Option<u64> {
+        self.into_f32().to_u64()
+    }
+
+    #[inline]
+    fn to_f32(&self) -> Option<f32> {
+        Some(self.into_f32())
+    }
+
+    #[inline]
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.into_f32() as f64)
+    }
+}
+
 impl<T> NumCastFrom<T> for Half
 where
     T: ToPrimitive,
@@ -263,7 +350,7 @@ where
 
 #[cfg(test)]
 mod half_tests {
-    use super::Half;
+    use super::{Half, HalfError};
     // use crate::numbers::{Char, Float};
     use crate::numbers::NumCastFrom; //, NumCastInto, Number};
     use num_traits::{One, Zero};
@@ -318,6 +405,16 @@ mod half_tests {
         assert_eq!(one_again, one);
     }
 
+    #[test]
+    fn test_to_primitive_impl() {
+        use num_traits::ToPrimitive;
+        let h = Half::from(2.5f32);
+        assert_eq!(h.to_f32(), Some(2.5));
+        assert_eq!(h.to_f64(), Some(2.5));
+        assert_eq!(h.to_i64(), Some(2));
+        assert_eq!(Half::from(-1.0f32).to_u64(), None);
+    }
+
     #[test]
     fn test_num_cast_impl() {
         let h01: Half = NumCastFrom::num_cast_from(10i8).unwrap();
@@ -370,6 +467,22 @@ mod half_tests {
     //     assert!(result.is_err());
     // }
 
+    #[test]
+    fn halfs_can_be_converted_from_and_to_f32_slices() {
+        let floats = vec![0.0f32, 1.0, -2.5, 65504.0];
+        let halfs = Half::from_f32_slice(&floats[..]);
+        assert_eq!(halfs[2], Half::from_f32(-2.5));
+        assert_eq!(halfs[3], Half::max_value());
+        assert_eq!(Half::into_f32_vec(&halfs[..]), floats);
+
+        let mut dst = vec![0.0f32; 3];
+        let err = Half::convert_to_f32_slice(&halfs[..], &mut dst[..]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HalfError>(),
+            Some(HalfError::LengthMismatch(4, 3))
+        ));
+    }
+
     #[test]
     fn test_half_max_is_expected_value() {
         let max_half = Half::max_value();
@@ -1,4 +1,5 @@
 pub mod cl_primitives;
+#[macro_use]
 pub mod cl_vectors;
 
 pub mod scalars;
@@ -29,3 +30,9 @@ pub use as_ptr::*;
 
 pub mod cast;
 pub use cast::*;
+
+pub mod cl_struct;
+pub use cl_struct::*;
+
+pub mod cl_type_name;
+pub use cl_type_name::*;
@@ -1,3 +1,4 @@
+use crate::numbers::cl_type_name_of;
 use crate::{Number, Output};
 use std::any;
 use std::cmp;
@@ -8,6 +9,7 @@ use thiserror::Error;
 pub struct NumberType {
     type_id: any::TypeId,
     type_name: &'static str,
+    cl_type_name: Option<&'static str>,
     size_of: usize,
 }
 
@@ -16,6 +18,7 @@ impl NumberType {
         NumberType {
             type_id: any::TypeId::of::<T>(),
             type_name: any::type_name::<T>(),
+            cl_type_name: cl_type_name_of(any::TypeId::of::<T>()),
             size_of: std::mem::size_of::<T>(),
         }
     }
@@ -28,9 +31,21 @@ impl NumberType {
         self.type_name
     }
 
+    /// The name of the number type in OpenCL C (e.g. `int` or `float4`). None for types
+    /// that are not built-in Numbers (e.g. ClStructs).
+    pub fn number_type_cl_name(&self) -> Option<&'static str> {
+        self.cl_type_name
+    }
+
     pub fn number_type_size_of(&self) -> usize {
         self.size_of
     }
+
+    /// Whether the number type is Half or a Half vector. These require the cl_khr_fp16
+    /// extension on the device.
+    pub fn is_half(&self) -> bool {
+        matches!(self.cl_type_name, Some(name) if name.starts_with("half"))
+    }
 }
 
 impl NumberTyped for NumberType {
@@ -82,6 +97,10 @@ pub trait NumberTypedT {
         Self::number_type().type_name
     }
 
+    fn cl_type_name() -> Option<&'static str> {
+        Self::number_type().cl_type_name
+    }
+
     fn size_of() -> usize {
         Self::number_type().size_of
     }
@@ -110,6 +129,10 @@ pub trait NumberTyped {
         self.number_type().type_name
     }
 
+    fn cl_type_name(&self) -> Option<&'static str> {
+        self.number_type().cl_type_name
+    }
+
     fn size_of(&self) -> usize {
         self.number_type().size_of
     }
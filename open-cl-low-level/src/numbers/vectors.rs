@@ -1,15 +1,19 @@
-use crate::numbers::cl_vectors::{ClVector, ClVector16, ClVector2, ClVector4, ClVector8};
-use crate::numbers::{Char, Double, Float, Int, Long, Short, Uchar, Uint, Ulong, Ushort};
+use crate::numbers::cl_vectors::{
+    ClVector, ClVector16, ClVector2, ClVector3, ClVector4, ClVector8,
+};
+use crate::numbers::{Char, Double, Float, Half, Int, Long, Short, Uchar, Uint, Ulong, Ushort};
 use crate::numbers::{NumCastFrom, NumCastInto, Number, NumberOps, One, Zero};
 use std::fmt;
 use std::mem::zeroed;
 use std::ops::*;
 
+// OpenCL aligns a vector to its size (a 3 component vector takes the size of a 4
+// component vector), so the alignment is given per vector type.
 macro_rules! define_struct {
-    ($scalar:ident, $n:expr) => {
+    ($scalar:ident, $n:expr, $align:literal) => {
         paste::item! {
             #[derive(Clone, Copy)]
-            #[repr(transparent)]
+            #[repr(C, align($align))]
             pub struct [<$scalar $n>]([<ClVector $n>]<$scalar>);
         }
     };
@@ -91,6 +95,115 @@ macro_rules! impl_mul {
     };
 }
 
+macro_rules! impl_sub {
+    ($scalar:ident, $n:expr) => {
+        paste::item! {
+            impl Sub for [<$scalar $n>] {
+                type Output = [<$scalar $n>];
+                #[inline(always)]
+                fn sub(self, other: [<$scalar $n>]) -> [<$scalar $n>] {
+                   [<$scalar $n>](self.0 - other.0)
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_div {
+    ($scalar:ident, $n:expr) => {
+        paste::item! {
+            impl Div for [<$scalar $n>] {
+                type Output = [<$scalar $n>];
+                #[inline(always)]
+                fn div(self, other: [<$scalar $n>]) -> [<$scalar $n>] {
+                   [<$scalar $n>](self.0 / other.0)
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_assign_ops {
+    ($scalar:ident, $n:expr) => {
+        paste::item! {
+            impl AddAssign for [<$scalar $n>] {
+                #[inline(always)]
+                fn add_assign(&mut self, other: [<$scalar $n>]) {
+                    self.0 += other.0;
+                }
+            }
+
+            impl SubAssign for [<$scalar $n>] {
+                #[inline(always)]
+                fn sub_assign(&mut self, other: [<$scalar $n>]) {
+                    self.0 -= other.0;
+                }
+            }
+
+            impl MulAssign for [<$scalar $n>] {
+                #[inline(always)]
+                fn mul_assign(&mut self, other: [<$scalar $n>]) {
+                    self.0 *= other.0;
+                }
+            }
+
+            impl DivAssign for [<$scalar $n>] {
+                #[inline(always)]
+                fn div_assign(&mut self, other: [<$scalar $n>]) {
+                    self.0 /= other.0;
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_from_cl_vector {
+    ($scalar:ident, $n:expr) => {
+        paste::item! {
+            impl From<[<ClVector $n>]<$scalar>> for [<$scalar $n>] {
+                #[inline(always)]
+                fn from(val: [<ClVector $n>]<$scalar>) -> [<$scalar $n>] {
+                    [<$scalar $n>](val)
+                }
+            }
+        }
+    };
+}
+
+// The swizzles (`.xy()`, `.zyx()`), `.lo()`, `.hi()`, `.even()` and `.odd()` of the
+// newtypes return newtypes (e.g. `Float4::xy` is a Float2).
+macro_rules! impl_swizzles_and_halves {
+    ($scalar:ident) => {
+        paste::item! {
+            impl_swizzles!(
+                [impl [<$scalar 2>]],
+                ([<$scalar 2>], [<$scalar 3>], [<$scalar 4>]),
+                [(x, 0), (y, 1)]
+            );
+            impl_swizzles!(
+                [impl [<$scalar 3>]],
+                ([<$scalar 2>], [<$scalar 3>], [<$scalar 4>]),
+                [(x, 0), (y, 1), (z, 2)]
+            );
+            impl_swizzles!(
+                [impl [<$scalar 4>]],
+                ([<$scalar 2>], [<$scalar 3>], [<$scalar 4>]),
+                [(x, 0), (y, 1), (z, 2), (w, 3)]
+            );
+            impl_halves!(@two [impl [<$scalar 2>]], $scalar);
+            impl_halves!(@three [impl [<$scalar 3>]], [<$scalar 2>]);
+            impl_halves!(@four [impl [<$scalar 4>]], [<$scalar 2>]);
+            impl_halves!(@eight [impl [<$scalar 8>]], [<$scalar 4>]);
+            impl_halves!(@sixteen [impl [<$scalar 16>]], [<$scalar 8>]);
+        }
+    };
+    ( $( $scalar:ident ),* ) => {
+        $(
+            impl_swizzles_and_halves!($scalar);
+        )*
+    };
+}
+
 macro_rules! impl_index {
     ($scalar:ident, $n:expr) => {
         paste::item! {
@@ -194,7 +307,7 @@ macro_rules! impl_from {
 
             impl From<[<$scalar $n>]> for [$scalar; $n] {
                 fn from(val: [<$scalar $n>]) -> [$scalar; $n] {
-                    val.into()
+                    val.0.into_array()
                 }
             }
         }
@@ -202,10 +315,12 @@ macro_rules! impl_from {
 }
 
 macro_rules! impl_vector {
-    ($scalar:ident, $n:expr) => {
-        define_struct!($scalar, $n);
+    ($scalar:ident, $n:expr, $align:literal) => {
+        define_struct!($scalar, $n, $align);
         impl_add!($scalar, $n);
+        impl_assign_ops!($scalar, $n);
         impl_debug_and_display!($scalar, $n);
+        impl_div!($scalar, $n);
         impl_default!($scalar, $n);
         impl_index_mut!($scalar, $n);
         impl_index!($scalar, $n);
@@ -215,27 +330,46 @@ macro_rules! impl_vector {
         impl_number!($scalar, $n);
         impl_one!($scalar, $n);
         impl_partial_eq!($scalar, $n);
+        impl_sub!($scalar, $n);
         impl_zero!($scalar, $n);
         impl_from!($scalar, $n);
+        impl_from_cl_vector!($scalar, $n);
         paste::item! {
             impl Eq for [<$scalar $n>] {}
         }
     };
-    ( $( $scalar:ident ),* ) => {
+    (
+        $( $scalar:ident => [$a2:literal, $a3:literal, $a4:literal, $a8:literal, $a16:literal] ),*
+    ) => {
         $(
-            impl_vector!($scalar, 2);
-            impl_vector!($scalar, 4);
-            impl_vector!($scalar, 8);
-            impl_vector!($scalar, 16);
+            impl_vector!($scalar, 2, $a2);
+            impl_vector!($scalar, 3, $a3);
+            impl_vector!($scalar, 4, $a4);
+            impl_vector!($scalar, 8, $a8);
+            impl_vector!($scalar, 16, $a16);
         )*
     };
 }
 
-impl_vector!(Char, Double, Float, Int, Long, Short, Uchar, Uint, Ulong, Ushort);
+impl_vector!(
+    Char => [2, 4, 4, 8, 16],
+    Uchar => [2, 4, 4, 8, 16],
+    Short => [4, 8, 8, 16, 32],
+    Ushort => [4, 8, 8, 16, 32],
+    Half => [4, 8, 8, 16, 32],
+    Int => [8, 16, 16, 32, 64],
+    Uint => [8, 16, 16, 32, 64],
+    Float => [8, 16, 16, 32, 64],
+    Long => [16, 32, 32, 64, 128],
+    Ulong => [16, 32, 32, 64, 128],
+    Double => [16, 32, 32, 64, 128]
+);
+impl_swizzles_and_halves!(Char, Double, Float, Half, Int, Long, Short, Uchar, Uint, Ulong, Ushort);
 
 macro_rules! impl_from_rust_array {
     ($scalar:ident, $rust_t:ident) => {
         impl_from_rust_array!($scalar, $rust_t, 2);
+        impl_from_rust_array!($scalar, $rust_t, 3);
         impl_from_rust_array!($scalar, $rust_t, 4);
         impl_from_rust_array!($scalar, $rust_t, 8);
         impl_from_rust_array!($scalar, $rust_t, 16);
@@ -244,6 +378,10 @@ macro_rules! impl_from_rust_array {
         impl_from_rust_array!($scalar, $rust_t, 2, [0, 1]);
     };
 
+    ($scalar:ident, $rust_t:ident, 3) => {
+        impl_from_rust_array!($scalar, $rust_t, 3, [0, 1, 2]);
+    };
+
     ($scalar:ident, $rust_t:ident, 4) => {
         impl_from_rust_array!($scalar, $rust_t, 4, [0, 1, 2, 3]);
     };
@@ -286,3 +424,4 @@ impl_from_rust_array!(Long, i64);
 impl_from_rust_array!(Ulong, u64);
 impl_from_rust_array!(Float, f32);
 impl_from_rust_array!(Double, f64);
+impl_from_rust_array!(Half, f32);
@@ -7,7 +7,7 @@
 /// Platform is the interface for listing Devices.
 use crate::cl::{
     clGetDeviceIDs, clGetPlatformIDs, clGetPlatformInfo, cl_device_id, cl_device_type,
-    cl_platform_id, cl_platform_info, ClObject, StatusCodeError,
+    cl_platform_id, cl_platform_info, ClError, ClObject,
 };
 use crate::Output;
 use libc::c_void;
@@ -24,17 +24,19 @@ pub unsafe fn list_platform_ids() -> Output<Vec<cl_platform_id>> {
     let platform_lock = PLATFORM_ACCESS.lock();
     // transactional access to the platform Mutex requires a lock for some OpenCL implementations.
     let mut num_platforms: u32 = 0;
-    StatusCodeError::check(clGetPlatformIDs(
-        0,
-        std::ptr::null_mut(),
-        &mut num_platforms,
-    ))?;
+    ClError::check(
+        clGetPlatformIDs(0, std::ptr::null_mut(), &mut num_platforms),
+        "clGetPlatformIDs",
+    )?;
     let mut ids: Vec<cl_platform_id> = vec![cl_platform_id::null_ptr(); num_platforms as usize];
-    StatusCodeError::check(clGetPlatformIDs(
-        num_platforms,
-        ids.as_mut_ptr() as *mut *mut c_void,
-        &mut num_platforms,
-    ))?;
+    ClError::check(
+        clGetPlatformIDs(
+            num_platforms,
+            ids.as_mut_ptr() as *mut *mut c_void,
+            &mut num_platforms,
+        ),
+        "clGetPlatformIDs",
+    )?;
     std::mem::drop(platform_lock);
     Ok(ids)
 }
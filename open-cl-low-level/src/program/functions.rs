@@ -1,30 +1,45 @@
 use crate::cl::{
     clBuildProgram, clCreateProgramWithBinary, clCreateProgramWithSource, clGetProgramBuildInfo,
-    clGetProgramInfo, cl_context, cl_device_id, cl_program, cl_program_build_info, cl_program_info,
-    cl_uint, ClObject, ProgramInfo, StatusCodeError,
+    clGetProgramInfo, cl_build_status, cl_context, cl_device_id, cl_program, cl_program_build_info,
+    cl_program_info, cl_uint, ClError, ClObject, ProgramBuildInfo, ProgramInfo,
 };
 use crate::Output;
 use libc::{c_void, size_t};
-use std::ffi::CString;
+
+#[cfg(feature = "opencl_version_2_1_0")]
+use crate::cl::clCreateProgramWithIL;
+use std::ffi::{CStr, CString};
+
+/// The pfn_notify of clBuildProgram. It is called with the program and the user_data once
+/// the build has completed, successfully or not.
+pub type BuildNotifyFn = extern "C" fn(*mut c_void, *mut c_void);
 
 /// A low-level helper function for calling the OpenCL FFI function clBuildProgram.
 ///
+/// Without a pfn_notify the call blocks until the build has completed. With one it may return
+/// as soon as the build has begun.
+///
 /// # Safety
 /// if the devices or the program are in an invalid state this function call results in
-/// undefined behavior.
-#[allow(clippy::transmuting_null)]
-#[allow(unused_mut)]
-#[inline(always)]
-pub unsafe fn build_program(program: cl_program, device_ids: &[cl_device_id]) -> Output<()> {
+/// undefined behavior. The user_data must be valid for the pfn_notify.
+pub unsafe fn build_program(
+    program: cl_program,
+    device_ids: &[cl_device_id],
+    options: &CStr,
+    pfn_notify: Option<BuildNotifyFn>,
+    user_data: *mut c_void,
+) -> Output<()> {
     let err_code = clBuildProgram(
         program.as_ptr() as *mut libc::c_void,
-        1u32,
+        device_ids.len() as cl_uint,
         device_ids.as_ptr() as *const *mut libc::c_void,
-        std::ptr::null(),
-        std::mem::transmute(std::ptr::null::<fn()>()), // pfn_notify
-        std::ptr::null_mut(),                          // user_data
+        options.as_ptr(),
+        pfn_notify,
+        user_data,
     );
-    StatusCodeError::check(err_code)
+    ClError::check_with(err_code, "clBuildProgram", || {
+        format!("{} devices", device_ids.len())
+    })
 }
 
 /// Low level helper function for clGetProgramBuildInfo.
@@ -40,6 +55,26 @@ pub unsafe fn get_program_build_log(
     cl_get_info!(One, String, clGetProgramBuildInfo, program, device, flag)
 }
 
+/// Low level helper function for clGetProgramBuildInfo with CL_PROGRAM_BUILD_STATUS.
+///
+/// # Safety
+/// If the program or device is in an invalid state this function call is undefined behavior.
+#[inline(always)]
+pub unsafe fn get_program_build_status(
+    program: cl_program,
+    device: cl_device_id,
+) -> Output<cl_build_status> {
+    let flag: cl_program_build_info = ProgramBuildInfo::Status.into();
+    cl_get_info!(
+        One,
+        cl_build_status,
+        clGetProgramBuildInfo,
+        program,
+        device,
+        flag
+    )
+}
+
 /// Low level helper function for calling the OpenCL FFI function clCreateProgramWithSource.
 ///
 /// # Safety
@@ -60,7 +95,7 @@ pub unsafe fn create_program_with_src(context: cl_context, src: CString) -> Outp
         std::ptr::null(),
         &mut err_code,
     );
-    StatusCodeError::check(err_code)?;
+    ClError::check(err_code, "clCreateProgramWithSource")?;
     cl_program::new(raw_program)
 }
 
@@ -87,7 +122,28 @@ pub unsafe fn create_program_with_binary(
         std::ptr::null_mut(),
         &mut status_code,
     );
-    StatusCodeError::check(status_code)?;
+    ClError::check_with(status_code, "clCreateProgramWithBinary", || {
+        format!("{} byte binary", binary.len())
+    })?;
+    cl_program::new(raw_program)
+}
+
+/// Low level helper function for calling the OpenCL FFI function clCreateProgramWithIL.
+///
+/// # Safety
+/// If the context is in an invalid state this function will cause undefined behavior.
+#[cfg(feature = "opencl_version_2_1_0")]
+pub unsafe fn create_program_with_il(context: cl_context, il: &[u8]) -> Output<cl_program> {
+    let mut status_code = 0;
+    let raw_program = clCreateProgramWithIL(
+        context.as_ptr() as *mut c_void,
+        il.as_ptr() as *const c_void,
+        il.len() as size_t,
+        &mut status_code,
+    );
+    ClError::check_with(status_code, "clCreateProgramWithIL", || {
+        format!("{} byte IL", il.len())
+    })?;
     cl_program::new(raw_program)
 }
 
@@ -2,3 +2,6 @@ pub(crate) mod functions;
 
 mod program;
 pub use program::*;
+
+mod pending_build;
+pub use pending_build::{DeviceBuildLog, PendingBuild, ProgramBuildError};
@@ -0,0 +1,234 @@
+use super::{functions, Program, ProgramPtr};
+use crate::cl::{BuildStatus, ProgramBuildInfo};
+use crate::{Device, DevicePtr, ErrorT, HasDeviceInfo};
+use libc::c_void;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::{self, Poll, Waker};
+
+/// The build status and log of a device a program failed to build for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceBuildLog {
+    pub device_name: String,
+    pub status: BuildStatus,
+    pub log: String,
+}
+
+/// An error from an asynchronous program build.
+#[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
+pub enum ProgramBuildError {
+    #[error("Program failed to build for {} device(s):\n{}", .0.len(), format_build_logs(.0))]
+    BuildFailed(Vec<DeviceBuildLog>),
+
+    #[error("Failed to query the result of the program build: {0}")]
+    QueryFailed(String),
+}
+
+fn format_build_logs(logs: &[DeviceBuildLog]) -> String {
+    logs.iter()
+        .map(|l| format!("{} ({:?}):\n{}", l.device_name, l.status, l.log))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[derive(Default)]
+pub(super) struct BuildShared {
+    state: Mutex<BuildState>,
+    completed: Condvar,
+}
+
+#[derive(Default)]
+struct BuildState {
+    done: bool,
+    waker: Option<Waker>,
+}
+
+impl BuildShared {
+    fn lock(&self) -> MutexGuard<'_, BuildState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(super) fn is_done(&self) -> bool {
+        self.lock().done
+    }
+
+    pub(super) fn complete(&self) {
+        let mut state = self.lock();
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        self.completed.notify_all();
+    }
+}
+
+/// The pfn_notify of clBuildProgram. The user_data is an `Arc<BuildShared>` turned into a raw
+/// pointer by `Program::build_async`; the reference is owned by this callback and reclaimed
+/// here. OpenCL calls this once per build, possibly from its own thread. Panics are caught
+/// because unwinding into OpenCL is undefined behavior.
+pub(super) extern "C" fn build_notify(_program: *mut c_void, user_data: *mut c_void) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let shared = unsafe { Arc::from_raw(user_data as *const BuildShared) };
+        shared.complete();
+    }));
+}
+
+/// A program build started by `Program::build_async`. It resolves (via `wait` or as a Future)
+/// once OpenCL calls the build callback, so the builds of several programs run in parallel.
+pub struct PendingBuild {
+    program: Program,
+    devices: Vec<Device>,
+    shared: Arc<BuildShared>,
+}
+
+impl PendingBuild {
+    pub(super) fn new(
+        program: Program,
+        devices: Vec<Device>,
+        shared: Arc<BuildShared>,
+    ) -> PendingBuild {
+        PendingBuild {
+            program,
+            devices,
+            shared,
+        }
+    }
+
+    /// Whether the build has completed, successfully or not.
+    pub fn is_complete(&self) -> bool {
+        self.shared.is_done()
+    }
+
+    /// Blocks until the build has completed.
+    pub fn wait(self) -> Result<Program, ProgramBuildError> {
+        let mut state = self.shared.lock();
+        while !state.done {
+            state = self
+                .shared
+                .completed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        std::mem::drop(state);
+        self.finish()
+    }
+
+    fn finish(&self) -> Result<Program, ProgramBuildError> {
+        let query_failed = |e: anyhow::Error| ProgramBuildError::QueryFailed(format!("{}", e));
+        let mut failures = Vec::new();
+        for device in self.devices.iter() {
+            let status = unsafe {
+                functions::get_program_build_status(self.program.program_ptr(), device.device_ptr())
+            }
+            .map_err(query_failed)?;
+            let status = BuildStatus::from(status);
+            if status == BuildStatus::Success {
+                continue;
+            }
+            let log = unsafe {
+                functions::get_program_build_log(
+                    self.program.program_ptr(),
+                    device.device_ptr(),
+                    ProgramBuildInfo::Log.into(),
+                )
+            }
+            .map_err(query_failed)?;
+            failures.push(DeviceBuildLog {
+                device_name: device.name().unwrap_or_else(|_| device.address()),
+                status,
+                log,
+            });
+        }
+        if failures.is_empty() {
+            Ok(self.program.clone())
+        } else {
+            Err(ProgramBuildError::BuildFailed(failures))
+        }
+    }
+}
+
+impl Future for PendingBuild {
+    type Output = Result<Program, ProgramBuildError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        {
+            let mut state = self.shared.lock();
+            if !state.done {
+                state.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(self.finish())
+    }
+}
+
+/// The program and devices are only used for info queries once the build has completed,
+/// which OpenCL allows from any thread.
+unsafe impl Send for PendingBuild {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn pending_build_resolves_once_the_build_completes() {
+        let src = "__kernel void test(__global int *i) { *i += 1; }";
+        let (context, devices) = ll_testing::get_context();
+        let programs: Vec<Program> = (0..3)
+            .map(|_| unsafe { Program::create_with_src(&context, src) }.unwrap())
+            .collect();
+        let pending: Vec<PendingBuild> = programs
+            .iter()
+            .map(|p| p.build_async(&devices[..], "").unwrap())
+            .collect();
+        for p in pending.into_iter() {
+            let program = p.wait().unwrap();
+            assert_eq!(unsafe { program.kernel_names() }.unwrap(), vec!["test"]);
+        }
+    }
+
+    #[test]
+    fn pending_build_reports_the_build_log_of_failed_devices() {
+        let src = "__kernel void test(__global int *i) { *i += ; }";
+        let (context, devices) = ll_testing::get_context();
+        let program = unsafe { Program::create_with_src(&context, src) }.unwrap();
+        let err = program
+            .build_async(&devices[..], "")
+            .unwrap()
+            .wait()
+            .unwrap_err();
+        match err {
+            ProgramBuildError::BuildFailed(logs) => {
+                assert_eq!(logs.len(), devices.len());
+                for l in logs.iter() {
+                    assert_eq!(l.status, BuildStatus::Error);
+                    assert!(!l.log.is_empty());
+                }
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn build_notify_completes_the_shared_state() {
+        let shared = Arc::new(BuildShared::default());
+        let user_data = Arc::into_raw(shared.clone()) as *mut c_void;
+        build_notify(std::ptr::null_mut(), user_data);
+        assert!(shared.is_done());
+        assert_eq!(Arc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn build_notify_owns_the_user_data_reference() {
+        let shared = Arc::new(BuildShared::default());
+        let weak = Arc::downgrade(&shared);
+        let user_data = Arc::into_raw(shared) as *mut c_void;
+        // Only the callback's reference is left, as after a failed build_async.
+        assert!(weak.upgrade().map_or(false, |s| !s.is_done()));
+        build_notify(std::ptr::null_mut(), user_data);
+        assert!(weak.upgrade().is_none());
+    }
+}
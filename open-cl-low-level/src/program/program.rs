@@ -1,8 +1,13 @@
 use super::functions;
+use super::pending_build::{build_notify, BuildShared, PendingBuild};
 use crate::cl::{
-    cl_device_id, cl_program, strings, ClObject, ObjectWrapper, ProgramBuildInfo, ProgramInfo,
+    cl_device_id, cl_program, strings, ClError, ClObject, ObjectWrapper, ProgramBuildInfo,
+    ProgramInfo,
 };
 use crate::{Context, ContextPtr, Device, DevicePtr, ErrorT, Output};
+use libc::c_void;
+use std::ffi::CString;
+use std::sync::Arc;
 
 /// An error related to Program.
 #[derive(ErrorT, Debug, PartialEq, Eq, Clone)]
@@ -13,10 +18,39 @@ pub enum ProgramError {
     InvalidProgramBinary,
     #[error("Cannot build a program with an empty list of devices")]
     EmptyDevicesList,
+    #[error("The given build options were not a valid CString")]
+    InvalidBuildOptions,
+    #[error("The given IL is not a SPIR-V module: {0}")]
+    InvalidSpirv(String),
 }
 
 use ProgramError::*;
 
+/// The first word of every SPIR-V module.
+pub const SPIRV_MAGIC_NUMBER: u32 = 0x0723_0203;
+
+/// Checks that the bytes start with a SPIR-V module header: the magic number (in either byte
+/// order) followed by the version, generator, bound and schema words.
+pub fn check_spirv(il: &[u8]) -> Output<()> {
+    if il.len() < 20 || il.len() % 4 != 0 {
+        return Err(InvalidSpirv(format!(
+            "expected a whole number of 4 byte words and at least a 20 byte header, got {} bytes",
+            il.len()
+        )))?;
+    }
+    let first_word = [il[0], il[1], il[2], il[3]];
+    let is_spirv = u32::from_le_bytes(first_word) == SPIRV_MAGIC_NUMBER
+        || u32::from_be_bytes(first_word) == SPIRV_MAGIC_NUMBER;
+    if !is_spirv {
+        return Err(InvalidSpirv(format!(
+            "expected the magic number {:#010x}, got {:#010x}",
+            SPIRV_MAGIC_NUMBER,
+            u32::from_le_bytes(first_word)
+        )))?;
+    }
+    Ok(())
+}
+
 pub type Program = ObjectWrapper<cl_program>;
 
 impl Program {
@@ -50,6 +84,18 @@ impl Program {
         Ok(Program::new(prog))
     }
 
+    /// Creates a new Program on the context from an intermediate language (SPIR-V) module.
+    /// The devices' supported IL versions are given by `HasDeviceInfo::il_version`.
+    ///
+    /// # Safety
+    /// The provided Context must be in valid state or else undefined behavior is expected.
+    #[cfg(feature = "opencl_version_2_1_0")]
+    pub unsafe fn create_with_il(context: &Context, il: &[u8]) -> Output<Program> {
+        check_spirv(il)?;
+        let prog = functions::create_program_with_il(context.context_ptr(), il)?;
+        Ok(Program::new(prog))
+    }
+
     pub fn build<D>(&mut self, devices: &[D]) -> Output<()>
     where
         D: DevicePtr,
@@ -59,7 +105,61 @@ impl Program {
         }
         unsafe {
             let device_ptrs: Vec<cl_device_id> = devices.iter().map(|d| d.device_ptr()).collect();
-            functions::build_program(self.program_ptr(), &device_ptrs[..])
+            functions::build_program(
+                self.program_ptr(),
+                &device_ptrs[..],
+                &CString::default(),
+                None,
+                std::ptr::null_mut(),
+            )
+        }
+    }
+
+    /// Starts building the program for the devices with the given build options and returns
+    /// without waiting for the build to complete. The returned PendingBuild resolves when
+    /// OpenCL calls the build callback.
+    ///
+    /// Errors that OpenCL reports before the build begins (e.g. invalid build options) are
+    /// returned here. A failed compilation is reported by the PendingBuild with the build
+    /// logs of the devices.
+    pub fn build_async<D>(&self, devices: &[D], options: &str) -> Output<PendingBuild>
+    where
+        D: DevicePtr,
+    {
+        if devices.is_empty() {
+            return Err(EmptyDevicesList)?;
+        }
+        let options = strings::to_c_string(options).ok_or_else(|| InvalidBuildOptions)?;
+        let shared = Arc::new(BuildShared::default());
+        unsafe {
+            let device_ptrs: Vec<cl_device_id> = devices.iter().map(|d| d.device_ptr()).collect();
+            let user_data = Arc::into_raw(shared.clone()) as *mut c_void;
+            let built = functions::build_program(
+                self.program_ptr(),
+                &device_ptrs[..],
+                &options,
+                Some(build_notify),
+                user_data,
+            );
+            if let Err(e) = built {
+                // The callback owns the user_data reference. OpenCL does not call the callback
+                // of a clBuildProgram call that failed, but some implementations call it
+                // anyway, so the reference is never dropped here; it leaks if the callback is
+                // not called.
+                let is_build_failure = e
+                    .downcast_ref::<ClError>()
+                    .map_or(false, ClError::is_build_failure);
+                if !is_build_failure {
+                    return Err(e);
+                }
+                // The build ran and failed; the PendingBuild reports the build logs.
+                shared.complete();
+            }
+            let devices = device_ptrs
+                .into_iter()
+                .map(|d| Device::retain_new(d))
+                .collect();
+            Ok(PendingBuild::new(self.clone(), devices, shared))
         }
     }
 
@@ -160,6 +260,45 @@ mod tests {
         *i += 1;
     }";
 
+    fn spirv_header(magic: [u8; 4]) -> Vec<u8> {
+        let mut header = magic.to_vec();
+        header.extend_from_slice(&[0u8; 16]);
+        header
+    }
+
+    #[test]
+    fn check_spirv_accepts_the_magic_number_in_either_byte_order() {
+        check_spirv(&spirv_header(SPIRV_MAGIC_NUMBER.to_le_bytes())[..]).unwrap();
+        check_spirv(&spirv_header(SPIRV_MAGIC_NUMBER.to_be_bytes())[..]).unwrap();
+    }
+
+    #[test]
+    fn check_spirv_rejects_non_spirv_bytes() {
+        for il in vec![
+            spirv_header(*b"\x7fELF"),
+            SPIRV_MAGIC_NUMBER.to_le_bytes().to_vec(),
+            vec![0u8; 21],
+        ] {
+            let err = check_spirv(&il[..]).unwrap_err();
+            match err.downcast_ref::<ProgramError>() {
+                Some(ProgramError::InvalidSpirv(_)) => (),
+                e => panic!("Unexpected error {:?}", e),
+            }
+        }
+    }
+
+    #[cfg(feature = "opencl_version_2_1_0")]
+    #[test]
+    fn create_with_il_rejects_non_spirv_bytes_before_calling_opencl() {
+        let (context, _devices) = ll_testing::get_context();
+        let src = SRC.as_bytes();
+        let err = unsafe { Program::create_with_il(&context, src) }.unwrap_err();
+        match err.downcast_ref::<ProgramError>() {
+            Some(ProgramError::InvalidSpirv(_)) => (),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
     #[test]
     fn program_ptr_reference_count() {
         let (prog, _devices, _context) = ll_testing::get_program(SRC);
@@ -4,8 +4,8 @@ use crate::numbers::{Number, NumberTyped, NumberTypedT};
 use crate::vec_or_slice::{MutVecOrSlice, VecOrSlice};
 use crate::{
     BufferBuilder, BufferReadEvent, BuiltContext, CommandQueue, CommandQueueOptions, Context,
-    ContextBuilder, Device, ErrorT, Event, Kernel, KernelOperation, Mem, MemConfig, Output,
-    Platform, Program, Waitlist, Work,
+    ContextBuilder, ContextErrorCallback, ContextProperty, Device, ErrorT, Event, Kernel,
+    KernelOperation, Mem, MemConfig, Output, Platform, Program, Waitlist, Work,
 };
 use std::convert::TryInto;
 use std::marker::PhantomData;
@@ -291,6 +291,9 @@ pub enum SessionBuilderError {
     #[error("For session building either program src or program binaries must be specified.")]
     MustSpecifyProgramSrcOrProgramBinaries,
 
+    #[error("For session building program IL cannot be specified together with program src or binaries; they are mutually exclusive.")]
+    CannotSpecifyProgramIlAndProgramSrcOrBinaries,
+
     #[error("Building a session with program binaries requires exactly 1 device: Got {0} devices")]
     BinaryProgramRequiresExactlyOneDevice(usize),
 }
@@ -301,10 +304,15 @@ use SessionBuilderError::*;
 pub struct SessionBuilder<'a> {
     pub program_src: Option<&'a str>,
     pub program_binaries: Option<&'a [u8]>,
+    #[cfg(feature = "opencl_version_2_1_0")]
+    pub program_il: Option<&'a [u8]>,
     pub device_type: Option<DeviceType>,
     pub platforms: Option<&'a [Platform]>,
     pub devices: Option<&'a [Device]>,
     pub command_queue_properties: Option<CommandQueueProperties>,
+    pub command_queue_size: Option<u32>,
+    pub context_error_callback: Option<ContextErrorCallback>,
+    pub context_properties: Vec<ContextProperty>,
 }
 
 impl<'a> SessionBuilder<'a> {
@@ -312,10 +320,15 @@ impl<'a> SessionBuilder<'a> {
         SessionBuilder {
             program_src: None,
             program_binaries: None,
+            #[cfg(feature = "opencl_version_2_1_0")]
+            program_il: None,
             device_type: None,
             platforms: None,
             devices: None,
             command_queue_properties: None,
+            command_queue_size: None,
+            context_error_callback: None,
+            context_properties: Vec::new(),
         }
     }
 
@@ -329,6 +342,13 @@ impl<'a> SessionBuilder<'a> {
         self
     }
 
+    /// Sets an intermediate language (SPIR-V) module as the session's program.
+    #[cfg(feature = "opencl_version_2_1_0")]
+    pub fn with_program_il(mut self, il: &'a [u8]) -> SessionBuilder<'a> {
+        self.program_il = Some(il);
+        self
+    }
+
     pub fn with_platforms(mut self, platforms: &'a [Platform]) -> SessionBuilder<'a> {
         self.platforms = Some(platforms);
         self
@@ -351,7 +371,42 @@ impl<'a> SessionBuilder<'a> {
         self.command_queue_properties = Some(props);
         self
     }
+
+    /// Sets the size of the session's command queues. Only valid for ON_DEVICE
+    /// command queues (OpenCL v2.0).
+    pub fn with_command_queue_size(mut self, size: u32) -> SessionBuilder<'a> {
+        self.command_queue_size = Some(size);
+        self
+    }
+
+    /// Sets the callback for errors the driver reports in the session's context.
+    pub fn with_context_error_callback(
+        mut self,
+        error_callback: ContextErrorCallback,
+    ) -> SessionBuilder<'a> {
+        self.context_error_callback = Some(error_callback);
+        self
+    }
+
+    /// Adds a property to the session's context (see `ContextBuilder::with_property`).
+    pub fn with_context_property(mut self, property: ContextProperty) -> SessionBuilder<'a> {
+        self.context_properties.push(property);
+        self
+    }
+
     fn check_for_error_state(&self) -> Output<()> {
+        self.command_queue_properties
+            .unwrap_or_default()
+            .validate(self.command_queue_size)?;
+        #[cfg(feature = "opencl_version_2_1_0")]
+        {
+            if self.program_il.is_some() {
+                if self.program_src.is_some() || self.program_binaries.is_some() {
+                    return Err(CannotSpecifyProgramIlAndProgramSrcOrBinaries)?;
+                }
+                return Ok(());
+            }
+        }
         match self {
             Self {
                 program_src: Some(_),
@@ -380,6 +435,8 @@ impl<'a> SessionBuilder<'a> {
             devices: self.devices,
             device_type: self.device_type,
             platforms: self.platforms,
+            error_callback: self.context_error_callback.clone(),
+            properties: self.context_properties.clone(),
         };
         let built_context = context_builder.build()?;
         let (context, devices): (Context, Vec<Device>) = match built_context {
@@ -416,13 +473,26 @@ impl<'a> SessionBuilder<'a> {
                 },
                 n_devices,
             ) => Err(BinaryProgramRequiresExactlyOneDevice(n_devices)),
+            #[cfg(feature = "opencl_version_2_1_0")]
+            (
+                Self {
+                    program_il: Some(il),
+                    ..
+                },
+                _,
+            ) => {
+                let mut prog: Program = Program::create_with_il(&context, *il)?;
+                prog.build(&devices[..])?;
+                Ok(prog)
+            }
             _ => unreachable!(),
         }?;
 
-        let props = CommandQueueProperties::default();
+        let props = self.command_queue_properties.unwrap_or_default();
+        let queue_size = self.command_queue_size;
         let maybe_queues: Output<Vec<CommandQueue>> = devices
             .iter()
-            .map(|dev| CommandQueue::create(&context, dev, Some(props)))
+            .map(|dev| CommandQueue::create_with_size(&context, dev, Some(props), queue_size))
             .collect();
         let queues = maybe_queues?;
 
@@ -438,7 +508,7 @@ impl<'a> SessionBuilder<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{BufferReadEvent, KernelOperation, Session};
+    use crate::{BufferReadEvent, CommandQueueError, KernelOperation, Session, SessionBuilder};
 
     const SRC: &'static str = "__kernel void test(__global int *data) {
         data[get_global_id(0)] += 1;
@@ -476,4 +546,19 @@ mod tests {
             assert_eq!(data4, vec![2, 3, 4, 5, 6]);
         }
     }
+
+    #[test]
+    fn session_builder_rejects_queue_size_without_on_device() {
+        let err = unsafe {
+            SessionBuilder::new()
+                .with_program_src(SRC)
+                .with_command_queue_size(1024)
+                .build()
+        }
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CommandQueueError>(),
+            Some(&CommandQueueError::QueueSizeRequiresOnDevice)
+        );
+    }
 }